@@ -26,7 +26,7 @@ fn main() {
     // Poll and print the output
     while console.is_open() {
         if let Some(output) = console.poll() {
-            println!("{}", output);
+            console.print(output);
         }
     }
 }
@@ -1,19 +1,16 @@
 use encore::*;
 
 fn main() {
-    let console = Console::new(
-        || |input: &str| input.to_string(),
-        |output| {
-            if output == "quit" {
-                None
-            } else {
-                Some(output.to_uppercase())
-            }
-        },
-    );
+    let console = Console::builder()
+        .quit_command("quit")
+        .quit_key(KeyEvent::Ctrl('d'))
+        .build(
+            || |input: &str| input.to_string(),
+            |output| Some(output.to_uppercase()),
+        );
     while console.is_open() {
         if let Some(s) = console.poll() {
-            println!("{}", s);
+            console.print(s);
         }
     }
 }
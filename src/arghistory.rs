@@ -0,0 +1,65 @@
+use std::collections::HashMap;
+
+use crate::{word_start, Candidate, CaseMatching, Completion, Matching, Tokenizer};
+
+/// The most values remembered for each argument
+const MAX_VALUES: usize = 100;
+
+/// The values entered for each argument of each command
+#[derive(Default)]
+pub(crate) struct ArgHistory {
+    /// The values by command and argument position, most recent last
+    values: HashMap<(String, usize), Vec<String>>,
+}
+
+impl ArgHistory {
+    /// Recreate the argument values from the history
+    pub fn from_history(history: &[String], tokenizer: Tokenizer) -> Self {
+        let mut args = ArgHistory::default();
+        for line in history {
+            args.push(line, tokenizer);
+        }
+        args
+    }
+    /// Remember the arguments of a submitted line
+    pub fn push(&mut self, line: &str, tokenizer: Tokenizer) {
+        let words = match tokenizer.split(line) {
+            Ok(words) => words,
+            Err(_) => return,
+        };
+        let (command, args) = match words.split_first() {
+            Some(split) => split,
+            None => return,
+        };
+        for (i, arg) in args.iter().enumerate() {
+            // Values with spaces could not be completed without quoting them
+            if arg.is_empty() || arg.contains(char::is_whitespace) {
+                continue;
+            }
+            let values = self.values.entry((command.clone(), i + 1)).or_default();
+            values.retain(|value| value != arg);
+            values.push(arg.clone());
+            if values.len() > MAX_VALUES {
+                values.remove(0);
+            }
+        }
+    }
+    /// Complete the argument at the cursor from the values entered for it
+    /// before, most recent first
+    pub fn complete(&self, input: &str, cursor: usize, case: CaseMatching) -> Completion {
+        let start = word_start(input, cursor);
+        let mut words = input[..start].split_whitespace();
+        let command = match words.next() {
+            Some(command) => command.to_string(),
+            None => return Completion::default(),
+        };
+        let position = words.count() + 1;
+        let candidates = self
+            .values
+            .get(&(command, position))
+            .into_iter()
+            .flat_map(|values| values.iter().rev())
+            .map(|value| Candidate::new(value.clone()).description("recent"));
+        Completion::from_candidates_with_case(input, cursor, candidates, Matching::Prefix, case)
+    }
+}
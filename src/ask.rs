@@ -0,0 +1,93 @@
+use std::{
+    fmt::Display,
+    str::FromStr,
+    sync::{mpsc, Arc, Mutex},
+};
+
+use crate::{CloseReason, Console, ConsoleBuilder};
+
+impl ConsoleBuilder {
+    /// Read a single line with a console, blocking until it is submitted
+    ///
+    /// The console uses all of the builder's settings, like its prompt,
+    /// completer, and history. Returns `None` if the input ends or the user
+    /// quits.
+    pub fn read_line(self) -> Option<String> {
+        self.read_parsed()
+    }
+    /// Read a single value with a console, blocking until it is submitted
+    ///
+    /// If the input cannot be parsed, the error is printed and the user is
+    /// asked again. Returns `None` if the input ends or the user quits.
+    pub fn read_parsed<T>(self) -> Option<T>
+    where
+        T: FromStr + Send + 'static,
+        T::Err: Display,
+    {
+        self.read_with(|input| input.parse()).ok()
+    }
+    /// Read a single value with a console, parsing it with the given function
+    ///
+    /// If the input cannot be parsed, the error is printed and the user is
+    /// asked again. If the console closes without a value, the reason is
+    /// returned.
+    pub(crate) fn read_with<T, E, P>(mut self, parse: P) -> Result<T, CloseReason>
+    where
+        T: Send + 'static,
+        E: Display,
+        P: Fn(&str) -> Result<T, E> + Send + 'static,
+    {
+        let (reason_send, reason_recv) = mpsc::channel();
+        let on_close = self.on_close.take();
+        self = self.on_close(move |reason| {
+            let _ = reason_send.send(reason.clone());
+            if let Some(on_close) = on_close {
+                on_close(reason);
+            }
+        });
+        let answer = Arc::new(Mutex::new(None));
+        let console_answer = Arc::clone(&answer);
+        let console: Console<()> = self.build(
+            || move |input: &str| parse(input),
+            move |parsed| match parsed {
+                Ok(value) => {
+                    *console_answer.lock().unwrap() = Some(value);
+                    None
+                }
+                Err(e) => {
+                    println!("{}", e);
+                    Some(())
+                }
+            },
+        );
+        // Wait for the console to close, which it does once there is an answer
+        let reason = reason_recv.recv().unwrap_or(CloseReason::AppClosed);
+        drop(console);
+        let answer = answer.lock().unwrap().take();
+        answer.ok_or(reason)
+    }
+}
+
+/// Ask a question and wait for the answer
+///
+/// This is a shorthand for [`ConsoleBuilder::read_line`] with only a prompt.
+/// If the input ends, an empty string is returned.
+pub fn prompt(prompt: &str) -> String {
+    ConsoleBuilder::new()
+        .prompt(prompt)
+        .read_line()
+        .unwrap_or_default()
+}
+
+/// Ask a question and wait for an answer that parses into a value
+///
+/// This is a shorthand for [`ConsoleBuilder::read_parsed`] with only a
+/// prompt. The user is asked again until the answer parses. If the input
+/// ends, `None` is returned.
+pub fn prompt_parse<T>(prompt: &str) -> Option<T>
+where
+    T: FromStr + Send + 'static,
+    T::Err: Display,
+{
+    ConsoleBuilder::new().prompt(prompt).read_parsed()
+}
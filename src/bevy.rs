@@ -0,0 +1,164 @@
+use std::{
+    fmt::{self, Display, Write},
+    sync::{Mutex, MutexGuard},
+};
+
+use bevy_app::{App, AppExit, Plugin, PreUpdate};
+use bevy_ecs::prelude::*;
+use tracing::{
+    field::{Field, Visit},
+    Subscriber,
+};
+use tracing_subscriber::{layer::Context, registry::Registry, Layer};
+
+use crate::{Console, Envelope, Level, Responder};
+
+/// A Bevy plugin that runs a [`Console`] alongside an app
+///
+/// The console is inserted as a [`ConsoleResource`] and its [`Responder`] as
+/// a [`ConsoleResponder`]. At the start of each frame, the console's messages
+/// are sent as [`ConsoleMessage`] events. The app exits once the console
+/// closes.
+///
+/// To print Bevy's logs above the input line instead of over it, pass
+/// [`console_log_layer`] as the `custom_layer` of Bevy's `LogPlugin` and add
+/// this plugin before it.
+pub struct EncorePlugin<M> {
+    console: Mutex<Option<Console<M>>>,
+}
+
+impl<M> EncorePlugin<M> {
+    /// Create a new `EncorePlugin` for a console
+    pub fn new(console: Console<M>) -> Self {
+        EncorePlugin {
+            console: Mutex::new(Some(console)),
+        }
+    }
+}
+
+impl<M> Plugin for EncorePlugin<M>
+where
+    M: Send + Sync + 'static,
+{
+    fn build(&self, app: &mut App) {
+        let console = self
+            .console
+            .lock()
+            .unwrap()
+            .take()
+            .expect("the console plugin was built twice");
+        app.insert_resource(ConsoleResponder(console.responder()))
+            .insert_resource(ConsoleResource {
+                console: Mutex::new(console),
+            })
+            .add_event::<ConsoleMessage<M>>()
+            .add_systems(PreUpdate, drain_console::<M>);
+    }
+}
+
+/// The [`Console`] run by an [`EncorePlugin`]
+#[derive(Resource)]
+pub struct ConsoleResource<M: Send + 'static> {
+    console: Mutex<Console<M>>,
+}
+
+impl<M: Send + 'static> ConsoleResource<M> {
+    /// Lock the console
+    pub fn console(&self) -> MutexGuard<'_, Console<M>> {
+        self.console.lock().unwrap()
+    }
+}
+
+/// The [`Responder`] of the console run by an [`EncorePlugin`]
+#[derive(Resource, Clone)]
+pub struct ConsoleResponder(pub Responder);
+
+/// An event for a message from the console run by an [`EncorePlugin`]
+#[derive(Event)]
+pub struct ConsoleMessage<M: Send + Sync + 'static>(pub Envelope<M>);
+
+/// Send the console's messages as events
+fn drain_console<M>(
+    console: Res<ConsoleResource<M>>,
+    mut messages: EventWriter<ConsoleMessage<M>>,
+    mut exit: EventWriter<AppExit>,
+) where
+    M: Send + Sync + 'static,
+{
+    let console = console.console();
+    while let Some(envelope) = console.poll_envelope() {
+        messages.write(ConsoleMessage(envelope));
+    }
+    if !console.is_open() {
+        exit.write(AppExit::Success);
+    }
+}
+
+/// A tracing layer that prints events with a [`Responder`]
+///
+/// Events are printed above the input line at their matching [`Level`], so
+/// they do not garble the line being edited.
+pub struct ConsoleLogLayer {
+    responder: Responder,
+}
+
+impl ConsoleLogLayer {
+    /// Create a new `ConsoleLogLayer` that prints with the given responder
+    pub fn new(responder: Responder) -> Self {
+        ConsoleLogLayer { responder }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for ConsoleLogLayer {
+    fn on_event(&self, event: &tracing::Event<'_>, _: Context<'_, S>) {
+        let mut message = MessageVisitor::default();
+        event.record(&mut message);
+        let level = match *event.metadata().level() {
+            tracing::Level::ERROR => Level::Error,
+            tracing::Level::WARN => Level::Warn,
+            tracing::Level::INFO => Level::Info,
+            tracing::Level::DEBUG => Level::Debug,
+            tracing::Level::TRACE => Level::Trace,
+        };
+        self.responder.log(level, message);
+    }
+}
+
+/// Create a [`ConsoleLogLayer`] for the console added by an [`EncorePlugin`]
+///
+/// This has the signature of the `custom_layer` of Bevy's `LogPlugin`.
+/// Returns `None` if the plugin has not been added yet.
+pub fn console_log_layer(app: &mut App) -> Option<Box<dyn Layer<Registry> + Send + Sync>> {
+    let responder = app.world().get_resource::<ConsoleResponder>()?;
+    Some(Box::new(ConsoleLogLayer::new(responder.0.clone())))
+}
+
+/// Collects the message and other fields of a tracing event
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{:?}", value);
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = value.into();
+        } else {
+            let _ = write!(self.fields, " {}={}", field.name(), value);
+        }
+    }
+}
+
+impl Display for MessageVisitor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.message, self.fields)
+    }
+}
@@ -0,0 +1,100 @@
+use std::ops::Range;
+
+use crate::{next_char, prev_char};
+
+/// Get the character that closes a bracket or quote
+fn closing(c: char) -> Option<char> {
+    match c {
+        '(' => Some(')'),
+        '[' => Some(']'),
+        '{' => Some('}'),
+        '"' | '\'' | '`' => Some(c),
+        _ => None,
+    }
+}
+
+/// Get the character that opens a closing bracket
+fn opening(c: char) -> Option<char> {
+    match c {
+        ')' => Some('('),
+        ']' => Some('['),
+        '}' => Some('{'),
+        _ => None,
+    }
+}
+
+/// Type a character with its closing bracket or quote
+///
+/// Typing a closing character right before the same one moves over it
+/// instead. Quotes are not paired right after a letter or digit, so that
+/// apostrophes can be typed. Returns the new cursor, or `None` if the
+/// character should be inserted normally.
+pub(crate) fn insert_paired(input: &mut String, cursor: usize, c: char) -> Option<usize> {
+    let next = input[cursor..].chars().next();
+    if next == Some(c) && (opening(c).is_some() || closing(c) == Some(c)) {
+        return Some(cursor + c.len_utf8());
+    }
+    let close = closing(c)?;
+    let prev = input[..cursor].chars().next_back();
+    if close == c && prev.is_some_and(char::is_alphanumeric) {
+        return None;
+    }
+    input.insert(cursor, close);
+    input.insert(cursor, c);
+    Some(cursor + c.len_utf8())
+}
+
+/// Get the byte range of the empty pair of brackets or quotes around the
+/// cursor, if there is one
+pub(crate) fn empty_pair(input: &str, cursor: usize) -> Option<Range<usize>> {
+    let open = input[..cursor].chars().next_back()?;
+    let close = input[cursor..].chars().next()?;
+    if closing(open) != Some(close) {
+        return None;
+    }
+    Some(prev_char(input, cursor)..next_char(input, cursor))
+}
+
+/// Get the byte index of the bracket that matches the one under the cursor,
+/// or the one before the cursor if there is none under it
+pub(crate) fn matching_bracket(input: &str, cursor: usize) -> Option<usize> {
+    let is_bracket = |c: char| opening(c).is_some() || closing(c).is_some_and(|close| close != c);
+    let (index, bracket) = input[cursor..]
+        .chars()
+        .next()
+        .filter(|&c| is_bracket(c))
+        .map(|c| (cursor, c))
+        .or_else(|| {
+            let c = input[..cursor]
+                .chars()
+                .next_back()
+                .filter(|&c| is_bracket(c))?;
+            Some((prev_char(input, cursor), c))
+        })?;
+    let mut depth = 0;
+    if let Some(close) = closing(bracket) {
+        for (i, c) in input[index..].char_indices() {
+            if c == bracket {
+                depth += 1;
+            } else if c == close {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index + i);
+                }
+            }
+        }
+    } else {
+        let open = opening(bracket)?;
+        for (i, c) in input[..=index].char_indices().rev() {
+            if c == bracket {
+                depth += 1;
+            } else if c == open {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+        }
+    }
+    None
+}
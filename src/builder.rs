@@ -0,0 +1,1067 @@
+use std::{collections::HashMap, marker::PhantomData, path::PathBuf, sync::Arc, time::Duration};
+
+use crossterm::KeyEvent;
+
+use crate::{
+    editor::Stepper,
+    plugin::{Extension, Registered},
+    pool::*,
+    spawn::Spawner,
+    Authenticator, CaseMatching, CaseNormalization, CloseReason, CommandProcessor, Completer,
+    Console, ConsolePlugin, EmptyInput, FailedCommands, FileHistory, Hinter, HistoryNavigation,
+    HistoryStore, InputSource, IpFilter, IpRange, Level, LocalConsole, OutputMode, OutputSink,
+    RegistryHandle, Role, ScrollbackLimit, SharedState, TerminalInUse, Theme, Tokenizer,
+};
+
+/// A function called when a shell command exits
+pub(crate) type ShellExitHandler = Box<dyn FnMut(&str, Option<i32>) + Send>;
+
+/// A function that gives the title of the terminal window
+pub(crate) type TitleFn = Box<dyn FnMut() -> String + Send>;
+
+/// A builder for a [`Console`]
+pub struct ConsoleBuilder {
+    pub(crate) mode: OutputMode,
+    pub(crate) prompt: String,
+    pub(crate) completer: Option<Box<dyn Completer + Send>>,
+    pub(crate) inline_help: bool,
+    pub(crate) hinter: Option<Box<dyn Hinter + Send>>,
+    pub(crate) hints: bool,
+    pub(crate) suggest: bool,
+    pub(crate) abbreviations: bool,
+    pub(crate) tokenizer: Tokenizer,
+    pub(crate) expand_paths: bool,
+    pub(crate) expand_vars: bool,
+    pub(crate) vars: HashMap<String, String>,
+    pub(crate) session_scopes: bool,
+    pub(crate) history_expansion: bool,
+    pub(crate) heredocs: bool,
+    pub(crate) arg_history: bool,
+    pub(crate) mouse: bool,
+    pub(crate) builtins: bool,
+    pub(crate) latency_stats: bool,
+    pub(crate) quit_commands: Vec<String>,
+    pub(crate) quit_keys: Vec<KeyEvent>,
+    pub(crate) detach_key: Option<KeyEvent>,
+    pub(crate) paste_key: KeyEvent,
+    pub(crate) on_close: Option<Box<dyn FnOnce(CloseReason) + Send>>,
+    pub(crate) history_navigation: HistoryNavigation,
+    pub(crate) failed_commands: FailedCommands,
+    pub(crate) search_case: CaseMatching,
+    pub(crate) completion_case: Option<CaseMatching>,
+    pub(crate) command_case: CaseMatching,
+    pub(crate) history_store: Option<Box<dyn HistoryStore>>,
+    pub(crate) render_errors: bool,
+    pub(crate) pager: bool,
+    pub(crate) scrollback: Option<ScrollbackLimit>,
+    pub(crate) wrap: Option<usize>,
+    pub(crate) max_frame_rate: Option<u32>,
+    pub(crate) theme: Theme,
+    pub(crate) log_level: Level,
+    pub(crate) shell_commands: bool,
+    pub(crate) on_shell_exit: Option<ShellExitHandler>,
+    pub(crate) plugins: Vec<Box<dyn Extension>>,
+    pub(crate) definitions: Option<PathBuf>,
+    pub(crate) input_sources: Vec<Box<dyn InputSource>>,
+    pub(crate) output_sinks: Vec<Box<dyn OutputSink>>,
+    pub(crate) ip_filter: IpFilter,
+    pub(crate) authenticator: Option<Authenticator>,
+    pub(crate) session_role: Role,
+    pub(crate) restricted_commands: Vec<String>,
+    pub(crate) trim_input: bool,
+    pub(crate) empty_input: EmptyInput,
+    pub(crate) case: CaseNormalization,
+    pub(crate) mask_input: bool,
+    pub(crate) mask_char: char,
+    pub(crate) reveal_last_char: bool,
+    pub(crate) max_input: Option<usize>,
+    pub(crate) stepper: Option<Stepper>,
+    pub(crate) command_timeout: Option<Duration>,
+    pub(crate) command_timeouts: HashMap<String, Duration>,
+    pub(crate) cancel_on_timeout: bool,
+    pub(crate) pending_indicator: bool,
+    pub(crate) auto_pair: bool,
+    pub(crate) match_brackets: bool,
+    pub(crate) title: Option<TitleFn>,
+    #[cfg(feature = "notifications")]
+    pub(crate) notify: Option<crate::NotifyFilter>,
+    pub(crate) spawner: Spawner,
+    #[cfg(feature = "scripting")]
+    pub(crate) scripting: bool,
+    #[cfg(feature = "scripting")]
+    pub(crate) scripts: Vec<String>,
+}
+
+impl Default for ConsoleBuilder {
+    fn default() -> Self {
+        ConsoleBuilder {
+            mode: OutputMode::default(),
+            prompt: String::new(),
+            completer: None,
+            inline_help: false,
+            hinter: None,
+            hints: false,
+            suggest: false,
+            abbreviations: false,
+            tokenizer: Tokenizer::default(),
+            expand_paths: false,
+            expand_vars: false,
+            vars: HashMap::new(),
+            session_scopes: false,
+            history_expansion: false,
+            heredocs: false,
+            arg_history: false,
+            mouse: false,
+            builtins: false,
+            latency_stats: false,
+            quit_commands: Vec::new(),
+            quit_keys: Vec::new(),
+            detach_key: None,
+            paste_key: KeyEvent::Ctrl('v'),
+            on_close: None,
+            history_navigation: HistoryNavigation::default(),
+            failed_commands: FailedCommands::default(),
+            search_case: CaseMatching::Insensitive,
+            completion_case: None,
+            command_case: CaseMatching::Sensitive,
+            history_store: None,
+            render_errors: false,
+            pager: false,
+            scrollback: None,
+            wrap: None,
+            max_frame_rate: None,
+            theme: Theme::default(),
+            log_level: Level::default(),
+            shell_commands: false,
+            on_shell_exit: None,
+            plugins: Vec::new(),
+            definitions: None,
+            input_sources: Vec::new(),
+            output_sinks: Vec::new(),
+            ip_filter: IpFilter::default(),
+            authenticator: None,
+            session_role: Role::Restricted,
+            restricted_commands: Vec::new(),
+            trim_input: true,
+            empty_input: EmptyInput::default(),
+            case: CaseNormalization::default(),
+            mask_input: false,
+            mask_char: '*',
+            reveal_last_char: false,
+            max_input: None,
+            stepper: None,
+            command_timeout: None,
+            command_timeouts: HashMap::new(),
+            cancel_on_timeout: false,
+            pending_indicator: false,
+            auto_pair: false,
+            match_brackets: false,
+            title: None,
+            #[cfg(feature = "notifications")]
+            notify: None,
+            spawner: Spawner::default(),
+            #[cfg(feature = "scripting")]
+            scripting: false,
+            #[cfg(feature = "scripting")]
+            scripts: Vec::new(),
+        }
+    }
+}
+
+impl ConsoleBuilder {
+    /// Create a new `ConsoleBuilder` with the default settings
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Set the way output is formatted
+    pub fn output_mode(mut self, mode: OutputMode) -> Self {
+        self.mode = mode;
+        self
+    }
+    /// Set the prompt shown before the input line
+    pub fn prompt<S: Into<String>>(mut self, prompt: S) -> Self {
+        self.prompt = prompt.into();
+        self
+    }
+    /// Set the [`Completer`] used when Tab is pressed
+    ///
+    /// If no completer is set, the processor's own
+    /// [`complete`](CommandProcessor::complete) method is used.
+    pub fn completer<C>(mut self, completer: C) -> Self
+    where
+        C: Completer + Send + 'static,
+    {
+        self.completer = Some(Box::new(completer));
+        self
+    }
+    /// Set whether typing `?` at the end of the input shows help for what
+    /// can come next
+    ///
+    /// The help is printed above the input line and the input is left as it
+    /// was. This is disabled by default.
+    pub fn inline_help(mut self, inline_help: bool) -> Self {
+        self.inline_help = inline_help;
+        self
+    }
+    /// Set whether a hint about the expected arguments is shown below the
+    /// input line while typing
+    ///
+    /// This is disabled by default.
+    pub fn hints(mut self, hints: bool) -> Self {
+        self.hints = hints;
+        self
+    }
+    /// Set the [`Hinter`] used to show hints while typing
+    ///
+    /// This also enables hints. If no hinter is set, the processor's own
+    /// [`hint`](CommandProcessor::hint) method is used.
+    pub fn hinter<H>(mut self, hinter: H) -> Self
+    where
+        H: Hinter + Send + 'static,
+    {
+        self.hinter = Some(Box::new(hinter));
+        self.hints = true;
+        self
+    }
+    /// Set whether similar commands are suggested when an unrecognized
+    /// command is entered
+    ///
+    /// Clap `App`s already suggest commands in their errors, so this is for
+    /// other [`CommandProcessor`]s. This is disabled by default.
+    pub fn suggestions(mut self, suggest: bool) -> Self {
+        self.suggest = suggest;
+        self
+    }
+    /// Set whether commands can be abbreviated
+    ///
+    /// If enabled, a command that is an unambiguous prefix of exactly one of
+    /// the processor's [`commands`](CommandProcessor::commands) is expanded
+    /// before parsing. If it is a prefix of several commands, they are listed
+    /// and the input is not processed. This is disabled by default.
+    pub fn abbreviations(mut self, abbreviations: bool) -> Self {
+        self.abbreviations = abbreviations;
+        self
+    }
+    /// Set the way input is split into arguments
+    ///
+    /// This is passed to processors in the [`Context`](crate::Context) and
+    /// is used when parsing with a clap `App`.
+    pub fn tokenizer(mut self, tokenizer: Tokenizer) -> Self {
+        self.tokenizer = tokenizer;
+        self
+    }
+    /// Set whether `~` and glob patterns like `*.sav` are expanded into
+    /// paths before parsing
+    ///
+    /// See [`expand_paths`](crate::expand_paths) for details. This is
+    /// disabled by default.
+    pub fn expand_paths(mut self, expand_paths: bool) -> Self {
+        self.expand_paths = expand_paths;
+        self
+    }
+    /// Set whether `$VAR` and `${VAR}` references are expanded before parsing
+    ///
+    /// Variables set with [`ConsoleBuilder::var`] or
+    /// [`Console::set_var`] take precedence over environment variables.
+    /// Environment variables are only expanded in input from the terminal or
+    /// the console itself, so that remote sessions cannot read them. See
+    /// [`expand_vars`](crate::expand_vars) for details. This is disabled by
+    /// default.
+    pub fn expand_vars(mut self, expand_vars: bool) -> Self {
+        self.expand_vars = expand_vars;
+        self
+    }
+    /// Set a global console variable
+    pub fn var<K, V>(mut self, name: K, value: V) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.vars.insert(name.into(), value.into());
+        self
+    }
+    /// Set whether each session gets its own variables and aliases
+    ///
+    /// When enabled, the `set` and `alias` [built-ins](ConsoleBuilder::builtins)
+    /// only apply to the [origin](crate::Origin) that runs them unless `-g`
+    /// is given, so two people connected to the console do not change each
+    /// other's `$target`. The app can use a [`Scope`](crate::Scope) with
+    /// [`Console::set_scoped_var`] and [`Console::push_scoped_mode`]
+    /// either way. This is disabled by default.
+    pub fn session_scopes(mut self, session_scopes: bool) -> Self {
+        self.session_scopes = session_scopes;
+        self
+    }
+    /// Set whether `!!` and `!N` are expanded to history entries
+    ///
+    /// The expanded input is printed before it is processed and is stored in
+    /// the history in its expanded form. See
+    /// [`expand_history`](crate::expand_history) for details. This is
+    /// disabled by default.
+    pub fn history_expansion(mut self, history_expansion: bool) -> Self {
+        self.history_expansion = history_expansion;
+        self
+    }
+    /// Set whether an input ending in `<<TERM` starts capturing a block of
+    /// lines
+    ///
+    /// Each following line is kept verbatim until one that is just `TERM`.
+    /// The command is then processed with the lines of the block after it,
+    /// separated by newlines, so a script can be pasted as one input. This is
+    /// disabled by default.
+    pub fn heredocs(mut self, heredocs: bool) -> Self {
+        self.heredocs = heredocs;
+        self
+    }
+    /// Set whether the values entered for command arguments are remembered
+    ///
+    /// When an argument is completed, the values entered before for the same
+    /// argument of the same command are offered first, most recent first,
+    /// followed by the usual completions. For example, after `load castle`,
+    /// completing `load c` offers `castle`. The values are recreated from
+    /// the history when the console starts. This is disabled by default.
+    pub fn argument_history(mut self, argument_history: bool) -> Self {
+        self.arg_history = argument_history;
+        self
+    }
+    /// Set whether mouse input is captured
+    ///
+    /// If enabled, clicking the input line moves the cursor to the clicked
+    /// column, and the scroll wheel works like the up and down arrow keys,
+    /// going through the history or scrolling the pager. Clicks on other
+    /// rows are ignored. This is disabled by default.
+    pub fn mouse(mut self, mouse: bool) -> Self {
+        self.mouse = mouse;
+        self
+    }
+    /// Set whether the console handles its built-in commands
+    ///
+    /// Built-in commands are handled by the console and are never passed to
+    /// the processor. They are:
+    /// - `clear`: Clear the screen
+    /// - `loglevel [LEVEL]`: Show or set the [log level](ConsoleBuilder::log_level)
+    /// - `repeat COUNT [--delay SECONDS] COMMAND`: Run a command a number of
+    ///   times, optionally waiting between runs. Ctrl-C cancels the
+    ///   remaining runs.
+    /// - `watch SECONDS COMMAND`: Run a command every few seconds, showing
+    ///   the latest run at the top of the screen, until a key is pressed
+    /// - `at TIME COMMAND`: Schedule a command to run at a UTC time like
+    ///   `14:30`, or after a delay like `+5m`
+    /// - `every INTERVAL COMMAND`: Schedule a command to run on an interval
+    ///   like `30s` or `1h`
+    /// - `jobs`: List the scheduled commands
+    /// - `cancel ID`: Cancel a scheduled command
+    /// - `record NAME`: Start recording the commands that are entered as a
+    ///   macro
+    /// - `stop`: Finish recording a macro
+    /// - `play NAME`: Run the commands of a macro
+    /// - `latency [reset]`: Show or reset the latencies measured if
+    ///   [latency stats](ConsoleBuilder::latency_stats) are enabled
+    /// - `set [-g] NAME VALUE`: Set a console variable
+    /// - `unset [-g] NAME`: Remove a console variable
+    /// - `alias [-g] NAME = COMMAND`: Define an alias
+    /// - `unalias [-g] NAME`: Remove an alias
+    ///
+    /// With [session scopes](ConsoleBuilder::session_scopes), `-g` makes
+    /// variables and aliases global instead of only for the session.
+    ///
+    /// Macros are recreated from the history when the console starts, so they
+    /// persist if a [history store](ConsoleBuilder::history_store) is set.
+    ///
+    /// This is disabled by default, since the names of the built-ins are
+    /// common command names that the processor may want to handle itself.
+    pub fn builtins(mut self, builtins: bool) -> Self {
+        self.builtins = builtins;
+        self
+    }
+    /// Set whether the console measures its latencies
+    ///
+    /// The time from when a key is received until the line is redrawn, and
+    /// from when a command is submitted until the app polls its message, are
+    /// measured for the most recent 1000 keys and commands. They are shown
+    /// by the `latency` [built-in](ConsoleBuilder::builtins), which is useful
+    /// for seeing how much redrawing costs over a slow connection. This is
+    /// disabled by default.
+    pub fn latency_stats(mut self, latency_stats: bool) -> Self {
+        self.latency_stats = latency_stats;
+        self
+    }
+    /// Add a command that closes the console when entered
+    ///
+    /// Quit commands are handled by the console and are never passed to the
+    /// processor. If a quit message was set with
+    /// [`Console::set_quit_message`], it is sent when the console closes.
+    /// While in a nested mode, `exit` leaves the mode even if it is a quit
+    /// command.
+    pub fn quit_command<S: Into<String>>(mut self, command: S) -> Self {
+        self.quit_commands.push(command.into());
+        self
+    }
+    /// Add a key that closes the console when pressed on an empty line
+    ///
+    /// For example, `KeyEvent::Ctrl('d')` closes the console like an
+    /// end-of-file does in a shell.
+    pub fn quit_key(mut self, key: KeyEvent) -> Self {
+        self.quit_keys.push(key);
+        self
+    }
+    /// Set a key that detaches the terminal from the console
+    ///
+    /// While detached, the console keeps running commands from its other
+    /// sources, but nothing is drawn on the terminal and every key except
+    /// this one is ignored. Pressing it again re-attaches the terminal and
+    /// replays the output that was missed from the
+    /// [scrollback](ConsoleBuilder::scrollback), which this enables. No key
+    /// detaches the terminal by default.
+    pub fn detach_key(mut self, key: KeyEvent) -> Self {
+        self.detach_key = Some(key);
+        self.scrollback.get_or_insert_with(ScrollbackLimit::default);
+        self
+    }
+    /// Set the key that pastes the first line of the clipboard
+    ///
+    /// This only does anything with the `clipboard` feature. The default is
+    /// Ctrl-V. Terminals send the same key for Ctrl-Shift-V, so it can't be
+    /// bound on its own, and many terminals paste with it themselves.
+    pub fn paste_key(mut self, key: KeyEvent) -> Self {
+        self.paste_key = key;
+        self
+    }
+    /// Set a function that is called when the console closes
+    ///
+    /// The function is called on the console thread with the reason the
+    /// console closed.
+    pub fn on_close<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce(CloseReason) + Send + 'static,
+    {
+        self.on_close = Some(Box::new(f));
+        self
+    }
+    /// Set the way Up and Down move through the history
+    pub fn history_navigation(mut self, navigation: HistoryNavigation) -> Self {
+        self.history_navigation = navigation;
+        self
+    }
+    /// Set the way commands that failed are treated in the history
+    ///
+    /// Commands are only known to fail if the app reports them with
+    /// [`Console::report_outcome`] or if they are shell commands. Failed
+    /// commands are treated like any other by default.
+    pub fn failed_commands(mut self, failed: FailedCommands) -> Self {
+        self.failed_commands = failed;
+        self
+    }
+    /// Set how letter case is matched when searching the history with
+    /// Ctrl-R
+    ///
+    /// Case is ignored by default.
+    pub fn search_case(mut self, case: CaseMatching) -> Self {
+        self.search_case = case;
+        self
+    }
+    /// Set how letter case is matched when completing the word at the cursor
+    ///
+    /// The case is passed to the [completer](ConsoleBuilder::completer) with
+    /// [`Completer::complete_with_case`], which the completers in this crate
+    /// use, and also applies to the [argument history](ConsoleBuilder::argument_history).
+    /// By default, prefix matching is case-sensitive and fuzzy matching
+    /// ignores case.
+    pub fn completion_case(mut self, case: CaseMatching) -> Self {
+        self.completion_case = Some(case);
+        self
+    }
+    /// Set how letter case is matched when looking up commands
+    ///
+    /// This applies to quit commands, [abbreviations](ConsoleBuilder::abbreviations),
+    /// [restricted commands](ConsoleBuilder::restricted_commands), and the
+    /// processor's [commands](CommandProcessor::commands). A command typed in
+    /// another case is replaced with the processor's command before it is
+    /// parsed, so `LOAD map` runs `load map`. Case must match by default.
+    pub fn command_case(mut self, case: CaseMatching) -> Self {
+        self.command_case = case;
+        self
+    }
+    /// Set the [`HistoryStore`] used to persist the history
+    pub fn history_store<S>(mut self, store: S) -> Self
+    where
+        S: HistoryStore + 'static,
+    {
+        self.history_store = Some(Box::new(store));
+        self
+    }
+    /// Persist the history in a [`FileHistory`] at the given path
+    pub fn history_file<P: Into<PathBuf>>(self, path: P) -> Self {
+        self.history_store(FileHistory::new(path))
+    }
+    /// Set whether the console prints parse errors itself
+    ///
+    /// If enabled, inputs that fail to parse with an error that the
+    /// processor can [render](CommandProcessor::render_error) have the error
+    /// printed above the input line, and the parsed value is not passed to
+    /// the process function. Errors from a clap `App` are colored and
+    /// wrapped to the terminal width. This is disabled by default.
+    pub fn render_errors(mut self, render_errors: bool) -> Self {
+        self.render_errors = render_errors;
+        self
+    }
+    /// Set whether output that is too long to fit on the screen is shown in
+    /// a pager
+    ///
+    /// The pager is navigated with Space, `b`, and the arrow keys like
+    /// `less`. `/` searches, `n` and `N` jump between matches, and `q` closes
+    /// it. Output printed while the pager is open is added to the end of it.
+    /// This only applies to [`OutputMode::Interactive`] and is disabled by
+    /// default.
+    pub fn pager(mut self, pager: bool) -> Self {
+        self.pager = pager;
+        self
+    }
+    /// Set whether output is kept so that it can be scrolled back through
+    /// and searched
+    ///
+    /// If enabled, Page Up opens the output printed through the
+    /// [`Responder`](crate::Responder) in the [pager](ConsoleBuilder::pager),
+    /// and Ctrl-F opens it with a search toward older output. Matches are
+    /// highlighted, and `n` and `N` jump between them. In the pager, `v`
+    /// starts marking a range of lines, which can then be copied to the
+    /// clipboard with `y` or saved to a file with `w`. The scrollback keeps
+    /// output up to the default [`ScrollbackLimit`]. This only applies to
+    /// [`OutputMode::Interactive`] and is disabled by default.
+    pub fn scrollback(mut self, scrollback: bool) -> Self {
+        self.scrollback = scrollback.then(ScrollbackLimit::default);
+        self
+    }
+    /// Enable the [scrollback](ConsoleBuilder::scrollback) with the given
+    /// limits on how much output it keeps
+    pub fn scrollback_limit(mut self, limit: ScrollbackLimit) -> Self {
+        self.scrollback = Some(limit);
+        self
+    }
+    /// Set whether output lines that are wider than the terminal are wrapped
+    /// at word boundaries
+    ///
+    /// When the terminal is resized, the screen is cleared and the most
+    /// recent output is printed again, wrapped to the new width. This only
+    /// applies to [`OutputMode::Interactive`] and is disabled by default.
+    pub fn word_wrap(mut self, word_wrap: bool) -> Self {
+        self.wrap = word_wrap.then_some(self.wrap.unwrap_or(0));
+        self
+    }
+    /// Wrap output lines with the given hanging indent
+    ///
+    /// This also enables [word wrapping](ConsoleBuilder::word_wrap). Rows
+    /// after the first row of each wrapped line are indented by the given
+    /// number of spaces.
+    pub fn hanging_indent(mut self, indent: usize) -> Self {
+        self.wrap = Some(indent);
+        self
+    }
+    /// Limit how many times per second output is drawn
+    ///
+    /// Output printed between frames is batched and printed together, so an
+    /// app that prints hundreds of lines per second does not redraw the
+    /// input line for each of them and the terminal stays responsive. This
+    /// only applies to [`OutputMode::Interactive`]. Output is drawn as soon
+    /// as it is printed by default.
+    pub fn max_frame_rate(mut self, frames_per_second: u32) -> Self {
+        self.max_frame_rate = Some(frames_per_second);
+        self
+    }
+    /// Set whether input that starts with `!` is run in the system shell
+    ///
+    /// The command is run with `sh -c`, or `cmd /C` on Windows, and has the
+    /// terminal until it exits. Its exit code is stored with its history
+    /// entry and passed to the function set with
+    /// [`ConsoleBuilder::on_shell_exit`]. This is disabled by default.
+    pub fn shell_commands(mut self, shell_commands: bool) -> Self {
+        self.shell_commands = shell_commands;
+        self
+    }
+    /// Set a function that is called when a command run in the system shell
+    /// exits
+    ///
+    /// This also enables [shell commands](ConsoleBuilder::shell_commands).
+    /// The function is called on the console thread with the command and
+    /// its exit code, which is `None` if it could not be run or was
+    /// terminated by a signal.
+    pub fn on_shell_exit<F>(mut self, f: F) -> Self
+    where
+        F: FnMut(&str, Option<i32>) + Send + 'static,
+    {
+        self.on_shell_exit = Some(Box::new(f));
+        self.shell_commands = true;
+        self
+    }
+    /// Set the colors used for output
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+    /// Set the least severe level of messages that are printed with
+    /// [`Console::log`]
+    ///
+    /// This is [`Level::Info`] by default.
+    pub fn log_level(mut self, level: Level) -> Self {
+        self.log_level = level;
+        self
+    }
+    /// Add a plugin
+    ///
+    /// The plugin's commands are registered with the
+    /// [`CommandRegistry`](crate::CommandRegistry) that the given handle
+    /// belongs to. Its completions, highlighting, and output filters are used
+    /// by the console.
+    pub fn plugin<T, P>(mut self, mut plugin: P, commands: &RegistryHandle<T>) -> Self
+    where
+        T: 'static,
+        P: ConsolePlugin<T> + 'static,
+    {
+        plugin.register(commands);
+        self.plugins.push(Box::new(Registered {
+            plugin,
+            commands: PhantomData,
+        }));
+        self
+    }
+    /// Set whether commands can be defined in [Rhai](https://rhai.rs) scripts
+    ///
+    /// An input like `def greet(name) { print("hi " + name) }` defines a
+    /// function that can then be run as a command like `greet world`. The
+    /// arguments are passed to the function as strings. Functions can call
+    /// `print` to print output and `run` to run another command, such as one
+    /// for the processor. This is disabled by default.
+    #[cfg(feature = "scripting")]
+    pub fn scripting(mut self, scripting: bool) -> Self {
+        self.scripting = scripting;
+        self
+    }
+    /// Define commands in some Rhai source when the console starts
+    ///
+    /// The source's functions are defined as though each was entered with
+    /// `def`. This also enables [scripting](ConsoleBuilder::scripting).
+    #[cfg(feature = "scripting")]
+    pub fn script<S: Into<String>>(mut self, source: S) -> Self {
+        self.scripts.push(source.into());
+        self.scripting = true;
+        self
+    }
+    /// Load aliases, key bindings, and scripted commands from a file
+    ///
+    /// The file is loaded when the console starts and reloaded whenever it
+    /// changes. Each line of the file is one of:
+    /// - `alias NAME = COMMAND`: Replace the command `NAME` at the start of
+    ///   an input with `COMMAND`
+    /// - `bind KEY COMMAND`: Run a command when a key like `ctrl-t`, `alt-x`,
+    ///   or `f5` is pressed
+    /// - `def NAME(PARAMS) { BODY }`: Define a
+    ///   [scripted](ConsoleBuilder::scripting) command. The body may span
+    ///   several lines.
+    ///
+    /// Blank lines and lines starting with `#` are ignored.
+    pub fn definitions<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.definitions = Some(path.into());
+        self
+    }
+    /// Add a source of input
+    ///
+    /// Input from every source is handled by the same console. If no sources
+    /// are added, the console reads from the terminal with a
+    /// [`TerminalSource`](crate::TerminalSource).
+    pub fn input_source<S>(mut self, source: S) -> Self
+    where
+        S: InputSource + 'static,
+    {
+        self.input_sources.push(Box::new(source));
+        self
+    }
+    /// Add a sink that receives a copy of the output
+    ///
+    /// Output is still shown on the terminal. Each sink can keep or remove
+    /// ANSI escape codes, so the same output can be written to a transcript
+    /// file as plain text.
+    pub fn output_sink<S>(mut self, sink: S) -> Self
+    where
+        S: OutputSink + 'static,
+    {
+        self.output_sinks.push(Box::new(sink));
+        self
+    }
+    /// Allow connections from a range of addresses
+    ///
+    /// Once any range is allowed, connections accepted with
+    /// [`Console::accept_tcp`] from addresses outside of every allowed range
+    /// are closed right away and reported as
+    /// [`SessionEvent::Rejected`](crate::SessionEvent::Rejected). By default,
+    /// every address is allowed.
+    pub fn allow_ip(mut self, range: IpRange) -> Self {
+        self.ip_filter.allow.push(range);
+        self
+    }
+    /// Deny connections from a range of addresses
+    ///
+    /// Denied ranges take precedence over [allowed](ConsoleBuilder::allow_ip)
+    /// ones, so a range can be allowed except for some addresses in it. By
+    /// default, no addresses are denied.
+    pub fn deny_ip(mut self, range: IpRange) -> Self {
+        self.ip_filter.deny.push(range);
+        self
+    }
+    /// Require remote sessions to log in
+    ///
+    /// Each connection accepted with [`Console::accept_tcp`] or
+    /// [`Console::accept_unix`] is asked for a user name and a password,
+    /// which are passed to the given function. A connection whose login is
+    /// rejected is closed and reported as
+    /// [`SessionEvent::AuthFailed`](crate::SessionEvent::AuthFailed). The
+    /// password is sent as plain text, so it should only be used on trusted
+    /// networks or through a tunnel. By default, sessions do not log in.
+    pub fn authenticate<F>(mut self, check: F) -> Self
+    where
+        F: Fn(&str, &str) -> bool + Send + Sync + 'static,
+    {
+        self.authenticator = Some(Arc::new(check));
+        self
+    }
+    /// Set what the commands from remote sessions are allowed to do
+    ///
+    /// This applies to the connections accepted with [`Console::accept_tcp`]
+    /// and [`Console::accept_unix`]. By default, sessions have the
+    /// [`Role::Restricted`] role, so they can only run the
+    /// [restricted commands](ConsoleBuilder::restricted_commands).
+    pub fn session_role(mut self, role: Role) -> Self {
+        self.session_role = role;
+        self
+    }
+    /// Limit the commands that input with the [`Role::Restricted`](crate::Role::Restricted)
+    /// role may run
+    ///
+    /// Restricted input may only run the processor commands with the given
+    /// names. By default, it may not run any commands, so sources like
+    /// [chat adapters](crate::ChatAdapter) can do nothing until their
+    /// commands are allowed.
+    pub fn restricted_commands<I>(mut self, commands: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.restricted_commands = commands.into_iter().map(Into::into).collect();
+        self
+    }
+    /// Set whether whitespace is removed from both ends of submitted input
+    ///
+    /// Disabling this lets commands depend on exact spacing. This is enabled
+    /// by default.
+    pub fn trim_input(mut self, trim: bool) -> Self {
+        self.trim_input = trim;
+        self
+    }
+    /// Set whether empty input is ignored instead of being submitted
+    ///
+    /// Input with only whitespace counts as empty. Ignored input is not added
+    /// to the history. This is the same as setting
+    /// [`ConsoleBuilder::empty_input`] to [`EmptyInput::Ignore`] or
+    /// [`EmptyInput::Submit`]. This is disabled by default.
+    pub fn skip_empty_lines(mut self, skip: bool) -> Self {
+        self.empty_input = if skip {
+            EmptyInput::Ignore
+        } else {
+            EmptyInput::Submit
+        };
+        self
+    }
+    /// Set what happens when empty input is submitted
+    ///
+    /// For example, [`EmptyInput::RepeatLast`] makes pressing Enter on an
+    /// empty line run the previous command again, like in gdb. This is
+    /// [`EmptyInput::Submit`] by default.
+    pub fn empty_input(mut self, empty_input: EmptyInput) -> Self {
+        self.empty_input = empty_input;
+        self
+    }
+    /// Set the way the letter case of submitted input is changed
+    ///
+    /// This is [`CaseNormalization::Preserve`] by default.
+    pub fn case_normalization(mut self, case: CaseNormalization) -> Self {
+        self.case = case;
+        self
+    }
+    /// Set whether the input is hidden by drawing each character as `*`
+    ///
+    /// This is useful for passwords. Masked input is not added to the
+    /// history. This is disabled by default.
+    pub fn mask_input(mut self, mask: bool) -> Self {
+        self.mask_input = mask;
+        self
+    }
+    /// Set the character drawn in place of each character of masked input
+    ///
+    /// This is `*` by default.
+    pub fn mask_char(mut self, c: char) -> Self {
+        self.mask_char = c;
+        self
+    }
+    /// Set whether the last character typed in masked input is shown
+    /// briefly before it is masked, like on a phone
+    ///
+    /// This is disabled by default.
+    pub fn reveal_last_char(mut self, reveal: bool) -> Self {
+        self.reveal_last_char = reveal;
+        self
+    }
+    /// Set the most characters that can be typed in the input
+    ///
+    /// This is useful when commands are relayed over a protocol that limits
+    /// their length. Typing past the limit rings the bell, and the length of
+    /// the input is shown before the prompt, like `[128/200]> `. There is no
+    /// limit by default.
+    pub fn max_input_length(mut self, max: usize) -> Self {
+        self.max_input = Some(max);
+        self
+    }
+    /// Set how long the app has to respond to a command
+    ///
+    /// If nothing has been printed and the command's message is still held
+    /// by the app once the time has passed, a notice is printed. This is
+    /// disabled by default.
+    pub fn command_timeout(mut self, timeout: Duration) -> Self {
+        self.command_timeout = Some(timeout);
+        self
+    }
+    /// Set how long the app has to respond to a specific command
+    ///
+    /// This overrides [`ConsoleBuilder::command_timeout`] for the command
+    /// with the given name.
+    pub fn timeout_for<S: Into<String>>(mut self, command: S, timeout: Duration) -> Self {
+        self.command_timeouts.insert(command.into(), timeout);
+        self
+    }
+    /// Set whether a command's [`CancelToken`](crate::CancelToken) is cancelled
+    /// when it times out
+    ///
+    /// This is disabled by default.
+    pub fn cancel_on_timeout(mut self, cancel: bool) -> Self {
+        self.cancel_on_timeout = cancel;
+        self
+    }
+    /// Set whether the prompt shows how many messages the app has not
+    /// finished with
+    ///
+    /// A message counts until the app drops it, whether it is still queued
+    /// or still being handled. While there are any, the count is shown
+    /// before the prompt, like `[2]> `. This is disabled by default.
+    pub fn pending_indicator(mut self, indicator: bool) -> Self {
+        self.pending_indicator = indicator;
+        self
+    }
+    /// Set whether typing an opening bracket or quote also inserts the
+    /// closing one
+    ///
+    /// Typing the closing character right before it moves over it, and
+    /// backspace in an empty pair removes both. This is disabled by default.
+    pub fn auto_pair(mut self, auto_pair: bool) -> Self {
+        self.auto_pair = auto_pair;
+        self
+    }
+    /// Set whether the bracket that matches the one at the cursor is
+    /// highlighted
+    ///
+    /// This is disabled by default.
+    pub fn match_brackets(mut self, match_brackets: bool) -> Self {
+        self.match_brackets = match_brackets;
+        self
+    }
+    /// Set a function that gives the title of the terminal window
+    ///
+    /// The function is called about once a second, and the title is updated
+    /// when it changes, so it can show something like a server's player
+    /// count. For a title that only changes when the app says so, use
+    /// [`Console::set_title`].
+    pub fn title_with<F>(mut self, f: F) -> Self
+    where
+        F: FnMut() -> String + Send + 'static,
+    {
+        self.title = Some(Box::new(f));
+        self
+    }
+    /// Show a desktop notification for output that passes a filter
+    ///
+    /// The filter is given the level of output that was
+    /// [logged](crate::Responder::log), or `None` for other output, and the
+    /// output without its ANSI codes. For example,
+    /// `|level, _| level == Some(Level::Error)` shows a notification for each
+    /// error.
+    #[cfg(feature = "notifications")]
+    pub fn notify_if<F>(mut self, filter: F) -> Self
+    where
+        F: Fn(Option<Level>, &str) -> bool + Send + Sync + 'static,
+    {
+        self.notify = Some(Arc::new(filter));
+        self
+    }
+    /// Run the console's threads on an existing Tokio runtime
+    ///
+    /// The console thread, the threads that read input sources, and worker
+    /// threads run on the runtime's blocking pool instead of being spawned by
+    /// the console. Async completers and handlers run on the runtime too.
+    #[cfg(feature = "tokio")]
+    pub fn runtime(mut self, runtime: tokio::runtime::Handle) -> Self {
+        self.spawner.runtime = Some(runtime);
+        self
+    }
+    /// Build the `Console` with the given processor builder
+    ///
+    /// # Panics
+    ///
+    /// Panics if the console would read the terminal while another open
+    /// console is already reading it. Use [`ConsoleBuilder::try_build`] to
+    /// handle this.
+    pub fn build<M, B, F, P>(self, builder: B, process: F) -> Console<M>
+    where
+        M: Send + 'static,
+        B: FnOnce() -> P + Send + 'static,
+        P: CommandProcessor,
+        F: Fn(P::Parsed) -> Option<M> + Send + 'static,
+    {
+        self.try_build(builder, process)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+    /// Build the `Console` with a processor builder that is given a handle to
+    /// app state
+    ///
+    /// The processor can keep the handle to consult the state when parsing
+    /// or completing inputs, while the app updates the state through its own
+    /// handle.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the console would read the terminal while another open
+    /// console is already reading it
+    pub fn build_with_state<M, S, B, F, P>(
+        self,
+        state: &SharedState<S>,
+        builder: B,
+        process: F,
+    ) -> Console<M>
+    where
+        M: Send + 'static,
+        S: Send + Sync + 'static,
+        B: FnOnce(SharedState<S>) -> P + Send + 'static,
+        P: CommandProcessor,
+        F: Fn(P::Parsed) -> Option<M> + Send + 'static,
+    {
+        let state = state.clone();
+        self.build(move || builder(state), process)
+    }
+    /// Build the `Console` with the given processor builder, unless it would
+    /// read the terminal while another open console is already reading it
+    ///
+    /// A console reads the terminal if it was not given any
+    /// [input sources](ConsoleBuilder::input_source).
+    pub fn try_build<M, B, F, P>(self, builder: B, process: F) -> Result<Console<M>, TerminalInUse>
+    where
+        M: Send + 'static,
+        B: FnOnce() -> P + Send + 'static,
+        P: CommandProcessor,
+        F: Fn(P::Parsed) -> Option<M> + Send + 'static,
+    {
+        Console::start(self, builder, process, None)
+    }
+    /// Build a `Console` that sends each submitted line to the app as it is
+    ///
+    /// Nothing is parsed on the console thread, so the app can parse lines on
+    /// its own thread with access to its own state. Lines are still
+    /// [trimmed](ConsoleBuilder::trim_input) unless that is disabled.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the console would read the terminal while another open
+    /// console is already reading it
+    pub fn build_raw(self) -> Console<String> {
+        self.build(|| |input: &str| input.to_string(), Some)
+    }
+    /// Build a [`LocalConsole`] that parses and processes inputs on the
+    /// thread that polls it
+    ///
+    /// Unlike the other ways to build a console, neither the processor nor
+    /// the messages have to be `Send`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the console would read the terminal while another open
+    /// console is already reading it
+    pub fn build_local<M, F, P>(self, processor: P, process: F) -> LocalConsole<P, F>
+    where
+        P: CommandProcessor,
+        F: Fn(P::Parsed) -> Option<M>,
+    {
+        let tokenizer = self.tokenizer;
+        let render_errors = self.render_errors;
+        let console = self.build_raw();
+        LocalConsole::new(console, processor, process, tokenizer, render_errors)
+    }
+    /// Build the `Console` with inputs parsed and processed on worker threads
+    ///
+    /// Normally, inputs are parsed and processed on the same thread that
+    /// handles key presses, so an expensive parse makes typing stall. With
+    /// this, the given number of workers each build their own processor and
+    /// take turns handling submitted inputs. The console keeps one more
+    /// processor for completion and hints. Messages from different inputs
+    /// may arrive out of order, and "did you mean" suggestions are not shown.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the console would read the terminal while another open
+    /// console is already reading it
+    pub fn build_pooled<M, B, F, P>(self, workers: usize, builder: B, process: F) -> Console<M>
+    where
+        M: Send + 'static,
+        B: Fn() -> P + Send + Sync + 'static,
+        P: CommandProcessor,
+        F: Fn(P::Parsed) -> Option<M> + Send + Sync + 'static,
+    {
+        let builder = Arc::new(builder);
+        let process = Arc::new(process);
+        let pool = pool_starter(workers, Arc::clone(&builder), Arc::clone(&process));
+        Console::start(
+            self,
+            move || builder(),
+            move |parsed| process(parsed),
+            Some(pool),
+        )
+        .unwrap_or_else(|e| panic!("{}", e))
+    }
+    /// Build the `Console` with an async handler for parsed inputs
+    ///
+    /// Inputs are parsed on a worker thread, and the future returned by the
+    /// handler for each one is spawned onto the runtime set with
+    /// [`ConsoleBuilder::runtime`], or the current runtime if none was set.
+    /// The message the future resolves to is sent to the app, and a future
+    /// that resolves to `None` closes the console. Messages may arrive out of
+    /// order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no runtime was set and this is not called from within one,
+    /// or if the console would read the terminal while another open console
+    /// is already reading it
+    #[cfg(feature = "tokio")]
+    pub fn build_async<M, B, F, P, Fut>(self, builder: B, handler: F) -> Console<M>
+    where
+        M: Send + 'static,
+        B: Fn() -> P + Send + Sync + 'static,
+        P: CommandProcessor,
+        F: Fn(P::Parsed) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = Option<M>> + Send + 'static,
+    {
+        let runtime = self
+            .spawner
+            .runtime
+            .clone()
+            .unwrap_or_else(tokio::runtime::Handle::current);
+        let builder = Arc::new(builder);
+        let pool = async_pool_starter(Arc::clone(&builder), handler, runtime);
+        Console::start(self, move || builder(), |_| None, Some(pool))
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+}
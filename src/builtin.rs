@@ -0,0 +1,234 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant, SystemTime},
+};
+
+use crate::{parse_duration, parse_time_of_day, JobId};
+
+/// A command handled by the console itself rather than the processor
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Builtin {
+    /// Clear the screen
+    Clear,
+    /// Show or set the log level
+    LogLevel(Option<String>),
+    /// Run a command a number of times
+    Repeat {
+        count: usize,
+        delay: Duration,
+        command: String,
+    },
+    /// Run a command repeatedly until stopped
+    Watch { interval: Duration, command: String },
+    /// Schedule a command to run at a time
+    At { time: SystemTime, command: String },
+    /// Schedule a command to run on an interval
+    Every { interval: Duration, command: String },
+    /// List the scheduled jobs
+    Jobs,
+    /// Cancel a scheduled job
+    Cancel(JobId),
+    /// Start recording a macro
+    Record(String),
+    /// Stop recording a macro
+    Stop,
+    /// Play a recorded macro
+    Play(String),
+    /// Show or reset the measured latencies
+    Latency { reset: bool },
+    /// Set a console variable
+    Set {
+        global: bool,
+        name: String,
+        value: String,
+    },
+    /// Remove a console variable
+    Unset { global: bool, name: String },
+    /// Define an alias
+    Alias {
+        global: bool,
+        name: String,
+        command: String,
+    },
+    /// Remove an alias
+    Unalias { global: bool, name: String },
+}
+
+impl Builtin {
+    /// Parse a built-in command from an input
+    pub fn parse(input: &str) -> Option<Self> {
+        let mut words = input.split_whitespace();
+        let command = words.next()?;
+        match (command, words.next()) {
+            ("clear", None) => Some(Builtin::Clear),
+            ("loglevel", level) if words.next().is_none() => {
+                Some(Builtin::LogLevel(level.map(Into::into)))
+            }
+            ("repeat", Some(count)) => {
+                let count = count.parse::<usize>().ok()?;
+                match words.next()? {
+                    "-d" | "--delay" => Some(Builtin::Repeat {
+                        count,
+                        delay: parse_duration(words.next()?)?,
+                        command: command_after(input, 4)?,
+                    }),
+                    _ => Some(Builtin::Repeat {
+                        count,
+                        delay: Duration::ZERO,
+                        command: command_after(input, 2)?,
+                    }),
+                }
+            }
+            ("watch", Some(interval)) => Some(Builtin::Watch {
+                interval: parse_duration(interval)?,
+                command: command_after(input, 2)?,
+            }),
+            ("at", Some(time)) => {
+                let now = SystemTime::now();
+                let time = match time.strip_prefix('+') {
+                    Some(delay) => now.checked_add(parse_duration(delay)?)?,
+                    None => parse_time_of_day(time, now)?,
+                };
+                Some(Builtin::At {
+                    time,
+                    command: command_after(input, 2)?,
+                })
+            }
+            ("every", Some(interval)) => Some(Builtin::Every {
+                interval: parse_duration(interval)?,
+                command: command_after(input, 2)?,
+            }),
+            ("jobs", None) => Some(Builtin::Jobs),
+            ("cancel", Some(id)) if words.next().is_none() => {
+                Some(Builtin::Cancel(JobId(id.parse().ok()?)))
+            }
+            ("record", Some(name)) if words.next().is_none() => Some(Builtin::Record(name.into())),
+            ("stop", None) => Some(Builtin::Stop),
+            ("play", Some(name)) if words.next().is_none() => Some(Builtin::Play(name.into())),
+            ("latency", None) => Some(Builtin::Latency { reset: false }),
+            ("latency", Some("reset")) if words.next().is_none() => {
+                Some(Builtin::Latency { reset: true })
+            }
+            ("set", Some(first)) => {
+                let global = first == "-g";
+                let name = if global { words.next()? } else { first };
+                Some(Builtin::Set {
+                    global,
+                    name: name.into(),
+                    value: command_after(input, if global { 3 } else { 2 })?,
+                })
+            }
+            ("unset", Some(first)) => {
+                let global = first == "-g";
+                let name = if global { words.next()? } else { first };
+                words.next().is_none().then(|| Builtin::Unset {
+                    global,
+                    name: name.into(),
+                })
+            }
+            ("alias", Some(_)) => {
+                let rest = command_after(input, 1)?;
+                let (global, rest) = match rest.strip_prefix("-g ") {
+                    Some(rest) => (true, rest),
+                    None => (false, rest.as_str()),
+                };
+                let eq = rest.find('=')?;
+                let name = rest[..eq].trim();
+                let command = rest[eq + 1..].trim();
+                (!name.is_empty() && !name.contains(char::is_whitespace) && !command.is_empty())
+                    .then(|| Builtin::Alias {
+                        global,
+                        name: name.into(),
+                        command: command.into(),
+                    })
+            }
+            ("unalias", Some(first)) => {
+                let global = first == "-g";
+                let name = if global { words.next()? } else { first };
+                words.next().is_none().then(|| Builtin::Unalias {
+                    global,
+                    name: name.into(),
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Get the non-empty command that follows the given number of words
+fn command_after(input: &str, words: usize) -> Option<String> {
+    let mut rest = input.trim_start();
+    for _ in 0..words {
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        rest = rest[end..].trim_start();
+    }
+    let command = rest.trim_end();
+    (!command.is_empty()).then(|| command.into())
+}
+
+/// A command that is being run by the `repeat` built-in
+pub(crate) struct Repeat {
+    pub command: String,
+    /// The number of runs left
+    pub remaining: usize,
+    pub delay: Duration,
+    /// When the command is next run
+    pub next: Instant,
+}
+
+/// A command that is being run repeatedly by the `watch` built-in
+pub(crate) struct Watch {
+    pub interval: Duration,
+    pub command: String,
+    /// When the command is next run
+    pub next: Instant,
+}
+
+/// Macros recorded with the `record` built-in
+#[derive(Default)]
+pub(crate) struct Macros {
+    macros: HashMap<String, Vec<String>>,
+    /// The name and commands of the macro being recorded
+    recording: Option<(String, Vec<String>)>,
+    /// The names of the macros being played, innermost last
+    pub playing: Vec<String>,
+}
+
+impl Macros {
+    /// Recreate the macros that were recorded in the history
+    pub fn from_history(history: &[String]) -> Self {
+        let mut macros = Macros::default();
+        for line in history {
+            match Builtin::parse(line) {
+                Some(Builtin::Record(name)) => macros.record(name),
+                Some(Builtin::Stop) => {
+                    macros.stop();
+                }
+                _ => macros.push(line),
+            }
+        }
+        macros
+    }
+    /// Start recording a macro, discarding any unfinished one
+    pub fn record(&mut self, name: String) {
+        self.recording = Some((name, Vec::new()));
+    }
+    /// Add a submitted command to the macro being recorded
+    pub fn push(&mut self, command: &str) {
+        if let Some((_, commands)) = &mut self.recording {
+            commands.push(command.into());
+        }
+    }
+    /// Finish recording, returning the name of the macro and its number of
+    /// commands
+    pub fn stop(&mut self) -> Option<(String, usize)> {
+        let (name, commands) = self.recording.take()?;
+        let count = commands.len();
+        self.macros.insert(name.clone(), commands);
+        Some((name, count))
+    }
+    /// Get the commands of a macro
+    pub fn get(&self, name: &str) -> Option<Vec<String>> {
+        self.macros.get(name).cloned()
+    }
+}
@@ -0,0 +1,62 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Weak,
+};
+
+/// A flag that asks the handler of a command to stop
+///
+/// A token is delivered with each message in its
+/// [`Envelope`](crate::Envelope). The console trips it when the user presses
+/// Ctrl-C or when the input source the command came from disconnects, so
+/// long-running handlers can check it and stop early.
+///
+/// The console considers a command finished once every clone of its token
+/// has been dropped.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+    /// Only held by the token and its clones, so the console can tell when
+    /// the app has finished with the command
+    alive: Arc<()>,
+}
+
+impl CancelToken {
+    /// Create a new `CancelToken` that has not been cancelled
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Check if the command has been cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+    /// Cancel the command
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+    /// Get a handle the console keeps for the command without keeping it
+    /// alive
+    pub(crate) fn handle(&self) -> CommandHandle {
+        CommandHandle {
+            cancelled: Arc::clone(&self.cancelled),
+            alive: Arc::downgrade(&self.alive),
+        }
+    }
+}
+
+/// The console's handle to a command that was sent to the app
+#[derive(Debug, Clone)]
+pub(crate) struct CommandHandle {
+    cancelled: Arc<AtomicBool>,
+    alive: Weak<()>,
+}
+
+impl CommandHandle {
+    /// Check if the app still holds the command's token
+    pub fn is_alive(&self) -> bool {
+        self.alive.strong_count() > 0
+    }
+    /// Cancel the command
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
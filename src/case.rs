@@ -0,0 +1,45 @@
+/// The way letter case is matched between typed text and the text it is
+/// looked up in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaseMatching {
+    /// Letters must have the same case
+    Sensitive,
+    /// Letters match in either case
+    Insensitive,
+    /// Letters match in either case unless the typed text has an uppercase
+    /// letter
+    Smart,
+}
+
+impl CaseMatching {
+    /// Check if case is ignored when matching some typed text
+    pub fn ignores_case(self, typed: &str) -> bool {
+        match self {
+            CaseMatching::Sensitive => false,
+            CaseMatching::Insensitive => true,
+            CaseMatching::Smart => !typed.chars().any(char::is_uppercase),
+        }
+    }
+    /// Check if two characters match when matching some typed text
+    pub(crate) fn chars_match(self, typed: &str, a: char, b: char) -> bool {
+        a == b || self.ignores_case(typed) && a.to_lowercase().eq(b.to_lowercase())
+    }
+    /// Get the byte indices of the characters of a word that match typed
+    /// text at its start, or `None` if the word does not start with it
+    pub fn prefix_match(self, typed: &str, word: &str) -> Option<Vec<usize>> {
+        let mut word_chars = word.char_indices();
+        let mut indices = Vec::with_capacity(typed.len());
+        for t in typed.chars() {
+            let (i, w) = word_chars.next()?;
+            if !self.chars_match(typed, t, w) {
+                return None;
+            }
+            indices.push(i);
+        }
+        Some(indices)
+    }
+    /// Check if a word is the typed text
+    pub fn matches(self, typed: &str, word: &str) -> bool {
+        typed.chars().count() == word.chars().count() && self.prefix_match(typed, word).is_some()
+    }
+}
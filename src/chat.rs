@@ -0,0 +1,351 @@
+use std::{
+    collections::VecDeque,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    sync::{
+        mpsc::{self, Receiver, RecvTimeoutError},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{add_sink, Console, Input, InputSource, Origin, OutputSink, Role, Sinks};
+
+/// A command received from a chat service
+pub struct ChatCommand {
+    /// The text of the command
+    pub text: String,
+    /// The sink that the response to the command is written to
+    pub reply: Box<dyn OutputSink>,
+}
+
+/// An integration that receives commands from a chat service, like a Discord
+/// bot or an IRC bridge
+///
+/// Adapters are connected with [`Console::connect_chat`]. Their commands are
+/// run by the console's processor with the [`Role::Restricted`] role, so
+/// only the commands allowed with
+/// [`ConsoleBuilder::restricted_commands`](crate::ConsoleBuilder::restricted_commands)
+/// can be run. Output printed with [`Console::print_to`] for a command's
+/// origin is written to the command's reply sink.
+pub trait ChatAdapter: Send {
+    /// Get the name of the chat service
+    ///
+    /// This is used to name the origins of the adapter's commands.
+    fn name(&self) -> &str;
+    /// Wait for the next command
+    ///
+    /// Returns `None` once the adapter is closed
+    fn receive(&mut self) -> Option<ChatCommand>;
+}
+
+impl<M> Console<M>
+where
+    M: Send + 'static,
+{
+    /// Take commands from a chat service
+    ///
+    /// Each command is its own [`Origin::Source`], named after the adapter
+    /// and numbered like `webhook:0`, `webhook:1`, and so on.
+    pub fn connect_chat<A>(&self, adapter: A)
+    where
+        A: ChatAdapter + 'static,
+    {
+        self.add_input_source(ChatSource {
+            adapter,
+            sinks: Arc::clone(&self.sinks),
+            count: 0,
+            origin: Origin::Console,
+        })
+    }
+}
+
+/// Reads the commands from a chat adapter
+struct ChatSource<A> {
+    adapter: A,
+    sinks: Sinks,
+    /// The number of commands received
+    count: usize,
+    /// The origin of the last command
+    origin: Origin,
+}
+
+impl<A: ChatAdapter> InputSource for ChatSource<A> {
+    fn next_input(&mut self) -> Option<Input> {
+        let command = self.adapter.receive()?;
+        self.origin = Origin::Source(format!("{}:{}", self.adapter.name(), self.count));
+        self.count += 1;
+        // Forget the replies to earlier commands that timed out
+        self.sinks
+            .lock()
+            .unwrap()
+            .retain(|(_, sink)| !sink.try_lock().is_ok_and(|sink| sink.is_closed()));
+        add_sink(&self.sinks, Some(self.origin.clone()), command.reply);
+        Some(Input::Line(command.text))
+    }
+    fn origin(&self) -> Origin {
+        self.origin.clone()
+    }
+    fn role(&self) -> Role {
+        Role::Restricted
+    }
+}
+
+/// A [`ChatAdapter`] that receives commands as HTTP webhook requests
+///
+/// The body of each `POST` request is run as a command. Requests must have
+/// an `Authorization: Bearer SECRET` header with the adapter's secret, or
+/// they are refused. The first output for the command is sent back as the
+/// body of the response. If there is no output before the
+/// [timeout](WebhookAdapter::timeout), the response is empty. Bodies larger
+/// than 64 KiB are rejected.
+///
+/// Requests are read by a few worker threads, so a slow client does not
+/// hold up the others.
+pub struct WebhookAdapter {
+    listener: Option<TcpListener>,
+    secret: Arc<str>,
+    timeout: Duration,
+    /// The commands read by the workers, once they are started
+    commands: Option<Receiver<ChatCommand>>,
+}
+
+impl WebhookAdapter {
+    /// Listen for webhook requests on the given address, accepting the
+    /// requests that have the given secret
+    pub fn bind<A, S>(addr: A, secret: S) -> io::Result<Self>
+    where
+        A: ToSocketAddrs,
+        S: Into<String>,
+    {
+        Ok(WebhookAdapter {
+            listener: Some(TcpListener::bind(addr)?),
+            secret: secret.into().into(),
+            timeout: Duration::from_secs(10),
+            commands: None,
+        })
+    }
+    /// Set how long to wait for output before sending an empty response
+    ///
+    /// This is also how long a client has to send its request. It is 10
+    /// seconds by default.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+    /// Get the address the adapter is listening on
+    pub fn local_addr(&self) -> io::Result<std::net::SocketAddr> {
+        match &self.listener {
+            Some(listener) => listener.local_addr(),
+            None => Err(io::ErrorKind::NotConnected.into()),
+        }
+    }
+    /// Start the threads that accept connections, read requests, and send
+    /// the responses that time out
+    fn start(&mut self) -> Option<Receiver<ChatCommand>> {
+        let listener = self.listener.take()?;
+        let (command_send, command_recv) = mpsc::channel();
+        let (expire_send, expire_recv) = mpsc::channel();
+        let (stream_send, stream_recv) = mpsc::sync_channel::<TcpStream>(WEBHOOK_WORKERS);
+        let stream_recv = Arc::new(Mutex::new(stream_recv));
+        for _ in 0..WEBHOOK_WORKERS {
+            let streams = Arc::clone(&stream_recv);
+            let commands = command_send.clone();
+            let expire = expire_send.clone();
+            let secret = Arc::clone(&self.secret);
+            let timeout = self.timeout;
+            thread::spawn(move || loop {
+                let stream = match streams.lock().unwrap().recv() {
+                    Ok(stream) => stream,
+                    Err(_) => return,
+                };
+                let _ = stream.set_read_timeout(Some(timeout));
+                match read_request(&stream, &secret) {
+                    Ok(text) => {
+                        let stream = Arc::new(Mutex::new(Some(stream)));
+                        let deadline = Instant::now() + timeout;
+                        let _ = expire.send((deadline, Arc::clone(&stream)));
+                        let reply = Box::new(WebhookReply { stream });
+                        if commands.send(ChatCommand { text, reply }).is_err() {
+                            return;
+                        }
+                    }
+                    Err(status) => {
+                        let _ = respond(&mut &stream, status, "");
+                    }
+                }
+            });
+        }
+        thread::spawn(move || expire_replies(expire_recv));
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        if stream_send.send(stream).is_err() {
+                            return;
+                        }
+                    }
+                    // Errors like running out of file descriptors last a
+                    // while, so wait before accepting again
+                    Err(_) => thread::sleep(ACCEPT_RETRY_DELAY),
+                }
+            }
+        });
+        Some(command_recv)
+    }
+}
+
+impl ChatAdapter for WebhookAdapter {
+    fn name(&self) -> &str {
+        "webhook"
+    }
+    fn receive(&mut self) -> Option<ChatCommand> {
+        if self.commands.is_none() {
+            self.commands = self.start();
+        }
+        self.commands.as_ref()?.recv().ok()
+    }
+}
+
+/// The number of threads that read webhook requests
+const WEBHOOK_WORKERS: usize = 4;
+
+/// How long to wait before accepting again after accepting fails
+const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// A webhook response to send once its deadline passes
+type Expiring = (Instant, Arc<Mutex<Option<TcpStream>>>);
+
+/// Send an empty response to each webhook request that has no output by its
+/// deadline
+///
+/// Every request has the same timeout, so the deadlines arrive in order.
+fn expire_replies(recv: Receiver<Expiring>) {
+    let mut waiting: VecDeque<Expiring> = VecDeque::new();
+    loop {
+        let next = match waiting.front() {
+            Some((deadline, _)) => {
+                recv.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            }
+            None => recv.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        match next {
+            Ok(expiring) => waiting.push_back(expiring),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) if waiting.is_empty() => return,
+            Err(RecvTimeoutError::Disconnected) => {
+                thread::sleep(waiting[0].0.saturating_duration_since(Instant::now()));
+            }
+        }
+        let now = Instant::now();
+        while waiting
+            .front()
+            .is_some_and(|(deadline, _)| *deadline <= now)
+        {
+            let (_, unanswered) = waiting.pop_front().unwrap();
+            let stream = unanswered.lock().unwrap().take();
+            if let Some(mut stream) = stream {
+                let _ = respond(&mut stream, "204 No Content", "");
+            }
+        }
+    }
+}
+
+/// Sends the response to a webhook request
+struct WebhookReply {
+    stream: Arc<Mutex<Option<TcpStream>>>,
+}
+
+impl OutputSink for WebhookReply {
+    fn write(&mut self, output: &str) -> io::Result<()> {
+        // Only the first output is sent, after which the sink is removed
+        let mut stream = self
+            .stream
+            .lock()
+            .unwrap()
+            .take()
+            .ok_or(io::ErrorKind::NotConnected)?;
+        respond(&mut stream, "200 OK", output)?;
+        Err(io::ErrorKind::NotConnected.into())
+    }
+    fn ansi(&self) -> bool {
+        false
+    }
+    fn is_closed(&self) -> bool {
+        // The stream is taken once the response is sent or times out
+        self.stream.lock().unwrap().is_none()
+    }
+}
+
+/// The largest request body that is accepted
+const MAX_BODY: usize = 64 * 1024;
+
+/// The most bytes read for the request line and headers
+const MAX_HEAD: usize = 16 * 1024;
+
+/// Read an authorized HTTP `POST` request, returning its body
+///
+/// Returns the status to respond with if the request cannot be read or is
+/// refused.
+fn read_request(stream: &TcpStream, secret: &str) -> Result<String, &'static str> {
+    const BAD_REQUEST: &str = "400 Bad Request";
+    let mut reader = BufReader::new(stream.take((MAX_HEAD + MAX_BODY) as u64));
+    let mut request_line = String::new();
+    reader
+        .read_line(&mut request_line)
+        .map_err(|_| BAD_REQUEST)?;
+    let mut content_length = 0;
+    let mut authorized = false;
+    loop {
+        let mut header = String::new();
+        let read = reader.read_line(&mut header).map_err(|_| BAD_REQUEST)?;
+        if read == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            let name = name.trim();
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().map_err(|_| BAD_REQUEST)?;
+            } else if name.eq_ignore_ascii_case("authorization") {
+                let token = value.trim().strip_prefix("Bearer ").unwrap_or_default();
+                authorized = secrets_match(token.trim(), secret);
+            }
+        }
+    }
+    if !request_line.starts_with("POST ") {
+        return Err("405 Method Not Allowed");
+    }
+    if !authorized {
+        return Err("401 Unauthorized");
+    }
+    if content_length > MAX_BODY {
+        return Err("413 Payload Too Large");
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).map_err(|_| BAD_REQUEST)?;
+    Ok(String::from_utf8_lossy(&body).trim().into())
+}
+
+/// Compare a token with a secret in time that does not depend on where they
+/// differ
+fn secrets_match(token: &str, secret: &str) -> bool {
+    let difference = token
+        .bytes()
+        .zip(secret.bytes())
+        .fold(0, |difference, (a, b)| difference | (a ^ b));
+    difference == 0 && token.len() == secret.len()
+}
+
+/// Write an HTTP response and close the connection
+fn respond<W: Write>(writer: &mut W, status: &str, body: &str) -> io::Result<()> {
+    write!(
+        writer,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; charset=utf-8\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )?;
+    writer.flush()
+}
@@ -0,0 +1,43 @@
+/// Access to the OS clipboard
+///
+/// Without the `clipboard` feature, the clipboard is always empty and
+/// copying does nothing.
+#[derive(Default)]
+pub(crate) struct Clipboard {
+    #[cfg(feature = "clipboard")]
+    inner: Option<arboard::Clipboard>,
+}
+
+impl Clipboard {
+    /// Get the text on the clipboard
+    pub fn get(&mut self) -> Option<String> {
+        #[cfg(feature = "clipboard")]
+        {
+            self.inner()?.get_text().ok()
+        }
+        #[cfg(not(feature = "clipboard"))]
+        {
+            None
+        }
+    }
+    /// Put text on the clipboard
+    pub fn set(&mut self, text: &str) {
+        #[cfg(feature = "clipboard")]
+        {
+            if let Some(clipboard) = self.inner() {
+                let _ = clipboard.set_text(text);
+            }
+        }
+        #[cfg(not(feature = "clipboard"))]
+        {
+            let _ = text;
+        }
+    }
+    #[cfg(feature = "clipboard")]
+    fn inner(&mut self) -> Option<&mut arboard::Clipboard> {
+        if self.inner.is_none() {
+            self.inner = arboard::Clipboard::new().ok();
+        }
+        self.inner.as_mut()
+    }
+}
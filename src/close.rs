@@ -0,0 +1,29 @@
+use std::any::Any;
+
+/// The reason a [`Console`](crate::Console) closed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CloseReason {
+    /// The user quit, either with a quit command or key or because the
+    /// process function returned `None`
+    UserQuit,
+    /// Every input source ran out of input
+    Eof,
+    /// The app closed the `Console`
+    AppClosed,
+    /// The console thread panicked with the contained message
+    Error(String),
+}
+
+impl CloseReason {
+    /// Create a `CloseReason` from a panic payload
+    pub(crate) fn from_panic(payload: Box<dyn Any + Send>) -> Self {
+        let message = if let Some(s) = payload.downcast_ref::<&str>() {
+            s.to_string()
+        } else if let Some(s) = payload.downcast_ref::<String>() {
+            s.clone()
+        } else {
+            "console thread panicked".into()
+        };
+        CloseReason::Error(message)
+    }
+}
@@ -0,0 +1,581 @@
+use std::sync::Arc;
+
+use colored::Colorize;
+
+use crate::{fuzzy_match_case, CaseMatching};
+
+/// A possible completion of the word being typed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    /// The text that replaces the word being completed
+    pub replacement: String,
+    /// The byte indices of the characters in the replacement that matched
+    /// the typed word
+    ///
+    /// These are highlighted in the completion menu.
+    pub matched: Vec<usize>,
+    /// A short description shown next to the candidate in the completion menu
+    pub description: Option<String>,
+}
+
+impl Candidate {
+    /// Create a new `Candidate`
+    pub fn new<S: Into<String>>(replacement: S) -> Self {
+        Candidate {
+            replacement: replacement.into(),
+            matched: Vec::new(),
+            description: None,
+        }
+    }
+    /// Set the description
+    pub fn description<S: Into<String>>(mut self, description: S) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+}
+
+/// The way the typed word is matched against possible completions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Matching {
+    /// Words must start with the typed word
+    #[default]
+    Prefix,
+    /// Words must contain the characters of the typed word in order
+    ///
+    /// Candidates are ranked by how well they match, so `spw` completes to
+    /// `spawn_wave`.
+    Fuzzy,
+}
+
+impl Matching {
+    /// Match a word against the typed word, returning a score and the byte
+    /// indices of the matched characters
+    ///
+    /// Prefixes must match in case, while fuzzy matches ignore it.
+    pub fn score(self, typed: &str, word: &str) -> Option<(i64, Vec<usize>)> {
+        self.score_with_case(typed, word, self.default_case())
+    }
+    /// Get the way letter case is matched unless it is set
+    pub(crate) fn default_case(self) -> CaseMatching {
+        match self {
+            Matching::Prefix => CaseMatching::Sensitive,
+            Matching::Fuzzy => CaseMatching::Insensitive,
+        }
+    }
+    /// Match a word against the typed word, matching letter case in the
+    /// given way
+    pub fn score_with_case(
+        self,
+        typed: &str,
+        word: &str,
+        case: CaseMatching,
+    ) -> Option<(i64, Vec<usize>)> {
+        match self {
+            Matching::Prefix => Some((0, case.prefix_match(typed, word)?)),
+            Matching::Fuzzy => fuzzy_match_case(typed, word, case),
+        }
+    }
+}
+
+/// The result of completing an input
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Completion {
+    /// The byte index in the input where the word being completed starts
+    pub start: usize,
+    /// The possible completions of the word
+    pub candidates: Vec<Candidate>,
+}
+
+impl Completion {
+    /// Create a `Completion` of the word at the cursor from a list of
+    /// possible words
+    ///
+    /// Only words that start with the part of the word before the cursor are
+    /// kept as candidates.
+    pub fn from_words<I, S>(input: &str, cursor: usize, words: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self::from_words_matching(input, cursor, words, Matching::Prefix)
+    }
+    /// Create a `Completion` of the word at the cursor from a list of
+    /// possible words, matching them in the given way
+    ///
+    /// Candidates are sorted from best to worst match.
+    pub fn from_words_matching<I, S>(
+        input: &str,
+        cursor: usize,
+        words: I,
+        matching: Matching,
+    ) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let candidates = words.into_iter().map(Candidate::new);
+        Self::from_candidates(input, cursor, candidates, matching)
+    }
+    /// Create a `Completion` of the word at the cursor from a list of
+    /// possible candidates, matching them in the given way
+    ///
+    /// Candidates are sorted from best to worst match.
+    pub fn from_candidates<I>(input: &str, cursor: usize, candidates: I, matching: Matching) -> Self
+    where
+        I: IntoIterator<Item = Candidate>,
+    {
+        let case = matching.default_case();
+        Self::from_candidates_with_case(input, cursor, candidates, matching, case)
+    }
+    /// Create a `Completion` of the word at the cursor from a list of
+    /// possible candidates, matching them and their letter case in the given
+    /// ways
+    ///
+    /// Candidates are sorted from best to worst match.
+    pub fn from_candidates_with_case<I>(
+        input: &str,
+        cursor: usize,
+        candidates: I,
+        matching: Matching,
+        case: CaseMatching,
+    ) -> Self
+    where
+        I: IntoIterator<Item = Candidate>,
+    {
+        let start = word_start(input, cursor);
+        let typed = &input[start..cursor];
+        let mut scored: Vec<(i64, Candidate)> = candidates
+            .into_iter()
+            .filter_map(|mut candidate| {
+                let (score, matched) =
+                    matching.score_with_case(typed, &candidate.replacement, case)?;
+                candidate.matched = matched;
+                Some((score, candidate))
+            })
+            .collect();
+        scored.sort_by(|(a, _), (b, _)| b.cmp(a));
+        Completion {
+            start,
+            candidates: scored.into_iter().map(|(_, candidate)| candidate).collect(),
+        }
+    }
+}
+
+/// A [`Completer`] that completes the word at the cursor from a fixed list
+/// of words
+#[derive(Debug, Clone, Default)]
+pub struct WordCompleter {
+    words: Vec<String>,
+    matching: Matching,
+}
+
+impl WordCompleter {
+    /// Create a new `WordCompleter` from a list of words
+    pub fn new<I, S>(words: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        WordCompleter {
+            words: words.into_iter().map(Into::into).collect(),
+            matching: Matching::default(),
+        }
+    }
+    /// Set the way the typed word is matched against the words
+    pub fn matching(mut self, matching: Matching) -> Self {
+        self.matching = matching;
+        self
+    }
+}
+
+impl Completer for WordCompleter {
+    fn complete(&mut self, input: &str, cursor: usize) -> Completion {
+        Completion::from_words_matching(input, cursor, &self.words, self.matching)
+    }
+    fn complete_with_case(&mut self, input: &str, cursor: usize, case: CaseMatching) -> Completion {
+        let candidates = self.words.iter().map(Candidate::new);
+        Completion::from_candidates_with_case(input, cursor, candidates, self.matching, case)
+    }
+}
+
+/// A [`Completer`] that completes with an async function
+///
+/// The function is called with the input and the cursor position, and its
+/// future is run to completion on the Tokio runtime set with
+/// [`ConsoleBuilder::runtime`](crate::ConsoleBuilder::runtime). Without a
+/// runtime, nothing is completed.
+#[cfg(feature = "tokio")]
+pub struct AsyncCompleter<F> {
+    f: F,
+}
+
+#[cfg(feature = "tokio")]
+impl<F, Fut> AsyncCompleter<F>
+where
+    F: Fn(String, usize) -> Fut,
+    Fut: std::future::Future<Output = Completion>,
+{
+    /// Create a new `AsyncCompleter` from a function that returns a future
+    pub fn new(f: F) -> Self {
+        AsyncCompleter { f }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<F, Fut> Completer for AsyncCompleter<F>
+where
+    F: Fn(String, usize) -> Fut,
+    Fut: std::future::Future<Output = Completion>,
+{
+    fn complete(&mut self, input: &str, cursor: usize) -> Completion {
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => runtime.block_on((self.f)(input.into(), cursor)),
+            Err(_) => Completion::default(),
+        }
+    }
+}
+
+/// A thread-safe source of values to complete
+///
+/// Providers are queried on the console thread every time completion is
+/// requested, so they can return live app data like the names of the
+/// currently connected players.
+pub trait ValueProvider: Send + Sync {
+    /// Get the current values
+    fn values(&self) -> Vec<String>;
+}
+
+impl<F> ValueProvider for F
+where
+    F: Fn() -> Vec<String> + Send + Sync,
+{
+    fn values(&self) -> Vec<String> {
+        self()
+    }
+}
+
+/// A [`Completer`] that completes the word at the cursor from the values of
+/// a [`ValueProvider`]
+#[derive(Clone)]
+pub struct ValueCompleter {
+    provider: Arc<dyn ValueProvider>,
+    matching: Matching,
+}
+
+impl ValueCompleter {
+    /// Create a new `ValueCompleter` from a provider
+    pub fn new<V>(provider: V) -> Self
+    where
+        V: ValueProvider + 'static,
+    {
+        Self::from_arc(Arc::new(provider))
+    }
+    /// Create a new `ValueCompleter` from a provider that is shared with the
+    /// app
+    pub fn from_arc(provider: Arc<dyn ValueProvider>) -> Self {
+        ValueCompleter {
+            provider,
+            matching: Matching::default(),
+        }
+    }
+    /// Set the way the typed word is matched against the values
+    pub fn matching(mut self, matching: Matching) -> Self {
+        self.matching = matching;
+        self
+    }
+}
+
+impl Completer for ValueCompleter {
+    fn complete(&mut self, input: &str, cursor: usize) -> Completion {
+        Completion::from_words_matching(input, cursor, self.provider.values(), self.matching)
+    }
+    fn complete_with_case(&mut self, input: &str, cursor: usize, case: CaseMatching) -> Completion {
+        let candidates = self.provider.values().into_iter().map(Candidate::new);
+        Completion::from_candidates_with_case(input, cursor, candidates, self.matching, case)
+    }
+}
+
+/// A [`Completer`] that uses different completers for different argument
+/// positions
+///
+/// Positions count the whitespace-separated words before the word being
+/// completed, so the command itself is at position 0. Completers bound to an
+/// argument of a specific command take precedence over completers bound to a
+/// position.
+#[derive(Default)]
+pub struct ArgCompleter {
+    positions: Vec<(usize, Box<dyn Completer + Send>)>,
+    command_args: Vec<(String, usize, Box<dyn Completer + Send>)>,
+    fallback: Option<Box<dyn Completer + Send>>,
+}
+
+impl ArgCompleter {
+    /// Create a new `ArgCompleter` with no completers
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Use a completer for the word at the given position
+    pub fn position<C>(mut self, position: usize, completer: C) -> Self
+    where
+        C: Completer + Send + 'static,
+    {
+        self.positions.push((position, Box::new(completer)));
+        self
+    }
+    /// Use a completer for an argument of the given command
+    ///
+    /// Arguments are counted from 0, not including the command itself.
+    pub fn command_arg<S, C>(mut self, command: S, arg: usize, completer: C) -> Self
+    where
+        S: Into<String>,
+        C: Completer + Send + 'static,
+    {
+        self.command_args
+            .push((command.into(), arg, Box::new(completer)));
+        self
+    }
+    /// Complete an argument of the given command from the values of a
+    /// [`ValueProvider`]
+    ///
+    /// Arguments are counted from 0, not including the command itself.
+    pub fn command_values<S, V>(self, command: S, arg: usize, provider: V) -> Self
+    where
+        S: Into<String>,
+        V: ValueProvider + 'static,
+    {
+        self.command_arg(command, arg, ValueCompleter::new(provider))
+    }
+    /// Use a completer for positions that have no other completer
+    pub fn fallback<C>(mut self, completer: C) -> Self
+    where
+        C: Completer + Send + 'static,
+    {
+        self.fallback = Some(Box::new(completer));
+        self
+    }
+}
+
+impl Completer for ArgCompleter {
+    fn complete(&mut self, input: &str, cursor: usize) -> Completion {
+        match self.completer(input, cursor) {
+            Some(completer) => completer.complete(input, cursor),
+            None => Completion::default(),
+        }
+    }
+    fn complete_with_case(&mut self, input: &str, cursor: usize, case: CaseMatching) -> Completion {
+        match self.completer(input, cursor) {
+            Some(completer) => completer.complete_with_case(input, cursor, case),
+            None => Completion::default(),
+        }
+    }
+}
+
+impl ArgCompleter {
+    /// Get the completer for the word at the cursor
+    fn completer(&mut self, input: &str, cursor: usize) -> Option<&mut Box<dyn Completer + Send>> {
+        let start = word_start(input, cursor);
+        let mut words = input[..start].split_whitespace();
+        let command = words.next();
+        let position = command.map(|_| 1 + words.count()).unwrap_or(0);
+        let command_arg = self
+            .command_args
+            .iter_mut()
+            .find(|(c, arg, _)| command == Some(c.as_str()) && position == arg + 1);
+        let completer = match command_arg {
+            Some((_, _, completer)) => Some(completer),
+            None => self
+                .positions
+                .iter_mut()
+                .find(|(p, _)| *p == position)
+                .map(|(_, completer)| completer),
+        };
+        completer.or(self.fallback.as_mut())
+    }
+}
+
+/// Behavior for completing input
+pub trait Completer {
+    /// Complete the word at the cursor
+    fn complete(&mut self, input: &str, cursor: usize) -> Completion;
+    /// Complete the word at the cursor, matching letter case in the given
+    /// way
+    ///
+    /// The console calls this instead of [`Completer::complete`] if
+    /// [`ConsoleBuilder::completion_case`](crate::ConsoleBuilder::completion_case)
+    /// is set. By default, the case is ignored.
+    fn complete_with_case(&mut self, input: &str, cursor: usize, case: CaseMatching) -> Completion {
+        let _ = case;
+        self.complete(input, cursor)
+    }
+}
+
+impl<F> Completer for F
+where
+    F: FnMut(&str, usize) -> Completion,
+{
+    fn complete(&mut self, input: &str, cursor: usize) -> Completion {
+        self(input, cursor)
+    }
+}
+
+/// Get the byte index of the start of the word that contains the cursor
+pub fn word_start(input: &str, cursor: usize) -> usize {
+    input[..cursor]
+        .rfind(char::is_whitespace)
+        .map(|i| i + 1)
+        .unwrap_or(0)
+}
+
+/// Get the longest prefix shared by all candidates
+pub(crate) fn common_prefix(candidates: &[Candidate]) -> &str {
+    let first = match candidates.first() {
+        Some(first) => &first.replacement,
+        None => return "",
+    };
+    let mut len = first.len();
+    for candidate in &candidates[1..] {
+        len = first
+            .char_indices()
+            .zip(candidate.replacement.chars())
+            .take_while(|((_, a), b)| a == b)
+            .map(|((i, a), _)| i + a.len_utf8())
+            .last()
+            .unwrap_or(0)
+            .min(len);
+    }
+    &first[..len]
+}
+
+/// A menu of completion candidates shown below the input line
+pub(crate) struct Menu {
+    /// The input before any candidate was selected
+    pub original: String,
+    /// The start of the word being completed
+    pub start: usize,
+    /// The end of the word being completed in the original input
+    pub end: usize,
+    pub candidates: Vec<Candidate>,
+    pub selected: Option<usize>,
+}
+
+impl Menu {
+    /// Select the next candidate
+    pub fn next(&mut self) {
+        self.selected = Some(match self.selected {
+            Some(i) => (i + 1) % self.candidates.len(),
+            None => 0,
+        });
+    }
+    /// Select the previous candidate
+    pub fn prev(&mut self) {
+        let len = self.candidates.len();
+        self.selected = Some(match self.selected {
+            Some(i) => (i + len - 1) % len,
+            None => len - 1,
+        });
+    }
+    /// Get the input and cursor position with the selected candidate
+    /// substituted in
+    pub fn apply(&self) -> (String, usize) {
+        match self.selected {
+            Some(i) => {
+                let replacement = &self.candidates[i].replacement;
+                let input = format!(
+                    "{}{}{}",
+                    &self.original[..self.start],
+                    replacement,
+                    &self.original[self.end..]
+                );
+                (input, self.start + replacement.len())
+            }
+            None => (self.original.clone(), self.end),
+        }
+    }
+    /// Render the menu to fit in the given width and number of rows
+    ///
+    /// If any candidate has a description, the candidates are listed one per
+    /// row with their descriptions. Otherwise, they are laid out in a single
+    /// row.
+    pub fn render(&self, width: usize, rows: usize) -> String {
+        if self.candidates.iter().any(|c| c.description.is_some()) {
+            self.render_rows(width, rows)
+        } else {
+            self.render_row(width)
+        }
+    }
+    /// Render the candidates in a single row
+    fn render_row(&self, width: usize) -> String {
+        let widths: Vec<usize> = self
+            .candidates
+            .iter()
+            .map(|c| c.replacement.chars().count() + 2)
+            .collect();
+        // Scroll so that the selected candidate is visible
+        let mut first = 0;
+        if let Some(selected) = self.selected {
+            while widths[first..=selected].iter().sum::<usize>() > width && first < selected {
+                first += 1;
+            }
+        }
+        let mut rendered = String::new();
+        let mut used = 0;
+        for (i, &candidate_width) in widths.iter().enumerate().skip(first) {
+            if used + candidate_width > width {
+                break;
+            }
+            used += candidate_width;
+            rendered.push_str(&self.render_candidate(i, usize::MAX));
+            rendered.push_str("  ");
+        }
+        rendered
+    }
+    /// Render the candidates one per row with their descriptions
+    fn render_rows(&self, width: usize, rows: usize) -> String {
+        let rows = rows.max(1);
+        let name_width = self
+            .candidates
+            .iter()
+            .map(|c| c.replacement.chars().count())
+            .max()
+            .unwrap_or(0)
+            .min(width);
+        // Scroll so that the selected candidate is visible
+        let first = match self.selected {
+            Some(selected) if selected >= rows => selected + 1 - rows,
+            _ => 0,
+        };
+        let mut rendered = Vec::new();
+        for (i, candidate) in self.candidates.iter().enumerate().skip(first).take(rows) {
+            let name_len = candidate.replacement.chars().count().min(name_width);
+            let mut row = self.render_candidate(i, name_width);
+            if let Some(description) = &candidate.description {
+                let room = width.saturating_sub(name_width + 2);
+                if room > 0 {
+                    let description: String = description.chars().take(room).collect();
+                    row.push_str(&" ".repeat(name_width - name_len + 2));
+                    row.push_str(&description.dimmed().to_string());
+                }
+            }
+            rendered.push(row);
+        }
+        rendered.join("\n")
+    }
+    /// Render a candidate's replacement, truncated to the given number of
+    /// characters, with its matched characters highlighted
+    fn render_candidate(&self, i: usize, max: usize) -> String {
+        let candidate = &self.candidates[i];
+        let selected = Some(i) == self.selected;
+        let mut rendered = String::new();
+        for (j, c) in candidate.replacement.char_indices().take(max) {
+            let mut c = c.to_string().normal();
+            if candidate.matched.contains(&j) {
+                c = c.bold();
+            }
+            if selected {
+                c = c.reversed();
+            }
+            rendered.push_str(&c.to_string());
+        }
+        rendered
+    }
+}
@@ -0,0 +1,86 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    process,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crossterm::Terminal;
+
+use crate::{Origin, Tokenizer};
+
+static NEXT_SESSION: AtomicU64 = AtomicU64::new(0);
+
+/// A unique identifier for a console session
+///
+/// Identifiers are random, so the sessions of different processes that
+/// share a history store do not collide.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SessionId(pub u64);
+
+impl SessionId {
+    pub(crate) fn next() -> Self {
+        // The hasher's keys are random, and the counter, process, and time
+        // make each session's identifier different even if they are not
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u64(NEXT_SESSION.fetch_add(1, Ordering::Relaxed));
+        hasher.write_u32(process::id());
+        let time = SystemTime::now().duration_since(UNIX_EPOCH);
+        hasher.write_u128(time.unwrap_or_default().as_nanos());
+        SessionId(hasher.finish())
+    }
+}
+
+/// What the input from a source is allowed to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Role {
+    /// Any command can be run
+    #[default]
+    Full,
+    /// Only the processor commands that are allowed with
+    /// [`ConsoleBuilder::restricted_commands`](crate::ConsoleBuilder::restricted_commands)
+    /// can be run
+    ///
+    /// Built-in commands, quit commands, shell commands, and scripted
+    /// commands are refused. No commands are allowed by default.
+    Restricted,
+}
+
+/// Information about the circumstances under which a command was entered
+///
+/// More fields may be added in the future, so a `Context` can only be created
+/// by the console.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct Context {
+    /// The width and height of the terminal
+    pub terminal_size: (u16, u16),
+    /// The session the command was entered in
+    pub session: SessionId,
+    /// The user who entered the command, if its session
+    /// [logged in](crate::ConsoleBuilder::authenticate)
+    pub user: Option<String>,
+    /// The time at which the command was submitted
+    pub timestamp: SystemTime,
+    /// The way the input should be split into arguments
+    pub tokenizer: Tokenizer,
+    /// Where the command came from
+    pub origin: Origin,
+    /// What the command is allowed to do
+    pub role: Role,
+}
+
+impl Context {
+    pub(crate) fn new(session: SessionId, user: Option<String>) -> Self {
+        Context {
+            terminal_size: Terminal::new().terminal_size(),
+            session,
+            user,
+            timestamp: SystemTime::now(),
+            tokenizer: Tokenizer::default(),
+            origin: Origin::Terminal,
+            role: Role::Full,
+        }
+    }
+}
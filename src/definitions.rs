@@ -0,0 +1,174 @@
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+use crossterm::KeyEvent;
+
+/// How often the definitions file is checked for changes
+pub(crate) const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Aliases, key bindings, and scripted commands loaded from a file
+///
+/// Each line of the file is one of:
+/// - `alias NAME = COMMAND`
+/// - `bind KEY COMMAND`
+/// - `def NAME(PARAMS) { BODY }`, which may span several lines
+///
+/// Blank lines and lines starting with `#` are ignored.
+pub(crate) struct Definitions {
+    path: PathBuf,
+    /// The modification time of the file when it was last loaded
+    modified: Option<SystemTime>,
+    /// When the file is next checked for changes
+    pub next_check: Instant,
+    pub aliases: HashMap<String, String>,
+    pub bindings: Vec<(KeyEvent, String)>,
+    /// The sources of the scripted commands
+    pub scripts: Vec<String>,
+}
+
+impl Definitions {
+    /// Create `Definitions` for the file at the given path without loading it
+    pub fn new(path: PathBuf) -> Self {
+        Definitions {
+            path,
+            modified: None,
+            next_check: Instant::now(),
+            aliases: HashMap::new(),
+            bindings: Vec::new(),
+            scripts: Vec::new(),
+        }
+    }
+    /// Get the path of the definitions file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    /// Check if the file has changed since it was last loaded
+    pub fn changed(&mut self) -> bool {
+        self.next_check = Instant::now() + CHECK_INTERVAL;
+        let modified = fs::metadata(&self.path)
+            .and_then(|meta| meta.modified())
+            .ok();
+        modified != self.modified
+    }
+    /// Load the file, replacing the current definitions
+    ///
+    /// Returns messages for the lines that could not be understood
+    pub fn load(&mut self) -> io::Result<Vec<String>> {
+        self.modified = fs::metadata(&self.path)
+            .and_then(|meta| meta.modified())
+            .ok();
+        let text = fs::read_to_string(&self.path)?;
+        self.aliases.clear();
+        self.bindings.clear();
+        self.scripts.clear();
+        let mut errors = Vec::new();
+        let mut lines = text.lines().enumerate();
+        while let Some((i, line)) = lines.next() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (keyword, rest) = match line.find(char::is_whitespace) {
+                Some(end) => (&line[..end], line[end..].trim()),
+                None => (line, ""),
+            };
+            match keyword {
+                "alias" => match rest.find('=') {
+                    Some(eq) if !rest[..eq].trim().is_empty() => {
+                        let name = rest[..eq].trim().into();
+                        self.aliases.insert(name, rest[eq + 1..].trim().into());
+                    }
+                    _ => errors.push(format!("line {}: expected `alias NAME = COMMAND`", i + 1)),
+                },
+                "bind" => {
+                    let mut words = rest.splitn(2, char::is_whitespace);
+                    let key = words.next().and_then(parse_key);
+                    match (key, words.next().map(str::trim)) {
+                        (Some(key), Some(command)) if !command.is_empty() => {
+                            self.bindings.retain(|(bound, _)| *bound != key);
+                            self.bindings.push((key, command.into()));
+                        }
+                        (None, _) => errors.push(format!("line {}: unknown key", i + 1)),
+                        _ => errors.push(format!("line {}: expected `bind KEY COMMAND`", i + 1)),
+                    }
+                }
+                "def" => {
+                    // Collect lines until the braces are balanced
+                    let mut source = format!("fn {}", rest);
+                    let mut depth = brace_depth(&source);
+                    while depth > 0 {
+                        match lines.next() {
+                            Some((_, line)) => {
+                                source.push('\n');
+                                source.push_str(line);
+                                depth += brace_depth(line);
+                            }
+                            None => break,
+                        }
+                    }
+                    self.scripts.push(source);
+                }
+                _ => errors.push(format!("line {}: unknown definition `{}`", i + 1, keyword)),
+            }
+        }
+        Ok(errors)
+    }
+    /// Replace the command name at the start of an input if it is an alias
+    pub fn expand_alias(&self, input: &str) -> Option<String> {
+        let input = input.trim_start();
+        let end = input.find(char::is_whitespace).unwrap_or(input.len());
+        let command = self.aliases.get(&input[..end])?;
+        Some(format!("{}{}", command, &input[end..]))
+    }
+    /// Get the command bound to a key
+    pub fn binding(&self, key: &KeyEvent) -> Option<&str> {
+        self.bindings
+            .iter()
+            .find(|(bound, _)| bound == key)
+            .map(|(_, command)| command.as_str())
+    }
+}
+
+/// Get the change in brace depth over some text
+fn brace_depth(text: &str) -> isize {
+    text.chars()
+        .map(|c| match c {
+            '{' => 1,
+            '}' => -1,
+            _ => 0,
+        })
+        .sum()
+}
+
+/// Parse a key like `ctrl-t`, `alt-x`, or `f5`
+fn parse_key(key: &str) -> Option<KeyEvent> {
+    let lower = key.to_lowercase();
+    let single = |s: &str| {
+        let mut chars = s.chars();
+        chars.next().filter(|_| chars.next().is_none())
+    };
+    if let Some(c) = lower.strip_prefix("ctrl-").and_then(single) {
+        return Some(KeyEvent::Ctrl(c));
+    }
+    if let Some(c) = lower.strip_prefix("alt-").and_then(single) {
+        return Some(KeyEvent::Alt(c));
+    }
+    if let Some(n) = lower.strip_prefix('f').and_then(|n| n.parse().ok()) {
+        return Some(KeyEvent::F(n));
+    }
+    Some(match lower.as_str() {
+        "home" => KeyEvent::Home,
+        "end" => KeyEvent::End,
+        "pageup" => KeyEvent::PageUp,
+        "pagedown" => KeyEvent::PageDown,
+        "insert" => KeyEvent::Insert,
+        "delete" => KeyEvent::Delete,
+        "esc" => KeyEvent::Esc,
+        "backtab" => KeyEvent::BackTab,
+        _ => KeyEvent::Char(single(key)?),
+    })
+}
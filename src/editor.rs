@@ -0,0 +1,2154 @@
+use std::{
+    borrow::Cow,
+    cell::{Cell, RefCell},
+    collections::{HashMap, HashSet},
+    env, fs, mem,
+    ops::Range,
+    process,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{Receiver, RecvTimeoutError, Sender},
+        Arc, Mutex,
+    },
+    time::{Instant, SystemTime},
+};
+
+use colored::Colorize;
+use crossterm::{
+    ClearType, InputEvent, KeyEvent, MouseButton, MouseEvent, Terminal, TerminalInput,
+};
+
+use crate::*;
+
+/// The state of the line editor that runs on the console thread
+pub(crate) struct Editor<M, P, F> {
+    pub line: Arc<Mutex<Line>>,
+    pub modes: Arc<Mutex<Scoped<Vec<Mode<M>>>>>,
+    pub closed: Arc<AtomicBool>,
+    pub send: MessageSender<M>,
+    pub session: SessionId,
+    pub prompt: String,
+    pub processor: P,
+    pub process: F,
+    pub completer: Option<Box<dyn Completer + Send>>,
+    pub hinter: Option<Box<dyn Hinter + Send>>,
+    pub hints: bool,
+    pub inline_help: bool,
+    pub suggest: bool,
+    pub abbreviations: bool,
+    pub tokenizer: Tokenizer,
+    pub expand_paths: bool,
+    pub expand_vars: bool,
+    pub vars: Arc<Mutex<Scoped<HashMap<String, String>>>>,
+    /// The aliases defined with the `alias` built-in
+    pub aliases: Scoped<HashMap<String, String>>,
+    pub session_scopes: bool,
+    pub history_expansion: bool,
+    pub history: Vec<String>,
+    pub curr: Option<usize>,
+    pub history_prefix: String,
+    pub history_navigation: HistoryNavigation,
+    pub history_store: Option<Box<dyn HistoryStore>>,
+    pub menu: Option<Menu>,
+    pub kill_ring: KillRing,
+    pub last_action: LastAction,
+    pub clipboard: Clipboard,
+    pub search: Option<HistorySearch>,
+    pub heredocs: bool,
+    /// The block being captured, if any
+    pub heredoc: Option<Heredoc>,
+    /// The secret being read, if any
+    pub secret: Option<SecretRead>,
+    pub mouse: bool,
+    pub builtins: bool,
+    /// The latencies measured, if they are
+    pub latency: Option<Arc<Latency>>,
+    pub quit_commands: Vec<String>,
+    pub quit_keys: Vec<KeyEvent>,
+    pub detach_key: Option<KeyEvent>,
+    pub paste_key: KeyEvent,
+    /// The scrollback mark from when the terminal was detached, if it is
+    pub detached: Option<u64>,
+    pub quit_message: Arc<Mutex<Option<M>>>,
+    pub render_errors: bool,
+    pub scrollback: Option<Arc<Mutex<Scrollback>>>,
+    pub log_level: Arc<Mutex<Level>>,
+    pub watch: Option<Watch>,
+    pub repeat: Option<Repeat>,
+    pub scheduler: Arc<Mutex<Scheduler>>,
+    pub timers: Arc<Mutex<Timers<M>>>,
+    pub events: Receiver<Event>,
+    pub event_send: Sender<Event>,
+    pub shell_commands: bool,
+    pub on_shell_exit: Option<ShellExitHandler>,
+    /// Whether Ctrl-X was the last key pressed
+    pub ctrl_x: bool,
+    /// The numeric argument typed with Alt and the digits, if any
+    pub numeric_arg: Option<usize>,
+    pub macros: Macros,
+    /// The values entered for each command argument, if they are remembered
+    pub arg_history: Option<ArgHistory>,
+    pub failed_commands: FailedCommands,
+    /// The lines whose commands failed the last time they were run
+    pub failed: HashSet<String>,
+    pub search_case: CaseMatching,
+    pub completion_case: Option<CaseMatching>,
+    pub command_case: CaseMatching,
+    pub plugins: Plugins,
+    pub definitions: Option<Definitions>,
+    pub sources: Vec<Box<dyn InputSource>>,
+    /// The number of sources that have not run out of input
+    pub open_sources: usize,
+    /// The origin of the input being handled
+    pub origin: Origin,
+    /// What the input being handled is allowed to do
+    pub role: Role,
+    /// The user who entered the input being handled, if they logged in
+    pub user: Option<String>,
+    /// The commands that restricted input may run, if they are limited
+    pub restricted_commands: Vec<String>,
+    pub sinks: Sinks,
+    /// The command being run
+    pub input: Option<String>,
+    /// When the command being run was submitted
+    pub submitted: SystemTime,
+    /// The number of messages sent
+    pub sequence: Cell<u64>,
+    /// The cancel tokens of the messages that handlers may still be running
+    pub cancel_tokens: RefCell<Vec<(Origin, CommandHandle)>>,
+    pub trim_input: bool,
+    pub empty_input: EmptyInput,
+    pub tick_message: Arc<Mutex<Option<TickMessage<M>>>>,
+    pub case: CaseNormalization,
+    /// Changes the input when Up or Down is pressed instead of moving through
+    /// the history
+    pub stepper: Option<Stepper>,
+    pub command_timeout: Option<Duration>,
+    pub command_timeouts: HashMap<String, Duration>,
+    pub cancel_on_timeout: bool,
+    pub pending_indicator: bool,
+    pub auto_pair: bool,
+    pub reveal_last_char: bool,
+    /// When the last character typed in masked input is masked again
+    pub reveal_until: Option<Instant>,
+    pub title: Option<TitleFn>,
+    /// The last title given by the title function
+    pub shown_title: Option<String>,
+    /// When the title function is next called
+    pub next_title: Instant,
+    /// The commands being watched for timeouts
+    pub timeouts: RefCell<Vec<PendingCommand>>,
+    /// The number of outputs printed by the responder for each origin
+    pub outputs: Arc<OutputCounts>,
+    /// The sender for jobs to parse and process on worker threads, if any
+    pub pool: Option<Sender<Job>>,
+    pub spawner: Spawner,
+    #[cfg(feature = "scripting")]
+    pub scripts: Option<Scripts>,
+}
+
+/// A function that creates the message sent when empty input is submitted
+pub(crate) type TickMessage<M> = Box<dyn Fn() -> M + Send>;
+
+/// A function that gets the input after pressing Up, if the flag is set, or
+/// Down
+pub(crate) type Stepper = Box<dyn Fn(&str, bool) -> String + Send>;
+
+/// Something that wakes up the editor
+pub(crate) enum Event {
+    /// Input from a source, along with the user who entered it
+    Input(Origin, Role, Option<String>, Input),
+    /// An input source was added
+    Opened,
+    /// The app changed something that the editor should check
+    Wake,
+    /// An input source ran out of input
+    Eof(Origin),
+    /// A worker's process function returned `None`
+    Quit,
+    /// A request for the completion of an input
+    Complete(String, usize, Sender<Completion>),
+    /// A request for the lines in the history
+    History(Sender<Vec<String>>),
+    /// A request for the history and aliases to save in a snapshot
+    Snapshot(Sender<SnapshotReply>),
+    /// Replace the history and add aliases from a snapshot
+    Restore(Vec<String>, HashMap<String, String>),
+    /// A request to read a secret with a prompt
+    ReadSecret(String, Sender<Secret>),
+    /// Whether the command on a line succeeded
+    Outcome(String, bool),
+}
+
+/// The history and aliases saved in a snapshot
+pub(crate) type SnapshotReply = (Vec<String>, HashMap<String, String>);
+
+/// Read an input source on its own thread, sending its input as events
+pub(crate) fn spawn_source(
+    spawner: &Spawner,
+    mut source: Box<dyn InputSource>,
+    events: Sender<Event>,
+) {
+    spawner.spawn(move || {
+        while let Some(input) = source.next_input() {
+            let event = Event::Input(source.origin(), source.role(), source.user(), input);
+            if events.send(event).is_err() {
+                return;
+            }
+        }
+        let _ = events.send(Event::Eof(source.origin()));
+    });
+}
+
+/// How often the number of unfinished messages is checked while there are any
+const PENDING_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How often the terminal is checked for a resize while output is wrapped
+const RESIZE_INTERVAL: Duration = Duration::from_millis(250);
+
+/// The largest number of times a key can be repeated by a numeric argument
+const MAX_NUMERIC_ARG: usize = 1000;
+
+/// How often the title function is called
+const TITLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long the last character typed in masked input is shown
+const REVEAL_DURATION: Duration = Duration::from_secs(1);
+
+/// The prompt shown while searching the history
+const SEARCH_PROMPT: &str = "search: ";
+
+/// The prompt shown while capturing a block of lines
+const HEREDOC_PROMPT: &str = "... ";
+
+/// Get the number of rows to use for lists shown below the input line
+fn below_rows() -> usize {
+    terminal_height().saturating_sub(3).clamp(1, 10)
+}
+
+/// The kind of editing action performed by the last key press
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub(crate) enum LastAction {
+    #[default]
+    Other,
+    Kill,
+    /// Text was yanked into the given byte range
+    Yank(Range<usize>),
+}
+
+impl<M, P, F> Editor<M, P, F>
+where
+    P: CommandProcessor,
+    F: Fn(P::Parsed) -> Option<M>,
+{
+    /// Read and handle input until the console is closed
+    pub fn run(mut self) -> CloseReason {
+        // Read each source on its own thread so that the editor can wake up
+        // to run commands while waiting for input
+        let sources = mem::take(&mut self.sources);
+        self.open_sources = sources.len();
+        for source in sources {
+            spawn_source(&self.spawner, source, self.event_send.clone());
+        }
+        if self.mouse {
+            let _ = TerminalInput::new().enable_mouse_mode();
+        }
+        if let Some(store) = &mut self.history_store {
+            self.history = store.load();
+            self.failed = store.load_failed().into_iter().collect();
+        }
+        self.macros = Macros::from_history(&self.history);
+        self.reload_arg_history();
+        self.reload_definitions(false);
+        self.update_title();
+        self.line.lock().unwrap().draw();
+        let reason = loop {
+            let event = match self.next_deadline() {
+                Some(deadline) => {
+                    let timeout = deadline.saturating_duration_since(Instant::now());
+                    self.events.recv_timeout(timeout)
+                }
+                None => self
+                    .events
+                    .recv()
+                    .map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            if self.closed.load(Ordering::Relaxed) {
+                break CloseReason::AppClosed;
+            }
+            if let Ok(Event::Input(origin, role, user, _)) = &event {
+                self.origin = origin.clone();
+                self.role = *role;
+                self.user = user.clone();
+            }
+            let open = match event {
+                Ok(Event::Input(_, _, _, Input::Event(InputEvent::Keyboard(key_event)))) => {
+                    let received = Instant::now();
+                    let open = self.handle_key(key_event);
+                    if let Some(latency) = &self.latency {
+                        latency.keypress.lock().unwrap().push(received.elapsed());
+                    }
+                    open
+                }
+                Ok(Event::Input(_, _, _, Input::Event(InputEvent::Mouse(mouse_event)))) => {
+                    self.handle_mouse(mouse_event)
+                }
+                Ok(Event::Input(_, _, _, Input::Line(input))) => self.submit_line(input),
+                Ok(Event::Input(..)) | Ok(Event::Wake) => true,
+                Ok(Event::Opened) => {
+                    self.open_sources += 1;
+                    true
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    self.origin = Origin::Console;
+                    self.role = Role::Full;
+                    self.user = None;
+                    self.run_due()
+                }
+                Ok(Event::Complete(input, cursor, reply)) => {
+                    let _ = reply.send(self.completion(&input, cursor));
+                    true
+                }
+                Ok(Event::History(reply)) => {
+                    let _ = reply.send(self.history.clone());
+                    true
+                }
+                Ok(Event::Snapshot(reply)) => {
+                    let aliases = self
+                        .definitions
+                        .as_ref()
+                        .map(|definitions| definitions.aliases.clone())
+                        .unwrap_or_default();
+                    let _ = reply.send((self.history.clone(), aliases));
+                    true
+                }
+                Ok(Event::Restore(history, aliases)) => {
+                    self.history = history;
+                    self.curr = None;
+                    self.macros = Macros::from_history(&self.history);
+                    self.reload_arg_history();
+                    if let Some(definitions) = &mut self.definitions {
+                        definitions.aliases.extend(aliases);
+                    }
+                    true
+                }
+                Ok(Event::ReadSecret(prompt, reply)) => {
+                    self.start_secret(prompt, reply);
+                    true
+                }
+                Ok(Event::Outcome(line, success)) => {
+                    self.record_outcome(line, success);
+                    true
+                }
+                Ok(Event::Quit) => {
+                    self.closed.store(true, Ordering::Relaxed);
+                    false
+                }
+                Ok(Event::Eof(origin)) => {
+                    self.cancel_commands(|from| *from == origin);
+                    self.open_sources -= 1;
+                    if self.open_sources == 0 {
+                        break CloseReason::Eof;
+                    }
+                    true
+                }
+                Err(RecvTimeoutError::Disconnected) => break CloseReason::Eof,
+            };
+            if !open {
+                break CloseReason::UserQuit;
+            }
+            self.update_pending();
+        };
+        if self.mouse {
+            let _ = TerminalInput::new().disable_mouse_mode();
+        }
+        let mut line = self.line.lock().unwrap();
+        line.print_frame(Instant::now(), true);
+        if line.overwrite {
+            line.set_overwrite(false);
+        }
+        if self.secret.take().is_some() {
+            zero(&mut line.input);
+        }
+        drop(line);
+        reason
+    }
+    /// Submit a whole line from an input source, keeping the line being
+    /// edited
+    ///
+    /// Returns `false` if the console was closed
+    fn submit_line(&mut self, input: String) -> bool {
+        let line = self.line.lock().unwrap();
+        if !line.detached {
+            line.clear();
+            println!("{}{}", line.prompt, input);
+            line.draw();
+        }
+        if let Some(reason) = &line.locked {
+            let message = format!("input is locked: {}", reason);
+            drop(line);
+            write_to_sinks(&self.sinks, Some(&self.origin), &message);
+            self.reject(Some(message));
+            return true;
+        }
+        drop(line);
+        self.submit(input)
+    }
+    /// Handle a mouse event
+    ///
+    /// Returns `false` if the console was closed
+    fn handle_mouse(&mut self, mouse_event: MouseEvent) -> bool {
+        match mouse_event {
+            // The wheel works like the arrow keys, going through the history
+            // or scrolling the pager
+            MouseEvent::Press(MouseButton::WheelUp, ..) => self.handle_key(KeyEvent::Up),
+            MouseEvent::Press(MouseButton::WheelDown, ..) => self.handle_key(KeyEvent::Down),
+            MouseEvent::Press(MouseButton::Left, column, row) => {
+                let mut line = self.line.lock().unwrap();
+                if line.pager.is_some() || self.detached.is_some() {
+                    return true;
+                }
+                // Only a click on the input line moves the cursor
+                if cursor_row() != Some(row) {
+                    return true;
+                }
+                let cursor = line.cursor_at_column(column.saturating_sub(1) as usize);
+                line.anchor = None;
+                line.cursor = cursor;
+                line.redraw();
+                true
+            }
+            _ => true,
+        }
+    }
+    /// Handle a key press
+    ///
+    /// Returns `false` if the console was closed
+    fn handle_key(&mut self, key_event: KeyEvent) -> bool {
+        let shared_line = Arc::clone(&self.line);
+        let mut line = shared_line.lock().unwrap();
+        // Only the detach key does anything while detached
+        if let Some(mark) = self.detached {
+            if self.detach_key.as_ref() == Some(&key_event) {
+                self.attach(&mut line, mark);
+            }
+            return true;
+        }
+        if self.detach_key.as_ref() == Some(&key_event) && line.pager.is_none() {
+            self.detach(&mut line);
+            return true;
+        }
+        // Nothing can be typed while the input is locked
+        if line.locked.is_some() {
+            return true;
+        }
+        if self.secret.is_some() {
+            self.handle_secret_key(&mut line, key_event);
+            return true;
+        }
+        // Ctrl-C cancels a repeated command
+        if key_event == KeyEvent::Ctrl('c') && self.repeat.take().is_some() {
+            line.clear();
+            println!("repeat cancelled");
+            line.draw();
+            return true;
+        }
+        // Ctrl-C also cancels the commands that are still being handled
+        if key_event == KeyEvent::Ctrl('c')
+            && self.cancel_commands(|origin| matches!(origin, Origin::Terminal | Origin::Console))
+        {
+            line.clear();
+            println!("command cancelled");
+            line.draw();
+            return true;
+        }
+        // Any key stops watching a command
+        if self.watch.take().is_some() {
+            line.redraw();
+            return true;
+        }
+        // Handle keys for the pager
+        if let Some(pager) = &mut line.pager {
+            match pager.handle_key(key_event, terminal_height()) {
+                PagerAction::Continue => {}
+                PagerAction::Close => {
+                    line.pager = None;
+                    line.clear_screen();
+                    return true;
+                }
+                PagerAction::Copy(text) => {
+                    self.clipboard.set(&text);
+                    pager.set_message("copied to the clipboard");
+                }
+                PagerAction::Save { path, text } => match fs::write(&path, text + "\n") {
+                    Ok(()) => pager.set_message(format!("saved to {}", path)),
+                    Err(e) => pager.set_message(format!("failed to save to {}: {}", path, e)),
+                },
+            }
+            line.draw();
+            return true;
+        }
+        // Collect a numeric argument for repeating the next key
+        if let KeyEvent::Alt(c @ '0'..='9') = key_event {
+            if self.search.is_none() {
+                let digit = c as usize - '0' as usize;
+                let arg = self.numeric_arg.unwrap_or(0) * 10 + digit;
+                self.numeric_arg = Some(arg.min(MAX_NUMERIC_ARG));
+                return true;
+            }
+        }
+        if let Some(count) = self.numeric_arg.take() {
+            if key_event != KeyEvent::Char('\n') {
+                drop(line);
+                for _ in 0..count {
+                    // Repeating never closes the console
+                    if self.quit_keys.contains(&key_event)
+                        && self.line.lock().unwrap().input.is_empty()
+                    {
+                        break;
+                    }
+                    if !self.handle_key(key_event.clone()) {
+                        return false;
+                    }
+                }
+                return true;
+            }
+        }
+        if line.input.is_empty() && self.quit_keys.contains(&key_event) {
+            println!();
+            drop(line);
+            self.quit();
+            return false;
+        }
+        // Handle keys for the history search
+        if let Some(mut search) = self.search.take() {
+            if self.handle_search_key(&mut line, &mut search, key_event) {
+                self.search = Some(search);
+            }
+            return true;
+        }
+        // Handle keys for the completion menu
+        if let Some(mut menu) = self.menu.take() {
+            match key_event {
+                KeyEvent::Char('\t') | KeyEvent::BackTab => {
+                    if let KeyEvent::BackTab = key_event {
+                        menu.prev();
+                    } else {
+                        menu.next();
+                    }
+                    let (input, cursor) = menu.apply();
+                    line.cursor = cursor;
+                    line.below = Some(menu.render(terminal_width(), below_rows()));
+                    line.set(input);
+                    self.menu = Some(menu);
+                    return true;
+                }
+                KeyEvent::Esc => {
+                    menu.selected = None;
+                    let (input, cursor) = menu.apply();
+                    line.cursor = cursor;
+                    line.below = None;
+                    line.set(input);
+                    self.update_hint(&mut line);
+                    return true;
+                }
+                KeyEvent::Char('\n') => {
+                    line.below = None;
+                    line.redraw();
+                    self.update_hint(&mut line);
+                    return true;
+                }
+                _ => {
+                    line.below = None;
+                    line.redraw();
+                }
+            }
+        }
+        // Handle keys that act on the selection
+        if line.selection().is_some() {
+            match key_event {
+                KeyEvent::Backspace | KeyEvent::Delete => {
+                    line.take_selection();
+                    line.redraw();
+                    self.update_hint(&mut line);
+                    return true;
+                }
+                KeyEvent::Ctrl('w') => {
+                    // Cut the selection
+                    if let Some(killed) = line.take_selection() {
+                        self.kill_ring.kill(killed, false, false);
+                    }
+                    line.redraw();
+                    self.last_action = LastAction::Kill;
+                    self.update_hint(&mut line);
+                    return true;
+                }
+                KeyEvent::Alt('w') => {
+                    // Copy the selection
+                    if let Some(range) = line.selection() {
+                        self.clipboard.set(&line.input[range]);
+                    }
+                    line.anchor = None;
+                    line.redraw();
+                    return true;
+                }
+                KeyEvent::Char(c) if c != '\n' && c != '\t' => {
+                    // Typing replaces the selection
+                    line.take_selection();
+                }
+                _ => {}
+            }
+        }
+        // Handle the key after Ctrl-X
+        if mem::take(&mut self.ctrl_x) {
+            if let KeyEvent::Ctrl('e') = key_event {
+                self.edit_externally(&mut line);
+                self.update_hint(&mut line);
+                return true;
+            }
+        }
+        // Run the command bound to the key
+        let binding = self
+            .definitions
+            .as_ref()
+            .and_then(|definitions| definitions.binding(&key_event))
+            .map(String::from);
+        if let Some(command) = binding {
+            line.clear();
+            println!("{}", command.dimmed());
+            line.draw();
+            drop(line);
+            let context = self.context();
+            return self.run_input(command, &context);
+        }
+        let mut action = LastAction::Other;
+        let before = line.max_input.map(|_| (line.input.clone(), line.cursor));
+        let was_revealed = line.revealed.take().is_some();
+        match key_event {
+            _ if key_event == self.paste_key => {
+                // Paste from the clipboard
+                if let Some(text) = self.clipboard.get() {
+                    // Only the first line is pasted so it isn't submitted,
+                    // and escape sequences and control characters are
+                    // removed so that they can't mess up the terminal
+                    let text = text.lines().next().unwrap_or_default();
+                    let text: String = strip_ansi(text)
+                        .chars()
+                        .filter(|c| !c.is_control())
+                        .collect();
+                    let cursor = line.cursor;
+                    line.input.insert_str(cursor, &text);
+                    line.cursor += text.len();
+                    line.redraw();
+                }
+            }
+            KeyEvent::ShiftLeft | KeyEvent::ShiftRight => {
+                // Extend the selection
+                if line.anchor.is_none() {
+                    line.anchor = Some(line.cursor);
+                }
+                line.cursor = if let KeyEvent::ShiftLeft = key_event {
+                    prev_char(&line.input, line.cursor)
+                } else {
+                    next_char(&line.input, line.cursor)
+                };
+                line.redraw();
+            }
+            KeyEvent::Char('\t') => self.complete(&mut line),
+            KeyEvent::Backspace
+                if self.auto_pair
+                    && !line.mask
+                    && empty_pair(&line.input, line.cursor).is_some() =>
+            {
+                // Remove both characters of an empty pair
+                let range = empty_pair(&line.input, line.cursor).unwrap();
+                line.input.replace_range(range.clone(), "");
+                line.cursor = range.start;
+                line.redraw();
+            }
+            KeyEvent::Backspace => {
+                let removed = line.remove_prev_char();
+                if removed {
+                    line.redraw();
+                }
+            }
+            KeyEvent::Delete | KeyEvent::Ctrl('d') => {
+                let removed = line.remove_next_char();
+                if removed {
+                    line.redraw();
+                }
+            }
+            KeyEvent::Up | KeyEvent::Down if self.stepper.is_some() => {
+                let stepper = self.stepper.as_ref().unwrap();
+                let input = stepper(&line.input, key_event == KeyEvent::Up);
+                line.cursor = input.len();
+                line.set(input);
+            }
+            KeyEvent::Up => self.history_up(&mut line),
+            KeyEvent::Down => self.history_down(&mut line),
+            KeyEvent::Left if line.cursor > 0 => {
+                line.cursor = prev_char(&line.input, line.cursor);
+                line.redraw();
+            }
+            KeyEvent::Right if line.cursor < line.input.len() => {
+                line.cursor = next_char(&line.input, line.cursor);
+                line.redraw();
+            }
+            KeyEvent::Ctrl('k') => {
+                // Kill to the end of the line
+                let cursor = line.cursor;
+                let killed = line.input.split_off(cursor);
+                self.kill(killed, false);
+                line.redraw();
+                action = LastAction::Kill;
+            }
+            KeyEvent::Ctrl('w') => {
+                // Kill the previous word
+                let cursor = line.cursor;
+                let start = prev_word_start(&line.input, cursor);
+                let killed = line.input.drain(start..cursor).collect();
+                self.kill(killed, true);
+                line.cursor = start;
+                line.redraw();
+                action = LastAction::Kill;
+            }
+            KeyEvent::Alt('d') => {
+                // Kill the next word
+                let cursor = line.cursor;
+                let end = next_word_end(&line.input, cursor);
+                let killed = line.input.drain(cursor..end).collect();
+                self.kill(killed, false);
+                line.redraw();
+                action = LastAction::Kill;
+            }
+            KeyEvent::Ctrl('t') => {
+                // Transpose characters
+                let cursor = line.cursor;
+                if let Some(cursor) = transpose_chars(&mut line.input, cursor) {
+                    line.cursor = cursor;
+                    line.redraw();
+                }
+            }
+            KeyEvent::Alt('t') => {
+                // Transpose words
+                let cursor = line.cursor;
+                if let Some(cursor) = transpose_words(&mut line.input, cursor) {
+                    line.cursor = cursor;
+                    line.redraw();
+                }
+            }
+            KeyEvent::Ctrl('y') => {
+                // Yank the most recently killed text
+                if let Some(text) = self.kill_ring.yank() {
+                    let cursor = line.cursor;
+                    line.input.insert_str(cursor, text);
+                    line.cursor += text.len();
+                    line.redraw();
+                    action = LastAction::Yank(cursor..line.cursor);
+                }
+            }
+            KeyEvent::Alt('y') => {
+                // Replace the yanked text with older killed text
+                if let LastAction::Yank(range) = self.last_action.clone() {
+                    if let Some(text) = self.kill_ring.yank_pop() {
+                        line.input.replace_range(range.clone(), text);
+                        line.cursor = range.start + text.len();
+                        line.redraw();
+                        action = LastAction::Yank(range.start..line.cursor);
+                    }
+                }
+            }
+            KeyEvent::Ctrl('l') => line.clear_screen(),
+            KeyEvent::Ctrl('x') => self.ctrl_x = true,
+            KeyEvent::Ctrl('r') => {
+                // Start searching the history
+                self.reload_history();
+                let search = HistorySearch::new(
+                    line.input.clone(),
+                    line.prompt.clone(),
+                    &self.history,
+                    self.search_case,
+                    &|entry| self.skipped(entry),
+                );
+                line.prompt = SEARCH_PROMPT.into();
+                line.input.clear();
+                line.cursor = 0;
+                line.below =
+                    Some(search.render(&self.history, below_rows(), &|entry| self.marked(entry)));
+                line.redraw();
+                self.search = Some(search);
+                return true;
+            }
+            KeyEvent::PageUp | KeyEvent::Ctrl('f') if self.scrollback.is_some() => {
+                // View the scrollback
+                let text = self.scrollback.as_ref().unwrap().lock().unwrap().text();
+                let height = terminal_height();
+                let mut pager = Pager::new(&text, terminal_width());
+                pager.scroll_to_bottom(height);
+                if let KeyEvent::PageUp = key_event {
+                    pager.handle_key(KeyEvent::PageUp, height);
+                } else {
+                    pager.start_search(true);
+                }
+                line.pager = Some(pager);
+                line.draw();
+                return true;
+            }
+            KeyEvent::Alt('w') => {
+                // Copy the line to the clipboard
+                self.clipboard.set(&line.input);
+            }
+            KeyEvent::Char('\n') => {
+                line.below = None;
+                line.anchor = None;
+                line.failed = false;
+                line.redraw();
+                println!();
+                // Submit
+                let input = mem::take(&mut line.input);
+                line.cursor = 0;
+                drop(line);
+                return self.submit(input);
+            }
+            KeyEvent::Char('?') if self.inline_help && line.cursor == line.input.len() => {
+                self.show_help(&mut line)
+            }
+            KeyEvent::Insert => {
+                let overwrite = !line.overwrite;
+                line.set_overwrite(overwrite);
+            }
+            KeyEvent::Char(c) if self.auto_pair && !line.overwrite && !line.mask => {
+                // Add character, pairing brackets and quotes
+                let cursor = line.cursor;
+                line.cursor = match insert_paired(&mut line.input, cursor, c) {
+                    Some(cursor) => cursor,
+                    None => {
+                        line.input.insert(cursor, c);
+                        cursor + c.len_utf8()
+                    }
+                };
+                line.redraw();
+            }
+            KeyEvent::Char(c) => {
+                // Add character
+                let cursor = line.cursor;
+                if line.overwrite {
+                    let end = next_char(&line.input, cursor);
+                    line.input.replace_range(cursor..end, "");
+                }
+                line.input.insert(cursor, c);
+                line.cursor += c.len_utf8();
+                if line.mask && self.reveal_last_char {
+                    line.revealed = Some(cursor);
+                    self.reveal_until = Some(Instant::now() + REVEAL_DURATION);
+                }
+                line.redraw();
+            }
+            _ => {}
+        }
+        // Refuse edits that make the input too long
+        if let (Some(max), Some((input, cursor))) = (line.max_input, before) {
+            if line.input.chars().count() > max {
+                line.input = input;
+                line.cursor = cursor;
+                line.revealed = None;
+                print!("\x07");
+                line.redraw();
+                action = LastAction::Other;
+            }
+        }
+        if was_revealed && line.revealed.is_none() {
+            line.redraw();
+        }
+        match key_event {
+            KeyEvent::ShiftLeft | KeyEvent::ShiftRight => {}
+            _ => {
+                if line.anchor.take().is_some() {
+                    line.redraw();
+                }
+            }
+        }
+        self.last_action = action;
+        if self.menu.is_none() {
+            self.update_hint(&mut line);
+        }
+        true
+    }
+    /// Start reading a secret, unless one is already being read
+    fn start_secret(&mut self, prompt: String, reply: Sender<Secret>) {
+        if self.secret.is_some() {
+            return;
+        }
+        let mut line = self.line.lock().unwrap();
+        line.clear();
+        // Leave the history search and the completion menu
+        if let Some(search) = self.search.take() {
+            line.prompt = search.prompt;
+            line.input = search.original;
+            line.cursor = line.input.len();
+        }
+        self.menu = None;
+        line.below = None;
+        line.anchor = None;
+        line.revealed = None;
+        // Reserve room up front so that typing does not leave copies behind
+        let input = mem::replace(&mut line.input, String::with_capacity(128));
+        self.secret = Some(SecretRead {
+            prompt: mem::replace(&mut line.prompt, prompt),
+            mask: line.mask,
+            input: (input, line.cursor),
+            reply,
+        });
+        line.mask = true;
+        line.secret = true;
+        line.cursor = 0;
+        line.draw();
+    }
+    /// Handle a key press while reading a secret
+    ///
+    /// Only keys that edit the line work, so that the secret is not seen by
+    /// anything else.
+    fn handle_secret_key(&mut self, line: &mut Line, key: KeyEvent) {
+        let cursor = line.cursor;
+        match key {
+            KeyEvent::Char('\n') | KeyEvent::Esc | KeyEvent::Ctrl('c') => {
+                line.redraw();
+                println!();
+                self.finish_secret(line, key == KeyEvent::Char('\n'));
+                return;
+            }
+            KeyEvent::Char(c) if !c.is_control() => {
+                line.input.insert(cursor, c);
+                line.cursor += c.len_utf8();
+            }
+            KeyEvent::Backspace => {
+                let start = prev_char(&line.input, cursor);
+                line.input.replace_range(start..cursor, "");
+                line.cursor = start;
+            }
+            KeyEvent::Delete | KeyEvent::Ctrl('d') => {
+                let end = next_char(&line.input, cursor);
+                line.input.replace_range(cursor..end, "");
+            }
+            KeyEvent::Left => line.cursor = prev_char(&line.input, cursor),
+            KeyEvent::Right => line.cursor = next_char(&line.input, cursor),
+            KeyEvent::Home | KeyEvent::Ctrl('a') => line.cursor = 0,
+            KeyEvent::End | KeyEvent::Ctrl('e') => line.cursor = line.input.len(),
+            _ => return,
+        }
+        line.redraw();
+    }
+    /// Stop reading a secret, sending it to the app if it was submitted
+    fn finish_secret(&mut self, line: &mut Line, submit: bool) {
+        let read = self.secret.take().unwrap();
+        let (input, cursor) = read.input;
+        let mut secret = mem::replace(&mut line.input, input);
+        line.cursor = cursor;
+        line.prompt = read.prompt;
+        line.mask = read.mask;
+        line.secret = false;
+        if submit {
+            let _ = read.reply.send(Secret::new(secret));
+        } else {
+            zero(&mut secret);
+        }
+        line.draw();
+    }
+    /// Handle a key press while searching the history
+    ///
+    /// Returns `false` if the search ended
+    fn handle_search_key(
+        &mut self,
+        line: &mut Line,
+        search: &mut HistorySearch,
+        key: KeyEvent,
+    ) -> bool {
+        match key {
+            KeyEvent::Char('\n') | KeyEvent::Esc | KeyEvent::Ctrl('c') | KeyEvent::Ctrl('g') => {
+                // Accept the selected entry or cancel
+                let input = match (key, search.selected_entry()) {
+                    (KeyEvent::Char('\n'), Some(i)) => self.history[i].clone(),
+                    _ => search.original.clone(),
+                };
+                line.prompt = search.prompt.clone();
+                line.below = None;
+                line.cursor = input.len();
+                line.set(input);
+                return false;
+            }
+            KeyEvent::Up | KeyEvent::Ctrl('p') => search.prev(),
+            KeyEvent::Down | KeyEvent::Ctrl('n') | KeyEvent::Ctrl('r') => search.next(),
+            KeyEvent::Backspace => {
+                search.query.pop();
+                search.update(&self.history, &|entry| self.skipped(entry));
+            }
+            KeyEvent::Char(c) if c != '\t' => {
+                search.query.push(c);
+                search.update(&self.history, &|entry| self.skipped(entry));
+            }
+            _ => return true,
+        }
+        line.input = search.query.clone();
+        line.cursor = line.input.len();
+        line.below = Some(search.render(&self.history, below_rows(), &|entry| self.marked(entry)));
+        line.redraw();
+        true
+    }
+    /// Load the definitions file if it has changed
+    fn reload_definitions(&mut self, announce: bool) {
+        let definitions = match &mut self.definitions {
+            Some(definitions) => definitions,
+            None => return,
+        };
+        if !definitions.changed() {
+            return;
+        }
+        let path = definitions.path().display().to_string();
+        let mut messages = match definitions.load() {
+            Ok(errors) => errors,
+            Err(e) => vec![format!("failed to load {}: {}", path, e)],
+        };
+        #[cfg(feature = "scripting")]
+        match &mut self.scripts {
+            Some(scripts) => {
+                for source in &definitions.scripts {
+                    if let Err(e) = scripts.define(source) {
+                        messages.push(e);
+                    }
+                }
+            }
+            None if !definitions.scripts.is_empty() => {
+                messages.push("scripted commands are ignored because scripting is disabled".into())
+            }
+            None => {}
+        }
+        #[cfg(not(feature = "scripting"))]
+        if !definitions.scripts.is_empty() {
+            messages.push("scripted commands require the `scripting` feature".into());
+        }
+        if !announce && messages.is_empty() {
+            return;
+        }
+        let line = self.line.lock().unwrap();
+        line.clear();
+        if announce {
+            println!("{}", format!("reloaded {}", path).dimmed());
+        }
+        for message in messages {
+            println!("{}", message);
+        }
+        line.draw();
+    }
+    /// Edit the input in the user's editor, replacing it with the result
+    ///
+    /// The editor is taken from `VISUAL` or `EDITOR`.
+    fn edit_externally(&mut self, line: &mut Line) {
+        let editor = env::var("VISUAL")
+            .or_else(|_| env::var("EDITOR"))
+            .unwrap_or_else(|_| if cfg!(windows) { "notepad" } else { "vi" }.into());
+        let path = env::temp_dir().join(format!("encore-{}-{}.txt", process::id(), self.session.0));
+        let edited = fs::write(&path, &line.input).and_then(|_| {
+            let mut words = editor.split_whitespace();
+            let program = words.next().unwrap_or("vi");
+            line.clear();
+            let pause = TerminalPause::new();
+            let status = process::Command::new(program)
+                .args(words)
+                .arg(&path)
+                .status();
+            drop(pause);
+            let status = status?;
+            if status.success() {
+                fs::read_to_string(&path).map(Some)
+            } else {
+                Ok(None)
+            }
+        });
+        let _ = fs::remove_file(&path);
+        match edited {
+            Ok(Some(edited)) => {
+                // The input is a single line
+                let input = edited.lines().collect::<Vec<_>>().join(" ");
+                line.input = input.trim().into();
+                line.cursor = line.input.len();
+            }
+            Ok(None) => {}
+            Err(e) => println!("failed to run `{}`: {}", editor, e),
+        }
+        line.anchor = None;
+        line.redraw();
+    }
+    /// Pick up entries that other consoles have added to the history store
+    fn reload_history(&mut self) {
+        if let Some(history) = self.history_store.as_mut().and_then(|store| store.reload()) {
+            self.history = history;
+        }
+    }
+    /// Move to the previous matching history entry
+    fn history_up(&mut self, line: &mut Line) {
+        let start = match self.curr {
+            Some(curr) => curr,
+            None => {
+                self.reload_history();
+                self.history_prefix = match self.history_navigation {
+                    HistoryNavigation::Plain => String::new(),
+                    HistoryNavigation::Prefix => line.input.clone(),
+                };
+                self.history.len()
+            }
+        };
+        let prefix = &self.history_prefix;
+        let found = self.history[..start].iter().rposition(|entry| {
+            entry.starts_with(prefix.as_str()) && *entry != line.input && !self.skipped(entry)
+        });
+        if let Some(i) = found {
+            self.curr = Some(i);
+            let new_input = self.history[i].clone();
+            line.failed = self.marked(&new_input);
+            line.cursor = new_input.len();
+            line.set(new_input);
+        }
+    }
+    /// Move to the next matching history entry, or back to the original input
+    fn history_down(&mut self, line: &mut Line) {
+        let curr = match self.curr {
+            Some(curr) => curr,
+            None => return,
+        };
+        let prefix = &self.history_prefix;
+        let found = self.history[curr + 1..]
+            .iter()
+            .position(|entry| {
+                entry.starts_with(prefix.as_str()) && *entry != line.input && !self.skipped(entry)
+            })
+            .map(|i| curr + 1 + i);
+        let new_input = match found {
+            Some(i) => {
+                self.curr = Some(i);
+                self.history[i].clone()
+            }
+            None => {
+                self.curr = None;
+                prefix.clone()
+            }
+        };
+        line.failed = self.marked(&new_input);
+        line.cursor = new_input.len();
+        line.set(new_input);
+    }
+    /// Check if a history entry is left out when moving through or searching
+    /// the history
+    fn skipped(&self, entry: &str) -> bool {
+        self.failed_commands == FailedCommands::Skip && self.failed.contains(entry)
+    }
+    /// Check if a history entry is marked as failed when moving through or
+    /// searching the history
+    fn marked(&self, entry: &str) -> bool {
+        self.failed_commands == FailedCommands::Mark && self.failed.contains(entry)
+    }
+    /// Record whether the last run of a command succeeded
+    fn record_outcome(&mut self, line: String, success: bool) {
+        if let Some(store) = &mut self.history_store {
+            store.set_exit_status(&line, self.session, if success { 0 } else { 1 });
+        }
+        if success {
+            self.failed.remove(&line);
+        } else {
+            self.failed.insert(line);
+        }
+    }
+    /// Add killed text to the kill ring, merging it with the previous kill
+    /// if the last action was also a kill
+    fn kill(&mut self, text: String, prepend: bool) {
+        let merge = self.last_action == LastAction::Kill;
+        self.kill_ring.kill(text, merge, prepend);
+    }
+    /// Submit an input
+    ///
+    /// Returns `false` if the console was closed
+    fn submit(&mut self, input: String) -> bool {
+        if let Some(heredoc) = &mut self.heredoc {
+            if heredoc.push(input) {
+                self.line.lock().unwrap().draw();
+                return true;
+            }
+            // The block is finished
+            let heredoc = self.heredoc.take().unwrap();
+            self.line.lock().unwrap().set_prompt(heredoc.prompt.clone());
+            let context = self.context();
+            return self.run_input(heredoc.payload(), &context);
+        }
+        if input.trim().is_empty() {
+            match self.empty_input {
+                EmptyInput::Submit => {}
+                EmptyInput::Ignore => {
+                    self.line.lock().unwrap().draw();
+                    return true;
+                }
+                EmptyInput::RepeatLast => {
+                    self.line.lock().unwrap().draw();
+                    return match self.history.last().cloned() {
+                        Some(last) => {
+                            let context = self.context();
+                            self.run_input(last, &context)
+                        }
+                        None => true,
+                    };
+                }
+                EmptyInput::Tick => {
+                    let tick = self
+                        .tick_message
+                        .lock()
+                        .unwrap()
+                        .as_ref()
+                        .map(|tick| tick());
+                    if let Some(message) = tick {
+                        self.input = None;
+                        self.submitted = SystemTime::now();
+                        self.send_message(message);
+                    }
+                    self.line.lock().unwrap().draw();
+                    return true;
+                }
+            }
+        }
+        let input = if self.trim_input {
+            input.trim().into()
+        } else {
+            input
+        };
+        let input = self.case.apply(input);
+        let context = self.context();
+        self.curr = None;
+        let input = if self.history_expansion {
+            match expand_history(&input, &self.history) {
+                Ok(Cow::Borrowed(_)) => input,
+                Ok(Cow::Owned(expanded)) => {
+                    println!("{}", expanded);
+                    expanded
+                }
+                Err(e) => {
+                    println!("{}", e);
+                    self.line.lock().unwrap().draw();
+                    return true;
+                }
+            }
+        } else {
+            input
+        };
+        if self.heredocs {
+            let mut line = self.line.lock().unwrap();
+            if let Some(heredoc) = Heredoc::start(&input, line.prompt.clone()) {
+                line.set_prompt(HEREDOC_PROMPT.into());
+                drop(line);
+                self.add_history(input, &context, None);
+                self.heredoc = Some(heredoc);
+                return true;
+            }
+        }
+        if self.shell_commands && self.role == Role::Full {
+            if let Some(command) = input.strip_prefix('!') {
+                let exit_status = self.run_shell(command.trim());
+                if exit_status.is_some_and(|code| code != 0) {
+                    self.failed.insert(input.clone());
+                } else {
+                    self.failed.remove(&input);
+                }
+                self.add_history(input, &context, exit_status);
+                return true;
+            }
+        }
+        self.add_history(input.clone(), &context, None);
+        self.run_input(input, &context)
+    }
+    /// Add an entry to the history
+    fn add_history(&mut self, line: String, context: &Context, exit_status: Option<i32>) {
+        if self.line.lock().unwrap().mask {
+            return;
+        }
+        if let Some(store) = &mut self.history_store {
+            store.append(&HistoryEntry {
+                line: line.clone(),
+                timestamp: context.timestamp,
+                session: self.session,
+                exit_status,
+            });
+        }
+        if !matches!(
+            Builtin::parse(&line),
+            Some(Builtin::Record(_) | Builtin::Stop)
+        ) {
+            self.macros.push(&line);
+        }
+        if let Some(args) = &mut self.arg_history {
+            args.push(&line, self.tokenizer);
+        }
+        self.history.push(line);
+    }
+    /// Recreate the remembered argument values from the history
+    fn reload_arg_history(&mut self) {
+        if self.arg_history.is_some() {
+            self.arg_history = Some(ArgHistory::from_history(&self.history, self.tokenizer));
+        }
+    }
+    /// Run a command in the system shell, handing the terminal to it until
+    /// it exits
+    ///
+    /// Returns the command's exit code
+    fn run_shell(&mut self, command: &str) -> Option<i32> {
+        let (shell, flag) = if cfg!(windows) {
+            ("cmd", "/C")
+        } else {
+            ("sh", "-c")
+        };
+        if self.mouse {
+            let _ = TerminalInput::new().disable_mouse_mode();
+        }
+        let pause = TerminalPause::new();
+        let status = process::Command::new(shell).arg(flag).arg(command).status();
+        drop(pause);
+        if self.mouse {
+            let _ = TerminalInput::new().enable_mouse_mode();
+        }
+        let code = match status {
+            Ok(status) => {
+                if !status.success() {
+                    let message = match status.code() {
+                        Some(code) => format!("exit status {}", code),
+                        None => "terminated by signal".into(),
+                    };
+                    println!("{}", message.dimmed());
+                }
+                status.code()
+            }
+            Err(e) => {
+                println!("failed to run `{}`: {}", command, e);
+                None
+            }
+        };
+        if let Some(on_shell_exit) = &mut self.on_shell_exit {
+            on_shell_exit(command, code);
+        }
+        self.line.lock().unwrap().draw();
+        code
+    }
+    /// Run an input as a built-in command, quit command, or command for the
+    /// current processor
+    ///
+    /// Returns `false` if the console was closed
+    fn run_input(&mut self, input: String, context: &Context) -> bool {
+        self.input = Some(input.clone());
+        self.submitted = context.timestamp;
+        let input = self.expand_alias(&input).unwrap_or(input);
+        let restricted = self.role == Role::Restricted;
+        if restricted && !self.permitted(&input) {
+            // Let the source know why nothing happened
+            write_to_sinks(&self.sinks, Some(&self.origin), "permission denied");
+            self.reject(Some("permission denied".into()));
+            return true;
+        }
+        if self.builtins && !restricted {
+            if let Some(builtin) = Builtin::parse(&input) {
+                return self.run_builtin(builtin);
+            }
+        }
+        let in_mode = !self
+            .modes
+            .lock()
+            .unwrap()
+            .stack_mut(&self.origin)
+            .is_empty();
+        let quit = self
+            .quit_commands
+            .iter()
+            .any(|command| self.command_case.matches(&input, command));
+        if quit && !(in_mode && input == "exit") && !restricted {
+            self.quit();
+            return false;
+        }
+        let mut scoped_modes = self.modes.lock().unwrap();
+        let modes = scoped_modes.stack_mut(&self.origin);
+        if let Some(mode) = modes.last_mut() {
+            // Let the current mode handle the input
+            let message = if input == "exit" && !restricted {
+                None
+            } else {
+                let mut input = input;
+                if let HookAction::Reject(message) = mode.processor.before_parse(&mut input) {
+                    drop(scoped_modes);
+                    self.reject(message);
+                    return true;
+                }
+                mode.processor.handle(&input, context)
+            };
+            if let Some(message) = message {
+                self.send_message(message);
+            } else {
+                modes.pop();
+            }
+            let new_prompt = scoped_modes.prompt(&self.prompt);
+            drop(scoped_modes);
+            self.line.lock().unwrap().set_prompt(new_prompt);
+            return true;
+        }
+        drop(scoped_modes);
+        #[cfg(feature = "scripting")]
+        if let Some(scripts) = self.scripts.as_mut().filter(|_| !restricted) {
+            if let Some(definition) = input.strip_prefix("def ") {
+                match scripts.define(&format!("fn {}", definition)) {
+                    Ok(names) => println!("defined {}", names.join(", ")),
+                    Err(e) => println!("{}", e),
+                }
+                self.line.lock().unwrap().draw();
+                return true;
+            }
+            let name = input.split_whitespace().next().unwrap_or_default();
+            if scripts.contains(name) {
+                let result = match context.tokenizer.split(&input) {
+                    Ok(args) => scripts.call(name, args.into_iter().skip(1).collect()),
+                    Err(e) => Err(e.to_string()),
+                };
+                match result {
+                    Ok(commands) => {
+                        for command in commands {
+                            if !self.run_input(command, context) {
+                                return false;
+                            }
+                        }
+                    }
+                    Err(e) => println!("{}", e),
+                }
+                self.line.lock().unwrap().draw();
+                return true;
+            }
+        }
+        let mut input = input;
+        if self.expand_vars {
+            let vars = self.vars.lock().unwrap();
+            // Only local input can read the environment, so that remote
+            // sessions cannot read secrets from it
+            let local = matches!(self.origin, Origin::Terminal | Origin::Console)
+                && self.role == Role::Full;
+            input = expand_vars(&input, |name| match vars.lookup(&self.origin, name) {
+                Some(value) => Some(value.clone()),
+                None if local => env::var(name).ok(),
+                None => None,
+            });
+        }
+        if self.expand_paths {
+            input = expand_paths(&input, self.tokenizer);
+        }
+        if self.command_case != CaseMatching::Sensitive {
+            let commands = self.processor.commands();
+            if let Some(matched) = match_command_case(&input, &commands, self.command_case) {
+                input = matched;
+            }
+        }
+        if self.abbreviations {
+            let commands = self.processor.commands();
+            match expand_abbreviation_with_case(&input, &commands, self.command_case) {
+                Abbreviation::Unchanged => {}
+                Abbreviation::Expanded(expanded) => input = expanded,
+                Abbreviation::Ambiguous(candidates) => {
+                    println!(
+                        "ambiguous command: could be {}",
+                        candidates
+                            .iter()
+                            .map(|c| format!("`{}`", c))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    );
+                    self.line.lock().unwrap().draw();
+                    return true;
+                }
+            }
+        }
+        if let HookAction::Reject(message) = self.processor.before_parse(&mut input) {
+            self.reject(message);
+            return true;
+        }
+        if let Some(pool) = &self.pool {
+            let _ = pool.send(Job {
+                input,
+                context: context.clone(),
+                envelope: self.envelope(),
+            });
+            self.line.lock().unwrap().draw();
+            return true;
+        }
+        let parsed = self.processor.parse_with_context(&input, context);
+        if self.suggest && self.processor.unrecognized(&input, &parsed) {
+            self.suggest_commands(&input);
+        }
+        if self.render_errors {
+            if let Some(error) = self.processor.render_error(&parsed, terminal_width()) {
+                let line = self.line.lock().unwrap();
+                line.clear();
+                print!("{}", error);
+                line.draw();
+                return true;
+            }
+        }
+        if let Some(message) = (self.process)(parsed) {
+            self.send_message(message);
+            self.line.lock().unwrap().draw();
+            true
+        } else {
+            self.closed.store(true, Ordering::Relaxed);
+            false
+        }
+    }
+    /// Create the context for an input that is being submitted
+    fn context(&self) -> Context {
+        Context {
+            tokenizer: self.tokenizer,
+            origin: self.origin.clone(),
+            role: self.role,
+            ..Context::new(self.session, self.user.clone())
+        }
+    }
+    /// Get the time at which [`Editor::run_due`] should next be called
+    fn next_deadline(&self) -> Option<Instant> {
+        let watch = self.watch.as_ref().map(|watch| watch.next);
+        let repeat = self.repeat.as_ref().map(|repeat| repeat.next);
+        let job = self.scheduler.lock().unwrap().next_due().map(|time| {
+            let delay = time.duration_since(SystemTime::now()).unwrap_or_default();
+            Instant::now() + delay
+        });
+        let timer = self.timers.lock().unwrap().next_due();
+        let definitions = self.definitions.as_ref().map(|d| d.next_check);
+        let timeout = self.timeouts.borrow().iter().map(|p| p.deadline).min();
+        let reveal = self.reveal_until;
+        let title = self.title.as_ref().map(|_| self.next_title);
+        let frame = self
+            .line
+            .lock()
+            .unwrap()
+            .frames
+            .as_ref()
+            .and_then(Frames::due);
+        // Check now and then whether the app has finished with its messages
+        let pending =
+            (self.line.lock().unwrap().pending > 0).then(|| Instant::now() + PENDING_INTERVAL);
+        let resize = self
+            .line
+            .lock()
+            .unwrap()
+            .wrap
+            .is_some()
+            .then(|| Instant::now() + RESIZE_INTERVAL);
+        watch
+            .into_iter()
+            .chain(repeat)
+            .chain(job)
+            .chain(timer)
+            .chain(definitions)
+            .chain(timeout)
+            .chain(pending)
+            .chain(resize)
+            .chain(reveal)
+            .chain(title)
+            .chain(frame)
+            .min()
+    }
+    /// Update the title of the terminal window if the title function gives a
+    /// new one
+    fn update_title(&mut self) {
+        self.next_title = Instant::now() + TITLE_INTERVAL;
+        let title = match &mut self.title {
+            Some(title) => title(),
+            None => return,
+        };
+        if self.shown_title.as_ref() == Some(&title) {
+            return;
+        }
+        if !self.line.lock().unwrap().detached {
+            set_title(&title);
+        }
+        self.shown_title = Some(title);
+    }
+    /// Update the number of unfinished messages shown before the prompt
+    fn update_pending(&self) {
+        if !self.pending_indicator {
+            return;
+        }
+        let pending = self
+            .cancel_tokens
+            .borrow()
+            .iter()
+            .filter(|(_, handle)| handle.is_alive())
+            .count();
+        let mut line = self.line.lock().unwrap();
+        if line.pending != pending {
+            line.clear();
+            line.pending = pending;
+            line.draw();
+        }
+    }
+    /// Start watching a command in case it takes too long
+    fn watch_timeout(&self, input: &str, handle: CommandHandle) {
+        let name = input.split_whitespace().next().unwrap_or_default();
+        let timeout = match self
+            .command_timeouts
+            .get(name)
+            .or(self.command_timeout.as_ref())
+        {
+            Some(timeout) => *timeout,
+            None => return,
+        };
+        self.timeouts.borrow_mut().push(PendingCommand {
+            command: input.into(),
+            deadline: Instant::now() + timeout,
+            origin: self.origin.clone(),
+            outputs: self.outputs.get(&self.origin),
+            handle,
+        });
+    }
+    /// Report the commands that the app has not responded to in time
+    fn check_timeouts(&self) {
+        let now = Instant::now();
+        let mut timeouts = self.timeouts.borrow_mut();
+        timeouts.retain(|pending| {
+            if pending.dropped() || pending.outputs != self.outputs.get(&pending.origin) {
+                return false;
+            }
+            if pending.deadline > now {
+                return true;
+            }
+            let notice = if self.cancel_on_timeout {
+                pending.handle.cancel();
+                format!("{} timed out", pending.command)
+            } else {
+                format!("{} is still running", pending.command)
+            };
+            let line = self.line.lock().unwrap();
+            line.clear();
+            println!("{}", notice.dimmed());
+            line.draw();
+            false
+        });
+    }
+    /// Fire the timers and run the commands that are due
+    ///
+    /// Returns `false` if the console was closed
+    fn run_due(&mut self) -> bool {
+        self.input = None;
+        self.submitted = SystemTime::now();
+        for message in self.timers.lock().unwrap().fire(Instant::now()) {
+            self.send_message(message);
+        }
+        let jobs = self.scheduler.lock().unwrap().take_due(SystemTime::now());
+        for (id, command) in jobs {
+            let line = self.line.lock().unwrap();
+            line.clear();
+            println!("{}", format!("[job {}] {}", id, command).dimmed());
+            line.draw();
+            drop(line);
+            let context = self.context();
+            if !self.run_input(command, &context) {
+                return false;
+            }
+        }
+        self.check_timeouts();
+        let now = Instant::now();
+        self.line.lock().unwrap().print_frame(now, false);
+        self.line.lock().unwrap().rewrap();
+        if self.next_title <= now {
+            self.update_title();
+        }
+        if self.reveal_until.is_some_and(|until| until <= now) {
+            self.reveal_until = None;
+            let mut line = self.line.lock().unwrap();
+            if line.revealed.take().is_some() {
+                line.redraw();
+            }
+        }
+        if let Some(repeat) = self.repeat.as_mut().filter(|repeat| repeat.next <= now) {
+            repeat.remaining = repeat.remaining.saturating_sub(1);
+            repeat.next = now + repeat.delay;
+            let command = repeat.command.clone();
+            if repeat.remaining == 0 {
+                self.repeat = None;
+            }
+            let context = self.context();
+            if !self.run_input(command, &context) {
+                return false;
+            }
+        }
+        if self
+            .definitions
+            .as_ref()
+            .is_some_and(|d| d.next_check <= now)
+        {
+            self.reload_definitions(true);
+        }
+        let watch = match &mut self.watch {
+            Some(watch) if watch.next <= now => watch,
+            _ => return true,
+        };
+        watch.next = now + watch.interval;
+        let command = watch.command.clone();
+        // Show the latest run at the top of the screen
+        let header = format!(
+            "every {}s: {} (press any key to stop)",
+            watch.interval.as_secs_f64(),
+            command
+        );
+        let line = self.line.lock().unwrap();
+        let _ = Terminal::new().clear(ClearType::All);
+        println!("{}", header.dimmed());
+        line.draw();
+        drop(line);
+        let context = self.context();
+        self.run_input(command, &context)
+    }
+    /// Print the message of a rejected input
+    fn reject(&self, message: Option<String>) {
+        if let Some(message) = message {
+            println!("{}", message);
+        }
+        self.line.lock().unwrap().draw();
+    }
+    /// Check if restricted input is allowed to run a command
+    fn permitted(&self, input: &str) -> bool {
+        let command = input.split_whitespace().next().unwrap_or_default();
+        self.restricted_commands
+            .iter()
+            .any(|allowed| self.command_case.matches(command, allowed))
+    }
+    /// Send a message to the app with information about the input
+    fn send_message(&self, message: M) {
+        self.send.send(self.envelope().map(|()| message));
+    }
+    /// Create the envelope for the next message
+    fn envelope(&self) -> Envelope<()> {
+        let sequence = self.sequence.get();
+        self.sequence.set(sequence + 1);
+        let cancel = CancelToken::new();
+        let mut tokens = self.cancel_tokens.borrow_mut();
+        // Forget tokens whose messages have been dropped
+        tokens.retain(|(_, handle)| handle.is_alive());
+        tokens.push((self.origin.clone(), cancel.handle()));
+        if let Some(input) = &self.input {
+            self.watch_timeout(input, cancel.handle());
+        }
+        Envelope {
+            message: (),
+            timestamp: self.submitted,
+            session: self.session,
+            input: self.input.clone(),
+            sequence,
+            origin: self.origin.clone(),
+            user: self.user.clone(),
+            cancel,
+        }
+    }
+    /// Cancel the commands from the origins that match the predicate
+    ///
+    /// Returns whether any commands were cancelled
+    fn cancel_commands<C>(&self, matches: C) -> bool
+    where
+        C: Fn(&Origin) -> bool,
+    {
+        let mut cancelled = false;
+        self.cancel_tokens.borrow_mut().retain(|(origin, handle)| {
+            if !matches(origin) {
+                return true;
+            }
+            cancelled |= handle.is_alive();
+            handle.cancel();
+            false
+        });
+        cancelled
+    }
+    /// Stop drawing on the terminal until the detach key is pressed again
+    fn detach(&mut self, line: &mut Line) {
+        line.clear();
+        println!(
+            "{}",
+            "detached, press the detach key again to attach".dimmed()
+        );
+        line.detached = true;
+        let mark = self
+            .scrollback
+            .as_ref()
+            .map_or(0, |s| s.lock().unwrap().mark());
+        self.detached = Some(mark);
+    }
+    /// Draw on the terminal again, showing the output that was missed
+    fn attach(&mut self, line: &mut Line, mark: u64) {
+        self.detached = None;
+        line.detached = false;
+        line.clear();
+        if let Some(scrollback) = &self.scrollback {
+            for missed in scrollback.lock().unwrap().since(mark) {
+                print!("{}\r\n", missed);
+            }
+        }
+        line.draw();
+    }
+    /// Close the console because the user quit
+    fn quit(&mut self) {
+        if let Some(message) = self.quit_message.lock().unwrap().take() {
+            self.send_message(message);
+        }
+        self.closed.store(true, Ordering::Relaxed);
+    }
+    /// Run a built-in command
+    ///
+    /// Returns `false` if the console was closed
+    fn run_builtin(&mut self, builtin: Builtin) -> bool {
+        let line = self.line.lock().unwrap();
+        match builtin {
+            Builtin::Clear => line.clear_screen(),
+            Builtin::Repeat {
+                count,
+                delay,
+                command,
+            } => {
+                // Each run waits for its turn in the event loop, so input is
+                // still handled and Ctrl-C can cancel the remaining runs
+                self.repeat = (count > 0).then(|| Repeat {
+                    command,
+                    remaining: count,
+                    delay,
+                    next: Instant::now(),
+                });
+            }
+            Builtin::Watch { interval, command } => {
+                self.watch = Some(Watch {
+                    interval,
+                    command,
+                    next: Instant::now(),
+                });
+            }
+            Builtin::At { time, command } => {
+                let id = self.scheduler.lock().unwrap().schedule(command, time, None);
+                println!("job {} scheduled for {} UTC", id, format_time_of_day(time));
+                line.draw();
+            }
+            Builtin::Every { interval, command } => {
+                let next = SystemTime::now() + interval;
+                let mut scheduler = self.scheduler.lock().unwrap();
+                let id = scheduler.schedule(command, next, Some(interval));
+                println!("job {} scheduled every {}s", id, interval.as_secs_f64());
+                line.draw();
+            }
+            Builtin::Jobs => {
+                let jobs = self.scheduler.lock().unwrap().jobs();
+                if jobs.is_empty() {
+                    println!("no scheduled jobs");
+                }
+                for job in jobs {
+                    let when = match job.every {
+                        Some(every) => format!("every {}s", every.as_secs_f64()),
+                        None => "once".into(),
+                    };
+                    println!(
+                        "  {}  next at {} UTC, {}: {}",
+                        job.id,
+                        format_time_of_day(job.next),
+                        when,
+                        job.command
+                    );
+                }
+                line.draw();
+            }
+            Builtin::Cancel(id) => {
+                if self.scheduler.lock().unwrap().cancel(id) {
+                    println!("job {} cancelled", id);
+                } else {
+                    println!("no job with id {}", id);
+                }
+                line.draw();
+            }
+            Builtin::Record(name) => {
+                println!("recording macro `{}`, enter `stop` to finish", name);
+                self.macros.record(name);
+                line.draw();
+            }
+            Builtin::Stop => {
+                match self.macros.stop() {
+                    Some((name, count)) => {
+                        println!("macro `{}` recorded with {} commands", name, count)
+                    }
+                    None => println!("not recording a macro"),
+                }
+                line.draw();
+            }
+            Builtin::Play(name) => {
+                let commands = match self.macros.get(&name) {
+                    Some(_) if self.macros.playing.contains(&name) => {
+                        println!("macro `{}` cannot play itself", name);
+                        line.draw();
+                        return true;
+                    }
+                    Some(commands) => commands,
+                    None => {
+                        println!("no macro named `{}`", name);
+                        line.draw();
+                        return true;
+                    }
+                };
+                drop(line);
+                self.macros.playing.push(name);
+                for command in commands {
+                    let context = self.context();
+                    if !self.run_input(command, &context) {
+                        return false;
+                    }
+                }
+                self.macros.playing.pop();
+            }
+            Builtin::Latency { reset } => {
+                match &self.latency {
+                    None => println!("latency stats are disabled"),
+                    Some(latency) if reset => {
+                        latency.reset();
+                        println!("latency stats reset");
+                    }
+                    Some(latency) => {
+                        println!("keypress to render: {}", latency.keypress.lock().unwrap());
+                        println!("submit to deliver: {}", latency.deliver.lock().unwrap());
+                    }
+                }
+                line.draw();
+            }
+            Builtin::Set {
+                global,
+                name,
+                value,
+            } => {
+                let scope = self.builtin_scope(global);
+                self.vars
+                    .lock()
+                    .unwrap()
+                    .get_mut(&scope)
+                    .insert(name, value);
+            }
+            Builtin::Unset { global, name } => {
+                let scope = self.builtin_scope(global);
+                if self
+                    .vars
+                    .lock()
+                    .unwrap()
+                    .get_mut(&scope)
+                    .remove(&name)
+                    .is_none()
+                {
+                    println!("no variable named `{}`", name);
+                    line.draw();
+                }
+            }
+            Builtin::Alias {
+                global,
+                name,
+                command,
+            } => {
+                let scope = self.builtin_scope(global);
+                self.aliases.get_mut(&scope).insert(name, command);
+            }
+            Builtin::Unalias { global, name } => {
+                let scope = self.builtin_scope(global);
+                if self.aliases.get_mut(&scope).remove(&name).is_none() {
+                    println!("no alias named `{}`", name);
+                    line.draw();
+                }
+            }
+            Builtin::LogLevel(None) => {
+                println!("log level: {}", self.log_level.lock().unwrap());
+                line.draw();
+            }
+            Builtin::LogLevel(Some(level)) => {
+                match level.parse::<Level>() {
+                    Ok(level) => {
+                        *self.log_level.lock().unwrap() = level;
+                        println!("log level set to {}", level);
+                    }
+                    Err(e) => println!("{}", e),
+                }
+                line.draw();
+            }
+        }
+        true
+    }
+    /// Get the scope of the variables and aliases set by a built-in
+    fn builtin_scope(&self, global: bool) -> Scope {
+        if global || !self.session_scopes {
+            Scope::Global
+        } else {
+            Scope::Session(self.origin.clone())
+        }
+    }
+    /// Replace the command name at the start of an input if it is an alias
+    ///
+    /// Aliases defined with the `alias` built-in for the current origin are
+    /// used first, then global ones, then the ones in the definitions file.
+    fn expand_alias(&self, input: &str) -> Option<String> {
+        let trimmed = input.trim_start();
+        let end = trimmed.find(char::is_whitespace).unwrap_or(trimmed.len());
+        match self.aliases.lookup(&self.origin, &trimmed[..end]) {
+            Some(command) => Some(format!("{}{}", command, &trimmed[end..])),
+            None => self.definitions.as_ref()?.expand_alias(input),
+        }
+    }
+    /// Print suggestions for an unrecognized command
+    fn suggest_commands(&mut self, input: &str) {
+        let command = match input.split_whitespace().next() {
+            Some(command) => command,
+            None => return,
+        };
+        let commands = self.processor.commands();
+        let suggestions = suggestions(command, commands.iter().map(String::as_str));
+        if let Some(message) = did_you_mean(&suggestions) {
+            println!("{}", message);
+        }
+    }
+    /// Get the completion of the word at the cursor
+    fn completion(&mut self, input: &str, cursor: usize) -> Completion {
+        if let Some(mode) = self
+            .modes
+            .lock()
+            .unwrap()
+            .stack_mut(&Origin::Terminal)
+            .last_mut()
+        {
+            return mode.processor.complete(input, cursor);
+        }
+        let completion = self
+            .plugins
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .find_map(|plugin| plugin.complete(input, cursor));
+        let mut completion = if let Some(completion) = completion {
+            completion
+        } else if let Some(completer) = &mut self.completer {
+            match self.completion_case {
+                Some(case) => completer.complete_with_case(input, cursor, case),
+                None => completer.complete(input, cursor),
+            }
+        } else {
+            self.processor.complete(input, cursor)
+        };
+        if let Some(args) = &self.arg_history {
+            // Values entered before come first, followed by the others
+            let case = self.completion_case.unwrap_or(CaseMatching::Sensitive);
+            let mut recent = args.complete(input, cursor, case);
+            if completion.candidates.is_empty() || completion.start == recent.start {
+                for candidate in completion.candidates {
+                    let seen = recent
+                        .candidates
+                        .iter()
+                        .any(|c| c.replacement == candidate.replacement);
+                    if !seen {
+                        recent.candidates.push(candidate);
+                    }
+                }
+                completion = recent;
+            }
+        }
+        completion
+    }
+    /// Complete the word at the cursor
+    fn complete(&mut self, line: &mut Line) {
+        let cursor = line.cursor;
+        let completion = self.completion(&line.input, cursor);
+        let start = completion.start;
+        match completion.candidates.len() {
+            0 => {}
+            1 => {
+                let replacement = &completion.candidates[0].replacement;
+                line.input.replace_range(start..cursor, replacement);
+                line.cursor = start + replacement.len();
+                line.redraw();
+            }
+            _ => {
+                let prefix = common_prefix(&completion.candidates);
+                // Only extend the word if the typed text still matches it
+                let typed = &line.input[start..cursor];
+                if prefix.len() > typed.len() && fuzzy_match(typed, prefix).is_some() {
+                    line.input.replace_range(start..cursor, prefix);
+                    line.cursor = start + prefix.len();
+                }
+                let menu = Menu {
+                    original: line.input.clone(),
+                    start,
+                    end: line.cursor,
+                    candidates: completion.candidates,
+                    selected: None,
+                };
+                line.below = Some(menu.render(terminal_width(), below_rows()));
+                line.redraw();
+                self.menu = Some(menu);
+            }
+        }
+    }
+    /// Show inline help for what can come next in the input
+    fn show_help(&mut self, line: &mut Line) {
+        let entries = if let Some(mode) = self
+            .modes
+            .lock()
+            .unwrap()
+            .stack_mut(&Origin::Terminal)
+            .last_mut()
+        {
+            mode.processor.help(&line.input)
+        } else if let Some(completer) = &mut self.completer {
+            completer
+                .complete(&line.input, line.cursor)
+                .candidates
+                .into_iter()
+                .map(HelpEntry::from)
+                .collect()
+        } else {
+            self.processor.help(&line.input)
+        };
+        line.clear();
+        if entries.is_empty() {
+            println!("  no help available");
+        } else {
+            print!("{}", render_help(&entries));
+        }
+        line.draw();
+    }
+    /// Let the plugins highlight the input line
+    fn update_highlight(&mut self, line: &mut Line) {
+        let highlighted = self
+            .plugins
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .find_map(|plugin| plugin.highlight(&line.input))
+            .map(|highlighted| (line.input.clone(), highlighted));
+        if line.highlighted != highlighted {
+            line.highlighted = highlighted;
+            line.redraw();
+        }
+    }
+    /// Update the hint shown below the input line
+    fn update_hint(&mut self, line: &mut Line) {
+        self.update_highlight(line);
+        if !self.hints {
+            return;
+        }
+        let hint = if let Some(mode) = self
+            .modes
+            .lock()
+            .unwrap()
+            .stack_mut(&Origin::Terminal)
+            .last_mut()
+        {
+            mode.processor.hint(&line.input, line.cursor)
+        } else if let Some(hinter) = &mut self.hinter {
+            hinter.hint(&line.input, line.cursor)
+        } else {
+            self.processor.hint(&line.input, line.cursor)
+        };
+        let hint = hint.map(|hint| hint.render());
+        if line.below != hint {
+            line.below = hint;
+            line.redraw();
+        }
+    }
+}
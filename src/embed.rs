@@ -0,0 +1,242 @@
+use std::{
+    collections::VecDeque,
+    io,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    common_prefix,
+    line::{next_char, prev_char},
+    strip_ansi, Candidate, Console, Origin, OutputSink,
+};
+
+/// The most lines of output an [`EmbeddedConsole`] keeps
+const MAX_LINES: usize = 1000;
+
+type OutputLines = Arc<Mutex<VecDeque<String>>>;
+
+/// Add output to some lines, dropping the oldest lines if there are too many
+fn push_output(lines: &OutputLines, output: &str) {
+    let mut lines = lines.lock().unwrap();
+    lines.extend(output.lines().map(Into::into));
+    while lines.len() > MAX_LINES {
+        lines.pop_front();
+    }
+}
+
+/// A sink that collects the output shown by an [`EmbeddedConsole`]
+struct EmbeddedSink(OutputLines);
+
+impl OutputSink for EmbeddedSink {
+    fn write(&mut self, output: &str) -> io::Result<()> {
+        push_output(&self.0, output);
+        Ok(())
+    }
+}
+
+/// The state of a [`Console`] embedded in another interface
+///
+/// An `EmbeddedConsole` has its own input line and a copy of the console's
+/// output, but it runs commands, completion, and the history through the
+/// console it was created for. This lets a game window or a TUI pane offer
+/// the same commands as the terminal with a single processor. The widgets
+/// for GUI and TUI libraries are built on it.
+pub struct EmbeddedConsole {
+    origin: Origin,
+    prompt: String,
+    output: OutputLines,
+    input: String,
+    cursor: usize,
+    /// The history being browsed and the index of the entry shown
+    browsing: Option<(Vec<String>, usize)>,
+    candidates: Vec<Candidate>,
+}
+
+impl EmbeddedConsole {
+    /// Create a new `EmbeddedConsole` for a console
+    ///
+    /// Input submitted from the embedded console has the given origin. Both
+    /// the console's general output and the output for that origin are
+    /// shown.
+    pub fn new<M: Send + 'static>(console: &Console<M>, origin: Origin) -> Self {
+        let state = Self::detached(origin);
+        console.add_output_sink(EmbeddedSink(Arc::clone(&state.output)));
+        console.add_origin_sink(
+            state.origin.clone(),
+            EmbeddedSink(Arc::clone(&state.output)),
+        );
+        state
+    }
+    /// Create a new `EmbeddedConsole` that does not collect any output
+    pub(crate) fn detached(origin: Origin) -> Self {
+        EmbeddedConsole {
+            origin,
+            prompt: "> ".into(),
+            output: OutputLines::default(),
+            input: String::new(),
+            cursor: 0,
+            browsing: None,
+            candidates: Vec::new(),
+        }
+    }
+    /// Set the prompt shown before the input
+    ///
+    /// This is `"> "` by default.
+    pub fn prompt<S: Into<String>>(mut self, prompt: S) -> Self {
+        self.prompt = prompt.into();
+        self
+    }
+    /// Get the prompt shown before the input
+    pub fn prompt_text(&self) -> &str {
+        &self.prompt
+    }
+    /// Get the lines of output, oldest first
+    pub fn output(&self) -> Vec<String> {
+        self.output
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|line| strip_ansi(line))
+            .collect()
+    }
+    /// Get the lines of output with their ANSI escape codes, oldest first
+    pub(crate) fn ansi_output(&self) -> Vec<String> {
+        self.output.lock().unwrap().iter().cloned().collect()
+    }
+    /// Clear the output
+    pub fn clear_output(&self) {
+        self.output.lock().unwrap().clear();
+    }
+    /// Get the input
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+    /// Get the byte index of the cursor in the input
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+    /// Move the cursor to a byte index in the input
+    ///
+    /// The index is moved back to the nearest character boundary.
+    pub fn set_cursor(&mut self, cursor: usize) {
+        let mut cursor = cursor.min(self.input.len());
+        while !self.input.is_char_boundary(cursor) {
+            cursor -= 1;
+        }
+        self.cursor = cursor;
+    }
+    /// Replace the input, placing the cursor at the end
+    pub fn set_input<S: Into<String>>(&mut self, input: S) {
+        self.input = input.into();
+        self.cursor = self.input.len();
+        self.candidates.clear();
+    }
+    /// Get the completion candidates from the last completion that had more
+    /// than one
+    pub fn candidates(&self) -> &[Candidate] {
+        &self.candidates
+    }
+    /// Insert a character at the cursor
+    pub fn insert(&mut self, c: char) {
+        self.input.insert(self.cursor, c);
+        self.cursor += c.len_utf8();
+        self.candidates.clear();
+    }
+    /// Delete the character before the cursor
+    pub fn backspace(&mut self) {
+        if self.cursor > 0 {
+            let start = prev_char(&self.input, self.cursor);
+            self.input.replace_range(start..self.cursor, "");
+            self.cursor = start;
+            self.candidates.clear();
+        }
+    }
+    /// Delete the character at the cursor
+    pub fn delete(&mut self) {
+        if self.cursor < self.input.len() {
+            let end = next_char(&self.input, self.cursor);
+            self.input.replace_range(self.cursor..end, "");
+            self.candidates.clear();
+        }
+    }
+    /// Move the cursor one character to the left
+    pub fn move_left(&mut self) {
+        self.cursor = prev_char(&self.input, self.cursor);
+    }
+    /// Move the cursor one character to the right
+    pub fn move_right(&mut self) {
+        self.cursor = next_char(&self.input, self.cursor);
+    }
+    /// Move the cursor to the start of the input
+    pub fn move_home(&mut self) {
+        self.cursor = 0;
+    }
+    /// Move the cursor to the end of the input
+    pub fn move_end(&mut self) {
+        self.cursor = self.input.len();
+    }
+    /// Submit the input to the console
+    ///
+    /// The input is echoed in the output after the prompt.
+    pub fn submit<M: Send + 'static>(&mut self, console: &Console<M>) {
+        push_output(&self.output, &format!("{}{}", self.prompt, self.input));
+        console.submit(self.origin.clone(), self.take_input());
+    }
+    /// Take the input, leaving an empty line
+    pub(crate) fn take_input(&mut self) -> String {
+        self.cursor = 0;
+        self.browsing = None;
+        self.candidates.clear();
+        std::mem::take(&mut self.input)
+    }
+    /// Complete the word at the cursor with the console's completer
+    ///
+    /// If there are several candidates, the word is extended to their common
+    /// prefix and they are kept in [`EmbeddedConsole::candidates`].
+    pub fn complete<M: Send + 'static>(&mut self, console: &Console<M>) {
+        let completion = console.complete(&self.input, self.cursor);
+        let replacement = match completion.candidates.len() {
+            0 => return,
+            1 => completion.candidates[0].replacement.clone(),
+            _ => common_prefix(&completion.candidates).to_string(),
+        };
+        if replacement.len() >= self.cursor - completion.start {
+            self.input
+                .replace_range(completion.start..self.cursor, &replacement);
+            self.cursor = completion.start + replacement.len();
+        }
+        self.candidates = if completion.candidates.len() > 1 {
+            completion.candidates
+        } else {
+            Vec::new()
+        };
+    }
+    /// Show the previous line in the console's history
+    pub fn history_prev<M: Send + 'static>(&mut self, console: &Console<M>) {
+        let (history, index) = self.browsing.get_or_insert_with(|| {
+            let history = console.history();
+            let len = history.len();
+            (history, len)
+        });
+        if *index > 0 {
+            *index -= 1;
+            let line = history[*index].clone();
+            self.set_input(line);
+        }
+    }
+    /// Show the next line in the console's history, or an empty input after
+    /// the last one
+    pub fn history_next(&mut self) {
+        if let Some((history, index)) = &mut self.browsing {
+            *index += 1;
+            let line = history.get(*index).cloned();
+            match line {
+                Some(line) => self.set_input(line),
+                None => {
+                    self.browsing = None;
+                    self.set_input("");
+                }
+            }
+        }
+    }
+}
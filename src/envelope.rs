@@ -0,0 +1,51 @@
+use std::time::SystemTime;
+
+use crate::{CancelToken, Origin, SessionId};
+
+/// A message from a [`Console`](crate::Console) along with information about
+/// the input that produced it
+///
+/// Envelopes are received with
+/// [`Console::poll_envelope`](crate::Console::poll_envelope).
+#[derive(Debug, Clone)]
+pub struct Envelope<M> {
+    /// The message
+    pub message: M,
+    /// The time at which the input was submitted
+    pub timestamp: SystemTime,
+    /// The session the input was entered in
+    pub session: SessionId,
+    /// The command that produced the message, if any
+    ///
+    /// This is `None` for messages from timers and tick messages.
+    pub input: Option<String>,
+    /// The number of messages the console sent before this one
+    pub sequence: u64,
+    /// Where the input came from
+    pub origin: Origin,
+    /// The user who entered the input, if its session
+    /// [logged in](crate::ConsoleBuilder::authenticate)
+    pub user: Option<String>,
+    /// A token that is cancelled if the user presses Ctrl-C or the input
+    /// source disconnects
+    pub cancel: CancelToken,
+}
+
+impl<M> Envelope<M> {
+    /// Transform the message, keeping the rest of the envelope
+    pub fn map<N, F>(self, f: F) -> Envelope<N>
+    where
+        F: FnOnce(M) -> N,
+    {
+        Envelope {
+            message: f(self.message),
+            timestamp: self.timestamp,
+            session: self.session,
+            input: self.input,
+            sequence: self.sequence,
+            origin: self.origin,
+            user: self.user,
+            cancel: self.cancel,
+        }
+    }
+}
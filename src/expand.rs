@@ -0,0 +1,203 @@
+use std::{borrow::Cow, env};
+
+use crate::Tokenizer;
+
+/// Apply a function to each whitespace-separated word of the input,
+/// preserving the whitespace between words
+pub(crate) fn map_words<'a, F>(input: &'a str, mut f: F) -> String
+where
+    F: FnMut(&'a str) -> Cow<'a, str>,
+{
+    let mut output = String::with_capacity(input.len());
+    let mut word_start = None;
+    for (i, c) in input.char_indices() {
+        if c.is_whitespace() {
+            if let Some(start) = word_start.take() {
+                output.push_str(&f(&input[start..i]));
+            }
+            output.push(c);
+        } else if word_start.is_none() {
+            word_start = Some(i);
+        }
+    }
+    if let Some(start) = word_start {
+        output.push_str(&f(&input[start..]));
+    }
+    output
+}
+
+/// Expand `~` and glob patterns in the words of an input
+///
+/// A `~` at the start of a word is replaced with the home directory. Words
+/// containing `*`, `?`, or `[` are treated as glob patterns and replaced with
+/// the paths that match them. Patterns that match nothing and quoted words
+/// are left as they are.
+///
+/// With [`Tokenizer::ShellWords`], paths containing whitespace, quotes, or
+/// backslashes are double-quoted so that each stays one argument. With
+/// [`Tokenizer::Whitespace`], such paths cannot be one argument, so they are
+/// left out.
+pub fn expand_paths(input: &str, tokenizer: Tokenizer) -> String {
+    map_words(input, |word| {
+        if word.starts_with('"') || word.starts_with('\'') {
+            return word.into();
+        }
+        let word = expand_tilde(word);
+        if !word.contains(['*', '?', '[']) {
+            return word;
+        }
+        let paths: Vec<String> = match glob::glob(&word) {
+            Ok(paths) => paths
+                .filter_map(Result::ok)
+                .filter_map(|path| {
+                    let path = path.to_string_lossy().into_owned();
+                    if !path.contains(|c: char| c.is_whitespace() || "\"'\\".contains(c)) {
+                        Some(path)
+                    } else if tokenizer == Tokenizer::ShellWords {
+                        Some(double_quote(&path))
+                    } else {
+                        None
+                    }
+                })
+                .collect(),
+            Err(_) => return word,
+        };
+        if paths.is_empty() {
+            word
+        } else {
+            paths.join(" ").into()
+        }
+    })
+}
+
+/// Put a path in double quotes, escaping the characters that
+/// [`shell_words`](crate::shell_words) would not take literally
+fn double_quote(path: &str) -> String {
+    let mut quoted = String::with_capacity(path.len() + 2);
+    quoted.push('"');
+    for c in path.chars() {
+        if c == '"' || c == '\\' {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Replace a `~` at the start of a word with the home directory
+fn expand_tilde(word: &str) -> Cow<'_, str> {
+    if word == "~" || word.starts_with("~/") {
+        if let Some(home) = home_dir() {
+            return format!("{}{}", home, &word[1..]).into();
+        }
+    }
+    word.into()
+}
+
+/// Get the home directory
+pub(crate) fn home_dir() -> Option<String> {
+    env::var("HOME").or_else(|_| env::var("USERPROFILE")).ok()
+}
+
+/// Expand `$VAR` and `${VAR}` references in an input
+///
+/// Variables are looked up with the given function. Variables that are not
+/// found expand to nothing. Text in single quotes is not expanded, and a
+/// `$` that does not start a variable name is left as it is.
+pub fn expand_vars<F>(input: &str, lookup: F) -> String
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut output = String::with_capacity(input.len());
+    let mut chars = input.char_indices().peekable();
+    let mut in_single_quotes = false;
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\'' => {
+                in_single_quotes = !in_single_quotes;
+                output.push(c);
+            }
+            '$' if !in_single_quotes => match chars.peek() {
+                Some(&(_, '{')) => {
+                    let start = i + 2;
+                    match input[start..].find('}') {
+                        Some(len) => {
+                            output
+                                .push_str(&lookup(&input[start..start + len]).unwrap_or_default());
+                            for (j, _) in chars.by_ref() {
+                                if j == start + len {
+                                    break;
+                                }
+                            }
+                        }
+                        None => output.push(c),
+                    }
+                }
+                Some(&(_, next)) if next == '_' || next.is_ascii_alphabetic() => {
+                    let start = i + 1;
+                    let mut end = start;
+                    while let Some(&(j, c)) = chars.peek() {
+                        if c == '_' || c.is_ascii_alphanumeric() {
+                            end = j + c.len_utf8();
+                            chars.next();
+                        } else {
+                            break;
+                        }
+                    }
+                    output.push_str(&lookup(&input[start..end]).unwrap_or_default());
+                }
+                _ => output.push(c),
+            },
+            c => output.push(c),
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, process};
+
+    use super::*;
+    use crate::shell_words;
+
+    #[test]
+    fn words() {
+        let upper = map_words(" a  bc\td ", |word| word.to_uppercase().into());
+        assert_eq!(upper, " A  BC\tD ");
+    }
+
+    #[test]
+    fn vars() {
+        let lookup = |name: &str| (name == "A").then(|| "x y".to_string());
+        assert_eq!(expand_vars("$A ${A}b $B", lookup), "x y x yb ");
+        assert_eq!(expand_vars("'$A' \"$A\"", lookup), "'$A' \"x y\"");
+        assert_eq!(expand_vars("$ $1 ${A", lookup), "$ $1 ${A");
+    }
+
+    #[test]
+    fn paths() {
+        let dir = env::temp_dir().join(format!("encore-expand-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for name in ["a.txt", "b c.txt", "d\"e.txt"] {
+            fs::write(dir.join(name), "").unwrap();
+        }
+        let dir_str = dir.to_string_lossy();
+        let pattern = format!("{}/*.txt", dir_str);
+        let input = format!("load {}", pattern);
+
+        let expanded = expand_paths(&input, Tokenizer::ShellWords);
+        let args = shell_words(&expanded).unwrap();
+        let expected = ["a.txt", "b c.txt", "d\"e.txt"].map(|name| format!("{}/{}", dir_str, name));
+        assert_eq!(args[0], "load");
+        assert_eq!(args[1..], expected);
+
+        let expanded = expand_paths(&input, Tokenizer::Whitespace);
+        assert_eq!(expanded, format!("load {}/a.txt", dir_str));
+
+        let missing = format!("load {}/*.sav '*.txt'", dir_str);
+        assert_eq!(expand_paths(&missing, Tokenizer::Whitespace), missing);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
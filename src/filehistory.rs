@@ -0,0 +1,100 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{HistoryEntry, HistoryStore};
+
+/// A [`HistoryStore`] backed by a plain text file with one line per entry
+///
+/// Several processes can share the same file. Every append takes an
+/// exclusive lock on the file so that concurrent writes are not interleaved,
+/// and the console reloads the file when the user starts moving through or
+/// searching the history if another process has written to it.
+pub struct FileHistory {
+    path: PathBuf,
+    /// The length of the file when it was last read
+    len: u64,
+}
+
+impl FileHistory {
+    /// Create a `FileHistory` that uses the file at the given path
+    ///
+    /// The file is created when the first entry is appended.
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        FileHistory {
+            path: path.into(),
+            len: 0,
+        }
+    }
+    /// Get the path of the history file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    fn read(&mut self) -> io::Result<Vec<String>> {
+        let mut file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                self.len = 0;
+                return Ok(Vec::new());
+            }
+            Err(e) => return Err(e),
+        };
+        file.lock_shared()?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+        self.len = bytes.len() as u64;
+        Ok(String::from_utf8_lossy(&bytes)
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(Into::into)
+            .collect())
+    }
+    fn write(&mut self, line: &str) -> io::Result<()> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(&self.path)?;
+        file.lock()?;
+        let len = file.metadata()?.len();
+        let mut record = String::with_capacity(line.len() + 2);
+        // Terminate a line left unfinished by a process that was interrupted
+        if len > 0 {
+            let mut last = [0];
+            file.seek(SeekFrom::Start(len - 1))?;
+            file.read_exact(&mut last)?;
+            if last[0] != b'\n' {
+                record.push('\n');
+            }
+        }
+        record.push_str(line);
+        record.push('\n');
+        file.write_all(record.as_bytes())?;
+        file.flush()?;
+        // Only count this write as seen if nothing else was written since the
+        // last read, so that other processes' entries are still reloaded
+        if len == self.len {
+            self.len += record.len() as u64;
+        }
+        Ok(())
+    }
+}
+
+impl HistoryStore for FileHistory {
+    fn load(&mut self) -> Vec<String> {
+        self.read().unwrap_or_default()
+    }
+    fn append(&mut self, entry: &HistoryEntry) {
+        let _ = self.write(&entry.line);
+    }
+    fn reload(&mut self) -> Option<Vec<String>> {
+        let len = fs::metadata(&self.path).map(|meta| meta.len()).unwrap_or(0);
+        if len == self.len {
+            None
+        } else {
+            Some(self.load())
+        }
+    }
+}
@@ -0,0 +1,256 @@
+use std::sync::Arc;
+
+use crossterm::KeyEvent;
+
+use crate::{CloseReason, ConsoleBuilder, WordCompleter};
+
+/// A check for an answer that returns an error message if it is invalid
+type Validator = Arc<dyn Fn(&str) -> Result<(), String> + Send + Sync>;
+
+/// The kind of answer a [`Form`] field asks for
+#[derive(Clone)]
+enum FieldKind {
+    Text,
+    Masked,
+    Select(Vec<String>),
+    Confirm(bool),
+}
+
+/// A single question in a [`Form`]
+#[derive(Clone)]
+struct Field {
+    name: String,
+    prompt: String,
+    kind: FieldKind,
+    validators: Vec<Validator>,
+}
+
+/// An answer to a single [`Form`] field
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Answer {
+    /// The text entered for a text or masked field
+    Text(String),
+    /// The option chosen for a select field
+    Selected(String),
+    /// The answer to a confirm field
+    Confirmed(bool),
+}
+
+/// The answers to a completed [`Form`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Answers {
+    answers: Vec<(String, Answer)>,
+}
+
+impl Answers {
+    /// Get the answer to the field with the given name
+    pub fn get(&self, name: &str) -> Option<&Answer> {
+        self.answers
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, answer)| answer)
+    }
+    /// Get the text entered for a text or masked field
+    pub fn text(&self, name: &str) -> Option<&str> {
+        match self.get(name)? {
+            Answer::Text(text) => Some(text),
+            _ => None,
+        }
+    }
+    /// Get the option chosen for a select field
+    pub fn selected(&self, name: &str) -> Option<&str> {
+        match self.get(name)? {
+            Answer::Selected(option) => Some(option),
+            _ => None,
+        }
+    }
+    /// Get the answer to a confirm field
+    pub fn confirmed(&self, name: &str) -> Option<bool> {
+        match self.get(name)? {
+            Answer::Confirmed(confirmed) => Some(*confirmed),
+            _ => None,
+        }
+    }
+    /// Iterate over the field names and their answers in order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Answer)> {
+        self.answers
+            .iter()
+            .map(|(name, answer)| (name.as_str(), answer))
+    }
+}
+
+/// A series of prompts that are asked one after another
+///
+/// Each field is asked with its own console line. Pressing Esc on an empty
+/// line goes back to the previous field, and pressing it on the first field
+/// cancels the form.
+///
+/// ```no_run
+/// use encore::Form;
+///
+/// let answers = Form::new()
+///     .text("name", "Server name: ")
+///     .validate(|name| {
+///         if name.is_empty() {
+///             Err("the name cannot be empty".into())
+///         } else {
+///             Ok(())
+///         }
+///     })
+///     .masked("password", "Password: ")
+///     .select("region", "Region: ", vec!["us", "eu", "asia"])
+///     .confirm("start", "Start now?", true)
+///     .run();
+/// if let Some(answers) = answers {
+///     println!("creating {}", answers.text("name").unwrap());
+/// }
+/// ```
+#[derive(Default)]
+pub struct Form {
+    fields: Vec<Field>,
+}
+
+impl Form {
+    /// Create a new empty `Form`
+    pub fn new() -> Self {
+        Self::default()
+    }
+    fn field<N, P>(mut self, name: N, prompt: P, kind: FieldKind) -> Self
+    where
+        N: Into<String>,
+        P: Into<String>,
+    {
+        self.fields.push(Field {
+            name: name.into(),
+            prompt: prompt.into(),
+            kind,
+            validators: Vec::new(),
+        });
+        self
+    }
+    /// Add a field that asks for a line of text
+    pub fn text<N, P>(self, name: N, prompt: P) -> Self
+    where
+        N: Into<String>,
+        P: Into<String>,
+    {
+        self.field(name, prompt, FieldKind::Text)
+    }
+    /// Add a field that asks for a line of text while hiding the input
+    pub fn masked<N, P>(self, name: N, prompt: P) -> Self
+    where
+        N: Into<String>,
+        P: Into<String>,
+    {
+        self.field(name, prompt, FieldKind::Masked)
+    }
+    /// Add a field that asks to choose one of some options
+    ///
+    /// The options are listed above the prompt and can be chosen by name or
+    /// by number. Tab completes option names.
+    pub fn select<N, P, I, S>(self, name: N, prompt: P, options: I) -> Self
+    where
+        N: Into<String>,
+        P: Into<String>,
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let options = options.into_iter().map(Into::into).collect();
+        self.field(name, prompt, FieldKind::Select(options))
+    }
+    /// Add a field that asks a yes or no question
+    ///
+    /// Submitting an empty line gives the default answer.
+    pub fn confirm<N, P>(self, name: N, prompt: P, default: bool) -> Self
+    where
+        N: Into<String>,
+        P: Into<String>,
+    {
+        self.field(name, prompt, FieldKind::Confirm(default))
+    }
+    /// Add a check to the most recently added field
+    ///
+    /// If the check returns an error, the message is printed and the field is
+    /// asked again.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no fields have been added
+    pub fn validate<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&str) -> Result<(), String> + Send + Sync + 'static,
+    {
+        self.fields
+            .last_mut()
+            .expect("validate called on a form with no fields")
+            .validators
+            .push(Arc::new(f));
+        self
+    }
+    /// Ask each field in order, blocking until the form is done
+    ///
+    /// Returns `None` if the form is cancelled or the input ends.
+    pub fn run(&self) -> Option<Answers> {
+        let mut answers = Vec::new();
+        while let Some(field) = self.fields.get(answers.len()) {
+            match field.ask() {
+                Ok(answer) => answers.push((field.name.clone(), answer)),
+                Err(CloseReason::UserQuit) if !answers.is_empty() => {
+                    answers.pop();
+                }
+                Err(_) => return None,
+            }
+        }
+        Some(Answers { answers })
+    }
+}
+
+impl Field {
+    /// Ask for the field's answer on its own console line
+    fn ask(&self) -> Result<Answer, CloseReason> {
+        let mut builder = ConsoleBuilder::new()
+            .prompt(self.prompt.clone())
+            .builtins(false)
+            .suggestions(false)
+            .quit_key(KeyEvent::Esc);
+        match &self.kind {
+            FieldKind::Text => {}
+            FieldKind::Masked => builder = builder.mask_input(true),
+            FieldKind::Select(options) => {
+                for (i, option) in options.iter().enumerate() {
+                    println!("  {}) {}", i + 1, option);
+                }
+                builder = builder.completer(WordCompleter::new(options.clone()));
+            }
+            FieldKind::Confirm(default) => {
+                let choices = if *default { "[Y/n]" } else { "[y/N]" };
+                builder = builder.prompt(format!("{} {} ", self.prompt, choices));
+            }
+        }
+        let field = self.clone();
+        builder.read_with(move |input| field.parse(input))
+    }
+    /// Check and parse an answer to the field
+    fn parse(&self, input: &str) -> Result<Answer, String> {
+        for validator in &self.validators {
+            validator(input)?;
+        }
+        match &self.kind {
+            FieldKind::Text | FieldKind::Masked => Ok(Answer::Text(input.into())),
+            FieldKind::Select(options) => input
+                .parse::<usize>()
+                .ok()
+                .and_then(|n| n.checked_sub(1))
+                .and_then(|i| options.get(i))
+                .or_else(|| options.iter().find(|option| option.as_str() == input))
+                .map(|option| Answer::Selected(option.clone()))
+                .ok_or_else(|| format!("choose one of: {}", options.join(", "))),
+            FieldKind::Confirm(default) => match input.to_lowercase().as_str() {
+                "" => Ok(Answer::Confirmed(*default)),
+                "y" | "yes" => Ok(Answer::Confirmed(true)),
+                "n" | "no" => Ok(Answer::Confirmed(false)),
+                _ => Err("answer yes or no".into()),
+            },
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use std::time::{Duration, Instant};
+
+/// Output waiting to be printed with the next frame
+pub(crate) struct Frames {
+    /// The shortest time between frames
+    interval: Duration,
+    /// When the last frame was drawn
+    last: Option<Instant>,
+    /// The lines printed since the last frame
+    batch: String,
+}
+
+impl Frames {
+    pub fn new(max_rate: u32) -> Self {
+        Frames {
+            interval: Duration::from_secs(1) / max_rate.max(1),
+            last: None,
+            batch: String::new(),
+        }
+    }
+    /// Get when the next frame can be drawn
+    fn next(&self) -> Option<Instant> {
+        self.last.map(|last| last + self.interval)
+    }
+    /// Get when the batched output is due to be printed, if there is any
+    pub fn due(&self) -> Option<Instant> {
+        if self.batch.is_empty() {
+            None
+        } else {
+            self.next()
+        }
+    }
+    /// Add some output to the batch
+    ///
+    /// Returns `None` if the output has to wait for the next frame, or all of
+    /// the batched output if a frame can be drawn now.
+    pub fn push(&mut self, output: &str) -> Option<String> {
+        self.batch.push_str(output);
+        self.batch.push('\n');
+        self.take(Instant::now())
+    }
+    /// Take the batched output if a frame can be drawn
+    pub fn take(&mut self, now: Instant) -> Option<String> {
+        if self.batch.is_empty() || self.next().is_some_and(|next| now < next) {
+            return None;
+        }
+        self.last = Some(now);
+        Some(std::mem::take(&mut self.batch))
+    }
+    /// Take the batched output whether a frame is due or not
+    pub fn take_all(&mut self) -> String {
+        std::mem::take(&mut self.batch)
+    }
+}
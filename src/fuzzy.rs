@@ -0,0 +1,55 @@
+use crate::CaseMatching;
+
+/// Score how well a pattern fuzzily matches some text
+///
+/// The pattern matches if all of its characters appear in the text in order.
+/// Matches score higher when matched characters are consecutive or start
+/// words. Returns the score and the byte indices of the matched characters,
+/// or `None` if the pattern does not match.
+///
+/// Letter case is ignored, but matches in the same case score higher.
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<(i64, Vec<usize>)> {
+    fuzzy_match_case(pattern, text, CaseMatching::Insensitive)
+}
+
+/// Score how well a pattern fuzzily matches some text, matching letter case
+/// in the given way
+pub(crate) fn fuzzy_match_case(
+    pattern: &str,
+    text: &str,
+    case: CaseMatching,
+) -> Option<(i64, Vec<usize>)> {
+    let mut score = 0;
+    let mut indices = Vec::with_capacity(pattern.len());
+    let mut text_chars = text.char_indices().peekable();
+    let mut prev: Option<(usize, char)> = None;
+    for p in pattern.chars() {
+        loop {
+            let (i, c) = text_chars.next()?;
+            if case.chars_match(pattern, p, c) {
+                score += 1;
+                match prev {
+                    // Consecutive match
+                    Some((j, pc)) if j + pc.len_utf8() == i => score += 5,
+                    Some(_) => {}
+                    // Match at the start of the text
+                    None if i == 0 => score += 8,
+                    None => {}
+                }
+                // Match at the start of a word
+                if i > 0 && !text[..i].ends_with(char::is_alphanumeric) {
+                    score += 3;
+                }
+                if c == p {
+                    score += 1;
+                }
+                indices.push(i);
+                prev = Some((i, c));
+                break;
+            }
+            // Penalize gaps
+            score -= 1;
+        }
+    }
+    Some((score, indices))
+}
@@ -0,0 +1,102 @@
+use std::time::Duration;
+
+use egui::{
+    text::{CCursor, CCursorRange},
+    Key, Response, RichText, ScrollArea, TextEdit, TextStyle, Ui, Widget,
+};
+
+use crate::{Console, EmbeddedConsole};
+
+/// How often the widget checks for new output while the window is idle
+const REFRESH: Duration = Duration::from_millis(100);
+
+/// An egui widget that shows an [`EmbeddedConsole`]
+///
+/// The widget shows the console's output above an input line. Enter runs
+/// the input, Tab completes it, and Up and Down browse the history, all
+/// through the same console as the terminal.
+///
+/// ```ignore
+/// ui.add(ConsoleWidget::new(&mut self.overlay, &self.console));
+/// ```
+pub struct ConsoleWidget<'a, M: Send + 'static> {
+    state: &'a mut EmbeddedConsole,
+    console: &'a Console<M>,
+}
+
+impl<'a, M: Send + 'static> ConsoleWidget<'a, M> {
+    /// Create a new `ConsoleWidget` for some state and its console
+    pub fn new(state: &'a mut EmbeddedConsole, console: &'a Console<M>) -> Self {
+        ConsoleWidget { state, console }
+    }
+}
+
+impl<M: Send + 'static> Widget for ConsoleWidget<'_, M> {
+    fn ui(self, ui: &mut Ui) -> Response {
+        let ConsoleWidget { state, console } = self;
+        let input_height =
+            ui.text_style_height(&TextStyle::Monospace) + ui.spacing().item_spacing.y * 4.0;
+        ScrollArea::vertical()
+            .stick_to_bottom(true)
+            .auto_shrink([false, false])
+            .max_height((ui.available_height() - input_height * 2.0).max(0.0))
+            .show(ui, |ui| {
+                for line in state.output() {
+                    ui.label(RichText::new(line).monospace());
+                }
+            });
+        if !state.candidates().is_empty() {
+            ui.horizontal_wrapped(|ui| {
+                for candidate in state.candidates() {
+                    ui.label(RichText::new(&candidate.replacement).monospace().weak());
+                }
+            });
+        }
+        ui.horizontal(|ui| {
+            ui.label(RichText::new(state.prompt_text()).monospace());
+            let mut text = state.input().to_string();
+            let mut output = TextEdit::singleline(&mut text)
+                .id_salt("encore console input")
+                .font(TextStyle::Monospace)
+                .desired_width(f32::INFINITY)
+                .lock_focus(true)
+                .show(ui);
+            if output.response.changed() {
+                state.set_input(text.as_str());
+            }
+            if let Some(range) = output.cursor_range {
+                let cursor = text
+                    .char_indices()
+                    .nth(range.primary.ccursor.index)
+                    .map_or(text.len(), |(i, _)| i);
+                state.set_cursor(cursor);
+            }
+            let before = state.input().to_string();
+            if output.response.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter)) {
+                state.submit(console);
+                output.response.request_focus();
+            } else if output.response.has_focus() {
+                if ui.input(|i| i.key_pressed(Key::Tab)) {
+                    state.complete(console);
+                } else if ui.input(|i| i.key_pressed(Key::ArrowUp)) {
+                    state.history_prev(console);
+                } else if ui.input(|i| i.key_pressed(Key::ArrowDown)) {
+                    state.history_next();
+                }
+            }
+            // Move the cursor to the end of input that was replaced
+            if state.input() != before {
+                let end = CCursor::new(state.input().chars().count());
+                output
+                    .state
+                    .cursor
+                    .set_char_range(Some(CCursorRange::one(end)));
+                output.state.store(ui.ctx(), output.response.id);
+                ui.ctx().request_repaint();
+            }
+            ui.ctx().request_repaint_after(REFRESH);
+            output.response
+        })
+        .inner
+    }
+}
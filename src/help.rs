@@ -0,0 +1,52 @@
+use crate::Candidate;
+
+/// An entry in the inline help shown when `?` is typed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HelpEntry {
+    /// The argument, flag, or subcommand
+    pub name: String,
+    /// A description of the entry
+    pub description: Option<String>,
+}
+
+impl HelpEntry {
+    /// Create a new `HelpEntry`
+    pub fn new<S: Into<String>>(name: S) -> Self {
+        HelpEntry {
+            name: name.into(),
+            description: None,
+        }
+    }
+    /// Set the description
+    pub fn description<S: Into<String>>(mut self, description: S) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+}
+
+impl From<Candidate> for HelpEntry {
+    fn from(candidate: Candidate) -> Self {
+        HelpEntry {
+            name: candidate.replacement,
+            description: candidate.description,
+        }
+    }
+}
+
+/// Render help entries as aligned rows
+pub(crate) fn render_help(entries: &[HelpEntry]) -> String {
+    let name_width = entries.iter().map(|e| e.name.len()).max().unwrap_or(0);
+    let mut rendered = String::new();
+    for entry in entries {
+        match &entry.description {
+            Some(description) => rendered.push_str(&format!(
+                "  {:width$}  {}\n",
+                entry.name,
+                description,
+                width = name_width
+            )),
+            None => rendered.push_str(&format!("  {}\n", entry.name)),
+        }
+    }
+    rendered
+}
@@ -0,0 +1,62 @@
+/// The state of a block of lines being captured after `command <<TERM`
+pub(crate) struct Heredoc {
+    /// The command the block is given to
+    pub command: String,
+    /// The line that ends the block
+    pub terminator: String,
+    /// The prompt before the block started
+    pub prompt: String,
+    pub lines: Vec<String>,
+}
+
+impl Heredoc {
+    /// Start capturing a block if an input ends with `<<TERM`
+    ///
+    /// The terminator can be quoted, like `<<'EOF'`. It must not start with a
+    /// digit, so that expressions like `1 << 4` are left alone.
+    pub fn start(input: &str, prompt: String) -> Option<Self> {
+        let (command, terminator) = input.rsplit_once("<<")?;
+        let terminator = terminator.trim();
+        let terminator = ['\'', '"']
+            .iter()
+            .find_map(|&quote| {
+                terminator
+                    .strip_prefix(quote)
+                    .and_then(|t| t.strip_suffix(quote))
+            })
+            .unwrap_or(terminator);
+        let valid = |c: char| c.is_alphanumeric() || c == '_' || c == '-';
+        let command = command.trim_end();
+        if command.is_empty()
+            || !terminator.chars().all(valid)
+            || !terminator.starts_with(|c: char| !c.is_ascii_digit())
+        {
+            return None;
+        }
+        Some(Heredoc {
+            command: command.into(),
+            terminator: terminator.into(),
+            prompt,
+            lines: Vec::new(),
+        })
+    }
+    /// Add a line to the block
+    ///
+    /// Returns `false` if the line was the terminator
+    pub fn push(&mut self, line: String) -> bool {
+        if line.trim_end() == self.terminator {
+            return false;
+        }
+        self.lines.push(line);
+        true
+    }
+    /// Get the command followed by the lines of the block
+    pub fn payload(&self) -> String {
+        let mut payload = self.command.clone();
+        for line in &self.lines {
+            payload.push('\n');
+            payload.push_str(line);
+        }
+        payload
+    }
+}
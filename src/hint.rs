@@ -0,0 +1,55 @@
+use std::ops::Range;
+
+use colored::Colorize;
+
+/// A hint shown below the input line while typing
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Hint {
+    /// The text of the hint
+    pub text: String,
+    /// A byte range of the text to emphasize, such as the argument under the
+    /// cursor
+    pub highlight: Option<Range<usize>>,
+}
+
+impl Hint {
+    /// Create a new `Hint`
+    pub fn new<S: Into<String>>(text: S) -> Self {
+        Hint {
+            text: text.into(),
+            highlight: None,
+        }
+    }
+    /// Set the range of the text to emphasize
+    pub fn highlight(mut self, range: Range<usize>) -> Self {
+        self.highlight = Some(range);
+        self
+    }
+    /// Render the hint dimly with the highlighted range emphasized
+    pub(crate) fn render(&self) -> String {
+        match &self.highlight {
+            Some(range) => format!(
+                "{}{}{}",
+                self.text[..range.start].dimmed(),
+                self.text[range.clone()].bold(),
+                self.text[range.end..].dimmed()
+            ),
+            None => self.text.dimmed().to_string(),
+        }
+    }
+}
+
+/// Behavior for providing hints about the input being typed
+pub trait Hinter {
+    /// Get a hint for the input with the cursor at the given byte index
+    fn hint(&mut self, input: &str, cursor: usize) -> Option<Hint>;
+}
+
+impl<F> Hinter for F
+where
+    F: FnMut(&str, usize) -> Option<Hint>,
+{
+    fn hint(&mut self, input: &str, cursor: usize) -> Option<Hint> {
+        self(input, cursor)
+    }
+}
@@ -0,0 +1,151 @@
+use std::{borrow::Cow, error::Error, fmt, time::SystemTime};
+
+use crate::SessionId;
+
+/// An entry in the history
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryEntry {
+    /// The line that was entered
+    pub line: String,
+    /// The time at which the line was entered
+    pub timestamp: SystemTime,
+    /// The session the line was entered in
+    pub session: SessionId,
+    /// The exit status of the command, if known
+    pub exit_status: Option<i32>,
+}
+
+/// Persistent storage for the history
+///
+/// If a store is set with
+/// [`ConsoleBuilder::history_store`](crate::ConsoleBuilder::history_store),
+/// the console loads the history from it when it starts and appends every
+/// entered line to it.
+pub trait HistoryStore: Send {
+    /// Load the stored lines, oldest first
+    fn load(&mut self) -> Vec<String>;
+    /// Store a new entry
+    fn append(&mut self, entry: &HistoryEntry);
+    /// Load the stored lines again if something else has changed them since
+    /// they were last loaded
+    ///
+    /// This lets several consoles that share a store see each other's
+    /// entries. The console calls it when the user starts moving through or
+    /// searching the history. By default, the lines are never reloaded.
+    fn reload(&mut self) -> Option<Vec<String>> {
+        None
+    }
+    /// Record the exit status of the most recent entry with the given line
+    /// from a session
+    ///
+    /// The console calls this when the app reports whether a command
+    /// succeeded with [`Console::report_outcome`](crate::Console::report_outcome),
+    /// with `0` for success and `1` for failure. By default, it does nothing.
+    fn set_exit_status(&mut self, line: &str, session: SessionId, exit_status: i32) {
+        let _ = (line, session, exit_status);
+    }
+    /// Load the stored lines whose commands failed the last time they were
+    /// run
+    ///
+    /// By default, no lines are known to have failed.
+    fn load_failed(&mut self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// The way Up and Down move through the history
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HistoryNavigation {
+    /// Move through every entry
+    #[default]
+    Plain,
+    /// Move through only the entries that start with the text that was typed
+    /// before navigating
+    Prefix,
+}
+
+/// The way commands that failed are treated in the history
+///
+/// A command fails if the app reports it with
+/// [`Console::report_outcome`](crate::Console::report_outcome), or if it is a
+/// shell command that exits with a nonzero status.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FailedCommands {
+    /// Treat failed commands like any other
+    #[default]
+    Show,
+    /// Mark failed commands with a red `✗` when moving through or searching
+    /// the history
+    Mark,
+    /// Leave failed commands out when moving through or searching the history
+    Skip,
+}
+
+/// An error indicating that a history expansion referred to an entry that
+/// does not exist
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventNotFound(pub String);
+
+impl fmt::Display for EventNotFound {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: event not found", self.0)
+    }
+}
+
+impl Error for EventNotFound {}
+
+/// Expand `!!` and `!N` references to history entries
+///
+/// `!!` is replaced with the most recent entry, and `!N` is replaced with
+/// the entry numbered `N`, starting from 1.
+pub fn expand_history<'a>(
+    input: &'a str,
+    history: &[String],
+) -> Result<Cow<'a, str>, EventNotFound> {
+    if !input.contains('!') {
+        return Ok(input.into());
+    }
+    let mut output = String::with_capacity(input.len());
+    let mut chars = input.char_indices().peekable();
+    let mut expanded = false;
+    while let Some((i, c)) = chars.next() {
+        if c != '!' {
+            output.push(c);
+            continue;
+        }
+        match chars.peek() {
+            Some(&(_, '!')) => {
+                chars.next();
+                let last = history.last().ok_or_else(|| EventNotFound("!!".into()))?;
+                output.push_str(last);
+                expanded = true;
+            }
+            Some(&(_, d)) if d.is_ascii_digit() => {
+                let mut end = i + 1;
+                while let Some(&(j, d)) = chars.peek() {
+                    if d.is_ascii_digit() {
+                        end = j + 1;
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                let reference = &input[i..end];
+                let entry = reference[1..]
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|n| n.checked_sub(1))
+                    .and_then(|n| history.get(n))
+                    .ok_or_else(|| EventNotFound(reference.into()))?;
+                output.push_str(entry);
+                expanded = true;
+            }
+            _ => output.push(c),
+        }
+    }
+    Ok(if expanded {
+        output.into()
+    } else {
+        input.into()
+    })
+}
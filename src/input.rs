@@ -0,0 +1,400 @@
+use std::{
+    collections::VecDeque,
+    error::Error,
+    fmt,
+    io::BufRead,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Condvar, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use crossterm::{InputEvent, TerminalInput};
+
+use crate::Role;
+
+/// An input from an [`InputSource`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Input {
+    /// A key press, mouse event, or other terminal event
+    Event(InputEvent),
+    /// A whole line, which is submitted as though it were typed
+    Line(String),
+}
+
+/// Where an input came from
+///
+/// Messages can be received with their origin with
+/// [`Console::poll_with_origin`](crate::Console::poll_with_origin), and
+/// output can be sent back to an origin with
+/// [`Responder::print_to`](crate::Responder::print_to).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub enum Origin {
+    /// The local terminal
+    #[default]
+    Terminal,
+    /// An input source with the contained name
+    Source(String),
+    /// The console itself, such as a scheduled job or a timer
+    Console,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Origin::Terminal => write!(f, "terminal"),
+            Origin::Source(name) => write!(f, "{}", name),
+            Origin::Console => write!(f, "console"),
+        }
+    }
+}
+
+/// A source of input for a [`Console`](crate::Console)
+///
+/// Sources are added with
+/// [`ConsoleBuilder::input_source`](crate::ConsoleBuilder::input_source).
+/// Each source is read on its own thread, so a console can take input from
+/// several at once. The console closes once every source has run out of
+/// input.
+pub trait InputSource: Send {
+    /// Wait for the next input
+    ///
+    /// Returns `None` once the source has no more input
+    fn next_input(&mut self) -> Option<Input>;
+    /// Get the origin of the input that was last returned
+    ///
+    /// By default, input is treated as coming from the terminal.
+    fn origin(&self) -> Origin {
+        Origin::Terminal
+    }
+    /// Get what the source's input is allowed to do
+    ///
+    /// By default, the input can run any command.
+    fn role(&self) -> Role {
+        Role::Full
+    }
+    /// Get the user who entered the input that was last returned
+    ///
+    /// By default, the input has no authenticated user.
+    fn user(&self) -> Option<String> {
+        None
+    }
+}
+
+/// An [`InputSource`] that reads key presses and mouse events from the
+/// terminal
+///
+/// This is the source that a console uses if no others are added. The
+/// terminal is read by a single shared thread, and its events go to the most
+/// recently created source whose console is still open, so consoles that are
+/// opened one after another do not lose key presses to each other.
+pub struct TerminalSource {
+    closed: Arc<AtomicBool>,
+}
+
+impl TerminalSource {
+    /// Create a new `TerminalSource`
+    pub fn new() -> Self {
+        Self::until_closed(Arc::new(AtomicBool::new(false)))
+    }
+    /// Create a `TerminalSource` that stops taking events once the flag is set
+    pub(crate) fn until_closed(closed: Arc<AtomicBool>) -> Self {
+        TERMINAL.lock().unwrap().readers.push(Arc::clone(&closed));
+        TerminalSource { closed }
+    }
+    /// Create a `TerminalSource` for a console, unless another open console
+    /// is already reading the terminal
+    pub(crate) fn claim(closed: Arc<AtomicBool>) -> Result<Self, TerminalInUse> {
+        let mut terminal = TERMINAL.lock().unwrap();
+        terminal
+            .readers
+            .retain(|closed| !closed.load(Ordering::Relaxed));
+        if !terminal.readers.is_empty() {
+            return Err(TerminalInUse);
+        }
+        terminal.readers.push(Arc::clone(&closed));
+        Ok(TerminalSource { closed })
+    }
+}
+
+/// An error indicating that another open console is already reading the
+/// terminal
+///
+/// Two consoles cannot share the terminal, since they would both draw their
+/// input line. Give one of them its own
+/// [input source](crate::ConsoleBuilder::input_source), or close the first
+/// one before building the second.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TerminalInUse;
+
+impl fmt::Display for TerminalInUse {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "another console is already reading the terminal")
+    }
+}
+
+impl Error for TerminalInUse {}
+
+impl Default for TerminalSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for TerminalSource {
+    fn drop(&mut self) {
+        TERMINAL
+            .lock()
+            .unwrap()
+            .readers
+            .retain(|closed| !Arc::ptr_eq(closed, &self.closed));
+    }
+}
+
+impl InputSource for TerminalSource {
+    fn next_input(&mut self) -> Option<Input> {
+        let mut terminal = TERMINAL.lock().unwrap();
+        if !terminal.reading {
+            terminal.reading = true;
+            thread::spawn(read_terminal);
+        }
+        loop {
+            terminal
+                .readers
+                .retain(|closed| !closed.load(Ordering::Relaxed));
+            if self.closed.load(Ordering::Relaxed) {
+                return None;
+            }
+            let newest = terminal
+                .readers
+                .last()
+                .is_some_and(|closed| Arc::ptr_eq(closed, &self.closed));
+            if newest {
+                if let Some(event) = terminal.events.pop_front() {
+                    return Some(Input::Event(event));
+                }
+            }
+            // Wake up now and then to notice closed consoles
+            terminal = TERMINAL_READY
+                .wait_timeout(terminal, Duration::from_millis(50))
+                .unwrap()
+                .0;
+        }
+    }
+}
+
+/// Terminal events that have not been taken by a [`TerminalSource`] yet
+struct TerminalQueue {
+    events: VecDeque<InputEvent>,
+    /// The closed flags of the terminal sources, newest last
+    readers: Vec<Arc<AtomicBool>>,
+    /// Whether the thread reading the terminal is running
+    reading: bool,
+    /// The number of [`TerminalPause`]s that are active
+    pauses: usize,
+    /// Whether the reading thread has stopped reading for a pause
+    paused: bool,
+}
+
+static TERMINAL: Mutex<TerminalQueue> = Mutex::new(TerminalQueue {
+    events: VecDeque::new(),
+    readers: Vec::new(),
+    reading: false,
+    pauses: 0,
+    paused: false,
+});
+
+static TERMINAL_READY: Condvar = Condvar::new();
+
+/// How long the reading thread waits for input before checking for a pause
+#[cfg(unix)]
+const PAUSE_CHECK_INTERVAL: Duration = Duration::from_millis(50);
+/// How long to wait for the terminal to report the cursor position
+#[cfg(unix)]
+const CURSOR_RESPONSE_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// Read terminal events into the queue until every [`TerminalSource`] is
+/// closed or dropped
+///
+/// On Windows, the thread only notices after the next event is read.
+fn read_terminal() {
+    let mut events = TerminalInput::new().read_sync();
+    #[cfg(unix)]
+    let tty = std::fs::File::open("/dev/tty").ok();
+    loop {
+        let mut terminal = TERMINAL.lock().unwrap();
+        terminal
+            .readers
+            .retain(|closed| !closed.load(Ordering::Relaxed));
+        if terminal.readers.is_empty() {
+            // Leave the keys to the app until another source is created
+            terminal.reading = false;
+            terminal.paused = true;
+            terminal.events.clear();
+            TERMINAL_READY.notify_all();
+            return;
+        }
+        while terminal.pauses > 0 {
+            terminal.paused = true;
+            TERMINAL_READY.notify_all();
+            terminal = TERMINAL_READY.wait(terminal).unwrap();
+        }
+        terminal.paused = false;
+        drop(terminal);
+        // Only read once there is input, so that a pause never waits for a
+        // read that would take the input of a child process
+        #[cfg(unix)]
+        if let Some(tty) = &tty {
+            if !input_ready(tty, PAUSE_CHECK_INTERVAL) {
+                continue;
+            }
+        }
+        match events.next() {
+            Some(event) => {
+                TERMINAL.lock().unwrap().events.push_back(event);
+                TERMINAL_READY.notify_all();
+            }
+            None => return,
+        }
+    }
+}
+
+/// Wait for a file to have input to read
+#[cfg(unix)]
+fn input_ready(file: &std::fs::File, timeout: Duration) -> bool {
+    use std::os::unix::io::AsRawFd;
+    let mut fd = libc::pollfd {
+        fd: file.as_raw_fd(),
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    let ready = unsafe { libc::poll(&mut fd, 1, timeout.as_millis() as libc::c_int) };
+    ready > 0
+}
+
+/// A guard that stops the console from reading the terminal until it is
+/// dropped
+///
+/// This is held while a child process, like a shell command or an editor,
+/// uses the terminal, so that the child gets all of the key presses.
+pub(crate) struct TerminalPause;
+
+impl TerminalPause {
+    /// Stop reading the terminal, waiting for the reading thread to notice
+    ///
+    /// The thread cannot stop in the middle of a read on Windows, so the
+    /// terminal is only paused on Unix.
+    pub fn new() -> Self {
+        let mut terminal = TERMINAL.lock().unwrap();
+        terminal.pauses += 1;
+        if cfg!(unix) && terminal.reading {
+            TERMINAL_READY.notify_all();
+            while !terminal.paused {
+                terminal = TERMINAL_READY.wait(terminal).unwrap();
+            }
+        }
+        TerminalPause
+    }
+}
+
+impl Drop for TerminalPause {
+    fn drop(&mut self) {
+        let mut terminal = TERMINAL.lock().unwrap();
+        terminal.pauses -= 1;
+        TERMINAL_READY.notify_all();
+    }
+}
+
+/// Get the row of the terminal cursor, starting at 1
+///
+/// On Unix, this asks the terminal for the cursor position, so it pauses
+/// the reading thread to keep it from taking the response.
+pub(crate) fn cursor_row() -> Option<u16> {
+    #[cfg(unix)]
+    {
+        use std::io::{Read, Write};
+        let _pause = TerminalPause::new();
+        let mut tty = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open("/dev/tty")
+            .ok()?;
+        tty.write_all(b"\x1b[6n").ok()?;
+        tty.flush().ok()?;
+        // The response looks like `ESC [ row ; column R`
+        let mut response = Vec::new();
+        while response.last() != Some(&b'R') {
+            if response.len() > 32 || !input_ready(&tty, CURSOR_RESPONSE_TIMEOUT) {
+                return None;
+            }
+            let mut byte = [0];
+            tty.read_exact(&mut byte).ok()?;
+            response.push(byte[0]);
+        }
+        let response = String::from_utf8(response).ok()?;
+        let start = response.rfind('[')? + 1;
+        let end = response[start..].find(';')? + start;
+        response[start..end].parse().ok()
+    }
+    #[cfg(not(unix))]
+    {
+        Some(crossterm::TerminalCursor::new().pos().1 + 1)
+    }
+}
+
+/// An [`InputSource`] that reads lines from a reader
+///
+/// This can read commands from a script, a socket, or a test harness.
+pub struct LineSource<R> {
+    reader: R,
+    origin: Origin,
+    role: Role,
+}
+
+impl<R: BufRead> LineSource<R> {
+    /// Create a `LineSource` that reads from the given reader
+    pub fn new(reader: R) -> Self {
+        LineSource {
+            reader,
+            origin: Origin::Terminal,
+            role: Role::Full,
+        }
+    }
+    /// Set the origin of the lines
+    ///
+    /// By default, lines are treated as coming from the terminal.
+    pub fn origin(mut self, origin: Origin) -> Self {
+        self.origin = origin;
+        self
+    }
+    /// Set what the lines are allowed to do
+    ///
+    /// By default, lines can run any command, so lines that do not come
+    /// from a trusted place should be given [`Role::Restricted`].
+    pub fn role(mut self, role: Role) -> Self {
+        self.role = role;
+        self
+    }
+}
+
+impl<R: BufRead + Send> InputSource for LineSource<R> {
+    fn next_input(&mut self) -> Option<Input> {
+        let mut line = String::new();
+        match self.reader.read_line(&mut line) {
+            Ok(0) | Err(_) => None,
+            Ok(_) => {
+                let len = line.trim_end_matches(&['\r', '\n'][..]).len();
+                line.truncate(len);
+                Some(Input::Line(line))
+            }
+        }
+    }
+    fn origin(&self) -> Origin {
+        self.origin.clone()
+    }
+    fn role(&self) -> Role {
+        self.role
+    }
+}
@@ -0,0 +1,196 @@
+use std::{error::Error, fmt, net::IpAddr, str::FromStr};
+
+/// A range of IP addresses, like `10.0.0.0/8`
+///
+/// Ranges are used to allow or deny connections with
+/// [`ConsoleBuilder::allow_ip`](crate::ConsoleBuilder::allow_ip) and
+/// [`ConsoleBuilder::deny_ip`](crate::ConsoleBuilder::deny_ip). They are
+/// parsed from CIDR notation or from a single address.
+///
+/// Only connections accepted with
+/// [`Console::accept_tcp`](crate::Console::accept_tcp) are filtered by
+/// address. Unix sockets and other input sources are not.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IpRange {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl IpRange {
+    /// Create an `IpRange` of the addresses whose first `prefix` bits are the
+    /// same as the given address's
+    ///
+    /// Returns `None` if the prefix is longer than the address
+    pub fn new(addr: IpAddr, prefix: u8) -> Option<Self> {
+        (prefix <= bits(addr)).then_some(IpRange { addr, prefix })
+    }
+    /// Check if the range contains an address
+    ///
+    /// An IPv4 address and the same address mapped to IPv6, like
+    /// `::ffff:10.0.0.1`, are treated as the same, so either form can be
+    /// matched by a range written in either form.
+    pub fn contains(&self, addr: IpAddr) -> bool {
+        let mapped = match addr {
+            IpAddr::V4(v4) => Some(IpAddr::V6(v4.to_ipv6_mapped())),
+            IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4),
+        };
+        self.contains_exactly(addr) || mapped.is_some_and(|addr| self.contains_exactly(addr))
+    }
+    /// Check if the range contains an address, without mapping between IPv4
+    /// and IPv6
+    fn contains_exactly(&self, addr: IpAddr) -> bool {
+        match (self.addr, addr) {
+            (IpAddr::V4(range), IpAddr::V4(addr)) => same_prefix(
+                u32::from(range).into(),
+                u32::from(addr).into(),
+                self.prefix,
+                32,
+            ),
+            (IpAddr::V6(range), IpAddr::V6(addr)) => {
+                same_prefix(range.into(), addr.into(), self.prefix, 128)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Get the number of bits in an address
+fn bits(addr: IpAddr) -> u8 {
+    match addr {
+        IpAddr::V4(_) => 32,
+        IpAddr::V6(_) => 128,
+    }
+}
+
+/// Check if the first `prefix` of the lowest `bits` bits of two numbers are
+/// the same
+fn same_prefix(a: u128, b: u128, prefix: u8, bits: u8) -> bool {
+    prefix == 0 || (a ^ b) >> (bits - prefix) == 0
+}
+
+impl fmt::Display for IpRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix)
+    }
+}
+
+impl From<IpAddr> for IpRange {
+    fn from(addr: IpAddr) -> Self {
+        IpRange {
+            addr,
+            prefix: bits(addr),
+        }
+    }
+}
+
+impl FromStr for IpRange {
+    type Err = InvalidIpRange;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidIpRange(s.into());
+        match s.split_once('/') {
+            Some((addr, prefix)) => {
+                let addr = addr.parse().map_err(|_| invalid())?;
+                let prefix = prefix.parse().map_err(|_| invalid())?;
+                IpRange::new(addr, prefix).ok_or_else(invalid)
+            }
+            None => s.parse::<IpAddr>().map(Into::into).map_err(|_| invalid()),
+        }
+    }
+}
+
+/// An error indicating that a string is not an [`IpRange`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidIpRange(pub String);
+
+impl fmt::Display for InvalidIpRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "invalid IP range: {} (expected an address or a range like 10.0.0.0/8)",
+            self.0
+        )
+    }
+}
+
+impl Error for InvalidIpRange {}
+
+/// The ranges of addresses that remote connections are allowed from
+#[derive(Debug, Clone, Default)]
+pub(crate) struct IpFilter {
+    /// If not empty, only these ranges are allowed
+    pub allow: Vec<IpRange>,
+    /// These ranges are denied even if they are allowed
+    pub deny: Vec<IpRange>,
+}
+
+impl IpFilter {
+    /// Check if connections from an address are allowed
+    pub fn permits(&self, addr: IpAddr) -> bool {
+        let allowed = self.allow.is_empty() || self.allow.iter().any(|range| range.contains(addr));
+        allowed && !self.deny.iter().any(|range| range.contains(addr))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(s: &str) -> IpRange {
+        s.parse().unwrap()
+    }
+
+    fn addr(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(range("10.0.0.0/8").to_string(), "10.0.0.0/8");
+        assert_eq!(range("10.1.2.3").to_string(), "10.1.2.3/32");
+        assert_eq!(range("::1").to_string(), "::1/128");
+        assert_eq!(range("fe80::/10").to_string(), "fe80::/10");
+        for invalid in ["10.0.0.0/33", "::/129", "10.0.0.0/", "10.0.0/8", "/8", "x"] {
+            assert!(invalid.parse::<IpRange>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn prefixes() {
+        assert!(range("0.0.0.0/0").contains(addr("255.255.255.255")));
+        assert!(range("::/0").contains(addr("ffff::1")));
+        assert!(range("10.1.2.3/32").contains(addr("10.1.2.3")));
+        assert!(!range("10.1.2.3/32").contains(addr("10.1.2.4")));
+        assert!(range("::1/128").contains(addr("::1")));
+        assert!(!range("::1/128").contains(addr("::2")));
+        assert!(range("192.168.0.0/16").contains(addr("192.168.255.1")));
+        assert!(!range("192.168.0.0/16").contains(addr("192.169.0.1")));
+        assert!(!range("10.0.0.0/8").contains(addr("::a00:1")));
+    }
+
+    #[test]
+    fn mapped() {
+        assert!(range("10.0.0.0/8").contains(addr("::ffff:10.0.0.1")));
+        assert!(range("::ffff:0:0/96").contains(addr("10.0.0.1")));
+        assert!(range("::ffff:0:0/96").contains(addr("::ffff:10.0.0.1")));
+        assert!(range("::ffff:10.0.0.0/104").contains(addr("10.0.0.1")));
+        assert!(!range("::ffff:10.0.0.0/104").contains(addr("11.0.0.1")));
+    }
+
+    #[test]
+    fn deny_over_allow() {
+        let filter = IpFilter {
+            allow: vec![range("10.0.0.0/8")],
+            deny: vec![range("10.0.0.5")],
+        };
+        assert!(filter.permits(addr("10.0.0.4")));
+        assert!(!filter.permits(addr("10.0.0.5")));
+        assert!(!filter.permits(addr("::ffff:10.0.0.5")));
+        assert!(!filter.permits(addr("11.0.0.1")));
+        let filter = IpFilter {
+            allow: Vec::new(),
+            deny: vec![range("::ffff:0:0/96")],
+        };
+        assert!(!filter.permits(addr("10.0.0.1")));
+        assert!(filter.permits(addr("::1")));
+    }
+}
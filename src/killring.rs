@@ -0,0 +1,49 @@
+use std::collections::VecDeque;
+
+/// The maximum number of entries kept in a [`KillRing`]
+const KILL_RING_SIZE: usize = 30;
+
+/// Text that was killed and can be yanked back
+#[derive(Default)]
+pub(crate) struct KillRing {
+    entries: VecDeque<String>,
+    index: usize,
+}
+
+impl KillRing {
+    /// Add killed text to the ring
+    ///
+    /// If `merge` is set, the text is merged with the most recent entry,
+    /// before it if `prepend` is set and after it otherwise.
+    pub fn kill(&mut self, text: String, merge: bool, prepend: bool) {
+        if text.is_empty() {
+            return;
+        }
+        self.index = 0;
+        if merge {
+            if let Some(last) = self.entries.front_mut() {
+                if prepend {
+                    last.insert_str(0, &text);
+                } else {
+                    last.push_str(&text);
+                }
+                return;
+            }
+        }
+        self.entries.push_front(text);
+        self.entries.truncate(KILL_RING_SIZE);
+    }
+    /// Get the most recently killed text
+    pub fn yank(&mut self) -> Option<&str> {
+        self.index = 0;
+        self.entries.front().map(String::as_str)
+    }
+    /// Get the text killed before the last yanked text
+    pub fn yank_pop(&mut self) -> Option<&str> {
+        if self.entries.is_empty() {
+            return None;
+        }
+        self.index = (self.index + 1) % self.entries.len();
+        self.entries.get(self.index).map(String::as_str)
+    }
+}
@@ -0,0 +1,57 @@
+use std::{collections::VecDeque, fmt, sync::Mutex, time::Duration};
+
+/// The most recent samples kept of each latency
+const MAX_SAMPLES: usize = 1000;
+
+/// Latencies measured for the `latency` built-in
+#[derive(Default)]
+pub(crate) struct Latency {
+    /// From when a key is received until the line is redrawn
+    pub keypress: Mutex<Samples>,
+    /// From when a command is submitted until the app polls its message
+    pub deliver: Mutex<Samples>,
+}
+
+impl Latency {
+    /// Forget all of the samples
+    pub fn reset(&self) {
+        self.keypress.lock().unwrap().0.clear();
+        self.deliver.lock().unwrap().0.clear();
+    }
+}
+
+/// The most recent samples of a latency
+#[derive(Default)]
+pub(crate) struct Samples(VecDeque<Duration>);
+
+impl Samples {
+    /// Add a sample, forgetting the oldest one if there are too many
+    pub fn push(&mut self, sample: Duration) {
+        if self.0.len() == MAX_SAMPLES {
+            self.0.pop_front();
+        }
+        self.0.push_back(sample);
+    }
+}
+
+impl fmt::Display for Samples {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0.is_empty() {
+            return write!(f, "no samples");
+        }
+        let mut sorted: Vec<Duration> = self.0.iter().copied().collect();
+        sorted.sort();
+        let percentile = |p: usize| sorted[(sorted.len() - 1) * p / 100];
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        write!(
+            f,
+            "{} samples, min {:.2}ms, p50 {:.2}ms, p95 {:.2}ms, p99 {:.2}ms, max {:.2}ms",
+            sorted.len(),
+            ms(sorted[0]),
+            ms(percentile(50)),
+            ms(percentile(95)),
+            ms(percentile(99)),
+            ms(sorted[sorted.len() - 1]),
+        )
+    }
+}
@@ -4,54 +4,239 @@
 This crate provides a terminal interface that runs alongside your app
 */
 
+mod arghistory;
+mod ask;
+#[cfg(feature = "bevy")]
+mod bevy;
+mod brackets;
+mod builder;
+mod builtin;
+mod cancel;
+mod case;
+mod chat;
+mod clipboard;
+mod close;
+mod completion;
+mod context;
+mod definitions;
+mod editor;
+mod embed;
+mod envelope;
+mod expand;
+mod filehistory;
+mod form;
+mod frame;
+mod fuzzy;
+#[cfg(feature = "egui")]
+mod gui;
+mod help;
+mod heredoc;
+mod hint;
+mod history;
+mod input;
+mod iprange;
+mod killring;
+mod latency;
+mod line;
+mod link;
+mod local;
+mod log;
+mod middleware;
+mod mode;
+#[cfg(feature = "mqtt")]
+mod mqtt;
+mod normalize;
+#[cfg(feature = "notifications")]
+mod notify;
+mod number;
+mod output;
+mod pager;
+mod pathcompleter;
+mod plugin;
+mod pool;
+mod processor;
+mod progress;
+mod queue;
+mod registry;
+mod schedule;
+mod scope;
+#[cfg(feature = "scripting")]
+mod script;
+mod scrollback;
+mod search;
+mod secret;
+#[cfg(feature = "serial")]
+mod serial;
+mod session;
+mod sink;
+mod snapshot;
+mod spawn;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+mod state;
+mod suggest;
+mod surface;
+#[cfg(all(unix, feature = "testing"))]
+pub mod testing;
+mod theme;
+mod timeout;
+mod timer;
+mod tokenize;
+#[cfg(feature = "ratatui")]
+mod tui;
+mod wrap;
+mod xterm;
+
 use std::{
-    io::{stdout, Write},
-    iter,
+    cell::{Cell, RefCell},
+    collections::{HashMap, HashSet},
+    io::{BufReader, Read, Write},
+    iter, mem,
+    net::TcpListener,
+    panic::{self, AssertUnwindSafe},
     sync::{
         atomic::{AtomicBool, Ordering},
-        mpsc::{self, Receiver},
-        Arc,
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+        Arc, Mutex,
     },
-    thread::{self, JoinHandle},
+    time::{Duration, Instant, SystemTime},
 };
 
-use clap::{App, ArgMatches, Result as ClapResult};
-use crossterm::{InputEvent, KeyEvent, Terminal, TerminalInput};
+/// How long dropping a [`Console`] waits for it to restore the terminal
+const DROP_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How long writing output to a remote session may take before the session
+/// is dropped
+const SESSION_WRITE_TIMEOUT: Duration = Duration::from_secs(5);
 
 pub use clap;
+pub use crossterm::KeyEvent;
 
-/// Behavior for processing commands
-pub trait CommandProcessor: Sized {
-    /// The type output after the command has been parsed
-    type Parsed;
-    /// Parse an input
-    fn parse(&mut self, input: &str) -> Self::Parsed;
-}
+use arghistory::*;
+pub use ask::*;
+#[cfg(feature = "bevy")]
+pub use bevy::*;
+use brackets::*;
+pub use builder::*;
+use builtin::*;
+pub use cancel::*;
+pub use case::*;
+pub use chat::*;
+use clipboard::*;
+pub use close::*;
+pub use completion::*;
+pub use context::*;
+use definitions::*;
+use editor::*;
+pub use embed::*;
+pub use envelope::*;
+pub use expand::*;
+pub use filehistory::*;
+pub use form::*;
+use frame::*;
+pub use fuzzy::*;
+#[cfg(feature = "egui")]
+pub use gui::*;
+pub use help::*;
+use heredoc::*;
+pub use hint::*;
+pub use history::*;
+pub use input::*;
+pub use iprange::*;
+use killring::*;
+use latency::*;
+use line::*;
+pub use link::*;
+pub use local::*;
+pub use log::*;
+pub use middleware::*;
+use mode::*;
+#[cfg(feature = "mqtt")]
+pub use mqtt::*;
+pub use normalize::*;
+#[cfg(feature = "notifications")]
+use notify::*;
+pub use number::*;
+pub use output::*;
+use pager::*;
+pub use pathcompleter::*;
+pub use plugin::*;
+use pool::*;
+pub use processor::*;
+pub use progress::*;
+use queue::*;
+pub use registry::*;
+pub use schedule::*;
+pub use scope::*;
+#[cfg(feature = "scripting")]
+use script::*;
+pub use scrollback::*;
+use search::*;
+pub use secret::*;
+pub use session::*;
+pub use sink::*;
+pub use snapshot::*;
+use spawn::*;
+#[cfg(feature = "sqlite")]
+pub use sqlite::*;
+pub use state::*;
+pub use suggest::*;
+pub use surface::*;
+pub use theme::*;
+use timeout::*;
+pub use timer::*;
+pub use tokenize::*;
+#[cfg(feature = "ratatui")]
+pub use tui::*;
+use wrap::*;
+pub use xterm::*;
 
-impl<F, R> CommandProcessor for F
-where
-    F: Fn(&str) -> R,
-{
-    type Parsed = R;
-    fn parse(&mut self, input: &str) -> Self::Parsed {
-        self(input)
-    }
+/// A handle to a terminal interface that processes commands
+pub struct Console<M> {
+    recv: Receiver<Envelope<M>>,
+    queue: Arc<QueueStats>,
+    latency: Option<Arc<Latency>>,
+    closed: Arc<AtomicBool>,
+    session: SessionId,
+    prompt: String,
+    modes: Arc<Mutex<Scoped<Vec<Mode<M>>>>>,
+    vars: Arc<Mutex<Scoped<HashMap<String, String>>>>,
+    quit_message: Arc<Mutex<Option<M>>>,
+    tick_message: Arc<Mutex<Option<TickMessage<M>>>>,
+    responder: Responder,
+    scheduler: Arc<Mutex<Scheduler>>,
+    timers: Arc<Mutex<Timers<M>>>,
+    events: Sender<Event>,
+    plugins: Plugins,
+    sinks: Sinks,
+    session_watchers: SessionWatchers,
+    ip_filter: IpFilter,
+    authenticator: Option<Authenticator>,
+    session_role: Role,
+    /// Disconnected once the console thread finishes
+    finished: Receiver<()>,
+    spawner: Spawner,
 }
 
-impl<'a, 'b> CommandProcessor for App<'a, 'b> {
-    type Parsed = ClapResult<ArgMatches<'a>>;
-    fn parse(&mut self, input: &str) -> Self::Parsed {
-        self.get_matches_from_safe_borrow(
-            iter::once(env!("CARGO_PKG_NAME")).chain(input.split_whitespace()),
-        )
+impl Console<()> {
+    /// Create a [`ConsoleBuilder`] for configuring a `Console`
+    pub fn builder() -> ConsoleBuilder {
+        ConsoleBuilder::new()
     }
 }
 
-/// A handle to a terminal interface that processes commands
-pub struct Console<M> {
-    recv: Receiver<M>,
-    closed: Arc<AtomicBool>,
-    handle: Option<JoinHandle<()>>,
+impl Console<String> {
+    /// Create a new `Console` that sends each submitted line to the app as
+    /// it is
+    ///
+    /// See [`ConsoleBuilder::build_raw`] for details.
+    ///
+    /// # Panics
+    ///
+    /// Panics if another open console is already reading the terminal
+    pub fn raw() -> Self {
+        ConsoleBuilder::new().build_raw()
+    }
 }
 
 impl<M> Console<M>
@@ -59,164 +244,798 @@ where
     M: Send + 'static,
 {
     /// Create a new `Console` with the given state and processor builder
+    ///
+    /// # Panics
+    ///
+    /// Panics if another open console is already reading the terminal. Use
+    /// [`ConsoleBuilder::try_build`] to handle this.
     pub fn new<B, F, P>(builder: B, process: F) -> Self
     where
         B: FnOnce() -> P + Send + 'static,
         P: CommandProcessor,
         F: Fn(P::Parsed) -> Option<M> + Send + 'static,
     {
-        let (send, recv) = mpsc::channel();
+        ConsoleBuilder::new().build(builder, process)
+    }
+    pub(crate) fn start<B, F, P>(
+        mut config: ConsoleBuilder,
+        builder: B,
+        process: F,
+        pool: Option<PoolStarter<M>>,
+    ) -> Result<Self, TerminalInUse>
+    where
+        B: FnOnce() -> P + Send + 'static,
+        P: CommandProcessor,
+        F: Fn(P::Parsed) -> Option<M> + Send + 'static,
+    {
         let closed = Arc::new(AtomicBool::from(false));
-        let closed_clone = Arc::clone(&closed);
-        let handle = thread::spawn(move || {
-            let closed = closed_clone;
-            let (width, _) = Terminal::new().terminal_size();
-            let terminal = TerminalInput::new();
-            let reader = terminal.read_sync();
-            let mut processor = builder();
-            let mut input = String::new();
-            let mut history: Vec<String> = Vec::new();
-            let mut curr = None;
-            let mut cursor = 0;
-            macro_rules! flush {
-                () => {
-                    let _ = stdout().flush();
-                };
-            }
-            macro_rules! clear_line {
-                () => {
-                    print!(
-                        "\r{}",
-                        (0..(width as usize - input.len()))
-                            .map(|_| ' ')
-                            .collect::<String>()
-                    );
-                };
+        // Claim the terminal before anything is started
+        if config.input_sources.is_empty() {
+            let source = TerminalSource::claim(Arc::clone(&closed))?;
+            config.input_sources.push(Box::new(source));
+        }
+        let (send, recv) = mpsc::channel();
+        let queue = Arc::new(QueueStats::default());
+        let latency = config.latency_stats.then(|| Arc::new(Latency::default()));
+        let editor_latency = latency.clone();
+        let send = MessageSender::new(send, Arc::clone(&queue));
+        let line = Arc::new(Mutex::new(Line {
+            prompt: config.prompt.clone(),
+            mask: config.mask_input,
+            mask_char: config.mask_char,
+            match_brackets: config.match_brackets,
+            max_input: config.max_input,
+            frames: config.max_frame_rate.map(Frames::new),
+            wrap: config.wrap.map(Rewrap::new),
+            ..Line::default()
+        }));
+        let modes = Arc::new(Mutex::new(Scoped::<Vec<Mode<M>>>::default()));
+        let prompt = config.prompt.clone();
+        let session = SessionId::next();
+        let editor_line = Arc::clone(&line);
+        let editor_modes = Arc::clone(&modes);
+        let editor_closed = Arc::clone(&closed);
+        let vars = Arc::new(Mutex::new(Scoped::new(config.vars.clone())));
+        let editor_vars = Arc::clone(&vars);
+        let output_mode = config.mode;
+        let pager = config.pager;
+        #[cfg(feature = "notifications")]
+        let notify = config.notify.take();
+        let ip_filter = mem::take(&mut config.ip_filter);
+        let authenticator = config.authenticator.take();
+        let session_role = config.session_role;
+        let theme = config.theme;
+        let log_level = Arc::new(Mutex::new(config.log_level));
+        let responder_log_level = Arc::clone(&log_level);
+        let scrollback = config
+            .scrollback
+            .map(|limit| Arc::new(Mutex::new(Scrollback::new(limit))));
+        let responder_scrollback = scrollback.clone();
+        let quit_message = Arc::new(Mutex::new(None));
+        let editor_quit_message = Arc::clone(&quit_message);
+        let tick_message = Arc::new(Mutex::new(None));
+        let editor_tick_message = Arc::clone(&tick_message);
+        let scheduler = Arc::new(Mutex::new(Scheduler::default()));
+        let editor_scheduler = Arc::clone(&scheduler);
+        let timers = Arc::new(Mutex::new(Timers::default()));
+        let editor_timers = Arc::clone(&timers);
+        let (event_send, events) = mpsc::channel();
+        let outputs = Arc::new(OutputCounts::default());
+        let editor_outputs = Arc::clone(&outputs);
+        let plugins: Plugins = Arc::new(Mutex::new(mem::take(&mut config.plugins)));
+        let editor_plugins = Arc::clone(&plugins);
+        let responder_plugins = Arc::clone(&plugins);
+        let sinks = mem::take(&mut config.output_sinks)
+            .into_iter()
+            .map(|sink| (None, Arc::new(Mutex::new(sink))))
+            .collect();
+        let sinks: Sinks = Arc::new(Mutex::new(sinks));
+        let responder_sinks = Arc::clone(&sinks);
+        let editor_sinks = Arc::clone(&sinks);
+        let editor_event_send = event_send.clone();
+        let pool = pool.map(|start| {
+            start(WorkerLink {
+                send: send.clone(),
+                line: Arc::clone(&line),
+                events: event_send.clone(),
+                render_errors: config.render_errors,
+                spawner: config.spawner.clone(),
+            })
+        });
+        let spawner = config.spawner.clone();
+        let editor_spawner = spawner.clone();
+        let finished = spawner.spawn(move || {
+            let on_close = config.on_close;
+            let closed = Arc::clone(&editor_closed);
+            let editor = Editor {
+                line: editor_line,
+                modes: editor_modes,
+                closed: editor_closed,
+                send,
+                session,
+                prompt: config.prompt,
+                processor: builder(),
+                process,
+                completer: config.completer,
+                hinter: config.hinter,
+                hints: config.hints,
+                inline_help: config.inline_help,
+                suggest: config.suggest,
+                abbreviations: config.abbreviations,
+                tokenizer: config.tokenizer,
+                expand_paths: config.expand_paths,
+                expand_vars: config.expand_vars,
+                vars: editor_vars,
+                aliases: Scoped::default(),
+                session_scopes: config.session_scopes,
+                history_expansion: config.history_expansion,
+                history: Vec::new(),
+                curr: None,
+                history_prefix: String::new(),
+                history_navigation: config.history_navigation,
+                history_store: config.history_store,
+                menu: None,
+                kill_ring: KillRing::default(),
+                last_action: LastAction::default(),
+                clipboard: Clipboard::default(),
+                search: None,
+                heredocs: config.heredocs,
+                heredoc: None,
+                secret: None,
+                mouse: config.mouse,
+                builtins: config.builtins,
+                latency: editor_latency,
+                quit_commands: config.quit_commands,
+                quit_keys: config.quit_keys,
+                detach_key: config.detach_key,
+                paste_key: config.paste_key,
+                detached: None,
+                quit_message: editor_quit_message,
+                render_errors: config.render_errors,
+                scrollback,
+                log_level,
+                watch: None,
+                repeat: None,
+                scheduler: editor_scheduler,
+                timers: editor_timers,
+                events,
+                event_send: editor_event_send,
+                shell_commands: config.shell_commands,
+                on_shell_exit: config.on_shell_exit,
+                ctrl_x: false,
+                numeric_arg: None,
+                macros: Macros::default(),
+                arg_history: config.arg_history.then(ArgHistory::default),
+                failed_commands: config.failed_commands,
+                failed: HashSet::new(),
+                search_case: config.search_case,
+                completion_case: config.completion_case,
+                command_case: config.command_case,
+                plugins: editor_plugins,
+                definitions: config.definitions.map(Definitions::new),
+                sources: config.input_sources,
+                open_sources: 0,
+                origin: Origin::Terminal,
+                role: Role::Full,
+                user: None,
+                restricted_commands: config.restricted_commands,
+                sinks: editor_sinks,
+                input: None,
+                submitted: SystemTime::now(),
+                sequence: Cell::new(0),
+                cancel_tokens: RefCell::new(Vec::new()),
+                trim_input: config.trim_input,
+                empty_input: config.empty_input,
+                tick_message: editor_tick_message,
+                case: config.case,
+                stepper: config.stepper,
+                command_timeout: config.command_timeout,
+                command_timeouts: config.command_timeouts,
+                cancel_on_timeout: config.cancel_on_timeout,
+                pending_indicator: config.pending_indicator,
+                auto_pair: config.auto_pair,
+                reveal_last_char: config.reveal_last_char,
+                reveal_until: None,
+                title: config.title,
+                shown_title: None,
+                next_title: Instant::now(),
+                timeouts: RefCell::new(Vec::new()),
+                outputs: editor_outputs,
+                pool,
+                spawner: editor_spawner,
+                #[cfg(feature = "scripting")]
+                scripts: if config.scripting {
+                    Some(Scripts::with_sources(&config.scripts))
+                } else {
+                    None
+                },
+            };
+            let reason = panic::catch_unwind(AssertUnwindSafe(|| editor.run()))
+                .unwrap_or_else(CloseReason::from_panic);
+            closed.store(true, Ordering::Relaxed);
+            if let Some(on_close) = on_close {
+                on_close(reason);
             }
-            macro_rules! set_line {
-                ($line:expr) => {
-                    clear_line!();
-                    input = $line;
-                    print!("\r{}", input);
-                    print!("\r{}", &input[..cursor.min(input.len())]);
-                    flush!();
-                };
+        });
+        Ok(Console {
+            recv,
+            queue,
+            latency,
+            closed,
+            session,
+            prompt,
+            modes,
+            vars,
+            quit_message,
+            tick_message,
+            responder: Responder {
+                line,
+                mode: output_mode,
+                pager,
+                scrollback: responder_scrollback,
+                theme,
+                log_level: responder_log_level,
+                plugins: responder_plugins,
+                sinks: responder_sinks,
+                outputs,
+                events: event_send.clone(),
+                #[cfg(feature = "notifications")]
+                notify,
+            },
+            scheduler,
+            timers,
+            plugins,
+            sinks,
+            session_watchers: SessionWatchers::default(),
+            ip_filter,
+            authenticator,
+            session_role,
+            events: event_send,
+            finished,
+            spawner,
+        })
+    }
+    /// Get a message from the `Console`
+    pub fn poll(&self) -> Option<M> {
+        self.poll_envelope().map(|envelope| envelope.message)
+    }
+    /// Get a message from the `Console` along with the origin of the input
+    /// that produced it
+    pub fn poll_with_origin(&self) -> Option<(Origin, M)> {
+        self.poll_envelope()
+            .map(|envelope| (envelope.origin, envelope.message))
+    }
+    /// Get a message from the `Console` in an [`Envelope`] with information
+    /// about the input that produced it
+    pub fn poll_envelope(&self) -> Option<Envelope<M>> {
+        let envelope = self.recv.try_recv().ok()?;
+        self.queue.polled();
+        if let Some(latency) = &self.latency {
+            let elapsed = envelope.timestamp.elapsed().unwrap_or_default();
+            latency.deliver.lock().unwrap().push(elapsed);
+        }
+        Some(envelope)
+    }
+    /// Report whether the command that produced a message succeeded
+    ///
+    /// The outcome is stored with the command's entry in the
+    /// [history store](ConsoleBuilder::history_store), and failed commands
+    /// can be marked or skipped in the history with
+    /// [`ConsoleBuilder::failed_commands`]. Messages that did not come from a
+    /// command, like those from timers, are ignored.
+    pub fn report_outcome<N>(&self, envelope: &Envelope<N>, success: bool) {
+        if let Some(input) = &envelope.input {
+            let _ = self.events.send(Event::Outcome(input.clone(), success));
+        }
+    }
+    /// Get the number of messages waiting to be polled
+    ///
+    /// An app can use this to catch up on a burst of commands, for example by
+    /// skipping the rendering of a frame.
+    pub fn pending(&self) -> usize {
+        self.queue.queued()
+    }
+    /// Get the most messages that have been waiting to be polled at once
+    pub fn max_pending(&self) -> usize {
+        self.queue.max()
+    }
+    /// Check if the console is open
+    pub fn is_open(&self) -> bool {
+        !self.closed.load(Ordering::Relaxed)
+    }
+    /// Get the id of the `Console`'s session
+    pub fn session(&self) -> SessionId {
+        self.session
+    }
+    /// Enter a nested mode with its own prompt and processor
+    ///
+    /// Until the mode is left, all input is handled by the given processor
+    /// and process function instead of the `Console`'s own. The mode is left
+    /// when the user enters `exit`, when the process function returns `None`,
+    /// or when [`Console::pop_mode`] is called. Modes can be nested.
+    ///
+    /// The mode is global, so it handles the input from every session that
+    /// has not entered a mode of its own with [`Console::push_scoped_mode`].
+    pub fn push_mode<S, P, F>(&self, prompt: S, processor: P, process: F)
+    where
+        S: Into<String>,
+        P: CommandProcessor + Send + 'static,
+        F: Fn(P::Parsed) -> Option<M> + Send + 'static,
+    {
+        self.push_scoped_mode(&Scope::Global, prompt, processor, process)
+    }
+    /// Enter a nested mode for a scope
+    ///
+    /// This works like [`Console::push_mode`], except that a mode for a
+    /// session only handles the input from that session's origin.
+    pub fn push_scoped_mode<S, P, F>(&self, scope: &Scope, prompt: S, processor: P, process: F)
+    where
+        S: Into<String>,
+        P: CommandProcessor + Send + 'static,
+        F: Fn(P::Parsed) -> Option<M> + Send + 'static,
+    {
+        let mut modes = self.modes.lock().unwrap();
+        let mode = Mode::new(prompt.into(), processor, process);
+        modes.get_mut(scope).push(mode);
+        let new_prompt = modes.prompt(&self.prompt);
+        drop(modes);
+        self.responder.line.lock().unwrap().set_prompt(new_prompt);
+    }
+    /// Leave the current global mode
+    ///
+    /// Returns `false` if no mode was active
+    pub fn pop_mode(&self) -> bool {
+        self.pop_scoped_mode(&Scope::Global)
+    }
+    /// Leave the current mode of a scope
+    ///
+    /// Returns `false` if no mode was active in the scope
+    pub fn pop_scoped_mode(&self, scope: &Scope) -> bool {
+        let mut modes = self.modes.lock().unwrap();
+        let popped = modes.get_mut(scope).pop().is_some();
+        let new_prompt = modes.prompt(&self.prompt);
+        drop(modes);
+        if popped {
+            self.responder.line.lock().unwrap().set_prompt(new_prompt);
+        }
+        popped
+    }
+    /// Get the number of nested global modes currently active
+    pub fn mode_depth(&self) -> usize {
+        self.modes.lock().unwrap().global.len()
+    }
+    /// Set a message that is sent when the user quits with one of the
+    /// [quit commands](ConsoleBuilder::quit_command) or
+    /// [quit keys](ConsoleBuilder::quit_key)
+    pub fn set_quit_message(&self, message: M) {
+        *self.quit_message.lock().unwrap() = Some(message);
+    }
+    /// Set a message that is sent whenever empty input is submitted
+    ///
+    /// This only has an effect if [`ConsoleBuilder::empty_input`] is set to
+    /// [`EmptyInput::Tick`].
+    pub fn set_tick_message(&self, message: M)
+    where
+        M: Clone,
+    {
+        *self.tick_message.lock().unwrap() = Some(Box::new(move || message.clone()));
+    }
+    /// Replace the text of the input line
+    ///
+    /// This stages a command for the user to edit and submit. The cursor is
+    /// placed at the given byte index, which is clamped to the length of the
+    /// text.
+    pub fn set_input<S: Into<String>>(&self, text: S, cursor: usize) {
+        let text = text.into();
+        let mut cursor = cursor.min(text.len());
+        while !text.is_char_boundary(cursor) {
+            cursor -= 1;
+        }
+        let mut line = self.responder.line.lock().unwrap();
+        line.cursor = cursor;
+        line.set(text);
+    }
+    /// Lock the input so that no commands can be entered
+    ///
+    /// Keys are ignored and lines from input sources are refused until
+    /// [`Console::unlock_input`] is called. The reason is shown before the
+    /// prompt. This can keep commands from running during critical sections
+    /// like saving.
+    pub fn lock_input<S: Into<String>>(&self, reason: S) {
+        let mut line = self.responder.line.lock().unwrap();
+        line.clear();
+        line.locked = Some(reason.into());
+        line.draw();
+    }
+    /// Unlock the input after [`Console::lock_input`]
+    pub fn unlock_input(&self) {
+        let mut line = self.responder.line.lock().unwrap();
+        line.clear();
+        line.locked = None;
+        line.draw();
+    }
+    /// Set the title of the terminal window
+    ///
+    /// The title is set with an OSC escape code, which most terminals
+    /// support. To update it with a function, use
+    /// [`ConsoleBuilder::title_with`].
+    pub fn set_title<S: AsRef<str>>(&self, title: S) {
+        let line = self.responder.line.lock().unwrap();
+        if !line.detached {
+            set_title(title.as_ref());
+        }
+    }
+    /// Check if the input is locked
+    pub fn is_input_locked(&self) -> bool {
+        self.responder.line.lock().unwrap().locked.is_some()
+    }
+    /// Get the text of the input line
+    ///
+    /// This is empty while a [secret](Console::read_secret) is being read.
+    pub fn input(&self) -> String {
+        let line = self.responder.line.lock().unwrap();
+        if line.secret {
+            return String::new();
+        }
+        line.input.clone()
+    }
+    /// Submit a line of input as though it came from the given origin
+    ///
+    /// This lets another interface, like a window in a game, run commands
+    /// through the console.
+    pub fn submit<S: Into<String>>(&self, origin: Origin, input: S) {
+        let input = Input::Line(input.into());
+        let _ = self
+            .events
+            .send(Event::Input(origin, Role::Full, None, input));
+    }
+    /// Complete an input the same way the console would
+    ///
+    /// The cursor is a byte index into the input. This waits for the console
+    /// thread to answer.
+    pub fn complete(&self, input: &str, cursor: usize) -> Completion {
+        let (reply, completion) = mpsc::channel();
+        if self
+            .events
+            .send(Event::Complete(input.into(), cursor, reply))
+            .is_err()
+        {
+            return Completion::default();
+        }
+        completion.recv().unwrap_or_default()
+    }
+    /// Get the lines in the history, oldest first
+    ///
+    /// This waits for the console thread to answer.
+    pub fn history(&self) -> Vec<String> {
+        let (reply, history) = mpsc::channel();
+        if self.events.send(Event::History(reply)).is_err() {
+            return Vec::new();
+        }
+        history.recv().unwrap_or_default()
+    }
+    /// Set a global console variable
+    ///
+    /// Console variables are used when expanding variables in the input.
+    pub fn set_var<K, V>(&self, name: K, value: V)
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.set_scoped_var(&Scope::Global, name, value)
+    }
+    /// Get the value of a global console variable
+    pub fn var(&self, name: &str) -> Option<String> {
+        self.scoped_var(&Scope::Global, name)
+    }
+    /// Remove a global console variable, returning its value
+    pub fn remove_var(&self, name: &str) -> Option<String> {
+        self.remove_scoped_var(&Scope::Global, name)
+    }
+    /// Set a console variable for a scope
+    ///
+    /// A session's variables are used before the global ones when expanding
+    /// the input from its origin.
+    pub fn set_scoped_var<K, V>(&self, scope: &Scope, name: K, value: V)
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        let mut vars = self.vars.lock().unwrap();
+        vars.get_mut(scope).insert(name.into(), value.into());
+    }
+    /// Get the value of a console variable in a scope
+    ///
+    /// This does not fall back to the global variables for a session.
+    pub fn scoped_var(&self, scope: &Scope, name: &str) -> Option<String> {
+        let vars = self.vars.lock().unwrap();
+        vars.get(scope)?.get(name).cloned()
+    }
+    /// Remove a console variable from a scope, returning its value
+    pub fn remove_scoped_var(&self, scope: &Scope, name: &str) -> Option<String> {
+        let mut vars = self.vars.lock().unwrap();
+        vars.get_mut(scope).remove(name)
+    }
+    /// Schedule a command to be run at the given time
+    ///
+    /// The command is run on the console thread as if the user had entered
+    /// it, except that it is not added to the history.
+    pub fn schedule_at<S: Into<String>>(&self, time: SystemTime, command: S) -> JobId {
+        let id = self
+            .scheduler
+            .lock()
+            .unwrap()
+            .schedule(command.into(), time, None);
+        let _ = self.events.send(Event::Wake);
+        id
+    }
+    /// Schedule a command to be run every time the given interval passes
+    ///
+    /// The command is first run after one interval. Intervals shorter than a
+    /// millisecond are treated as a millisecond.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the interval is too long to be added to the current time
+    pub fn schedule_every<S: Into<String>>(&self, interval: Duration, command: S) -> JobId {
+        let interval = interval.max(MIN_INTERVAL);
+        let next = SystemTime::now() + interval;
+        let id = self
+            .scheduler
+            .lock()
+            .unwrap()
+            .schedule(command.into(), next, Some(interval));
+        let _ = self.events.send(Event::Wake);
+        id
+    }
+    /// Cancel a scheduled job
+    ///
+    /// Returns `false` if there was no job with the given id
+    pub fn cancel_job(&self, id: JobId) -> bool {
+        self.scheduler.lock().unwrap().cancel(id)
+    }
+    /// Get the scheduled jobs, soonest first
+    pub fn jobs(&self) -> Vec<ScheduledJob> {
+        self.scheduler.lock().unwrap().jobs()
+    }
+    /// Register a timer that sends a message every time the given interval
+    /// passes
+    ///
+    /// The messages are received with [`Console::poll`] along with the
+    /// messages from commands.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the interval is zero
+    pub fn every<F>(&self, interval: Duration, f: F) -> TimerId
+    where
+        F: FnMut() -> M + Send + 'static,
+    {
+        assert!(!interval.is_zero(), "timer interval must not be zero");
+        let id = self.timers.lock().unwrap().add(interval, Box::new(f));
+        let _ = self.events.send(Event::Wake);
+        id
+    }
+    /// Remove a timer
+    ///
+    /// Returns `false` if there was no timer with the given id
+    pub fn cancel_timer(&self, id: TimerId) -> bool {
+        self.timers.lock().unwrap().remove(id)
+    }
+    /// Get the names of the plugins
+    pub fn plugins(&self) -> Vec<String> {
+        self.plugins
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|plugin| plugin.name().into())
+            .collect()
+    }
+    /// Add a sink that receives a copy of the output
+    pub fn add_output_sink<S>(&self, sink: S)
+    where
+        S: OutputSink + 'static,
+    {
+        add_sink(&self.sinks, None, Box::new(sink));
+    }
+    /// Add a sink that only receives the output for the given origin
+    ///
+    /// See [`Responder::print_to`].
+    pub fn add_origin_sink<S>(&self, origin: Origin, sink: S)
+    where
+        S: OutputSink + 'static,
+    {
+        add_sink(&self.sinks, Some(origin), Box::new(sink));
+    }
+    /// Add a source of input while the console is running
+    pub fn add_input_source<S>(&self, source: S)
+    where
+        S: InputSource + 'static,
+    {
+        if self.events.send(Event::Opened).is_ok() {
+            spawn_source(&self.spawner, Box::new(source), self.events.clone());
+        }
+    }
+    /// Accept commands from the connections to a TCP listener
+    ///
+    /// Each connection sends commands as lines and is its own
+    /// [`Origin::Source`], named after the peer's address. Output printed
+    /// with [`Console::print_to`] for a connection's origin is written back
+    /// to it as plain text. Connections are reported to the receivers from
+    /// [`Console::session_events`], and the ones from addresses that are not
+    /// [allowed](ConsoleBuilder::allow_ip) are closed right away. If sessions
+    /// [log in](ConsoleBuilder::authenticate), each connection is asked to
+    /// log in first. Commands from the connections have the
+    /// [session role](ConsoleBuilder::session_role), and a connection that
+    /// does not take its output for a few seconds is dropped.
+    pub fn accept_tcp(&self, listener: TcpListener) {
+        let filter = self.ip_filter.clone();
+        let watchers = Arc::clone(&self.session_watchers);
+        let responder = self.responder.clone();
+        let connections = iter::from_fn(move || Some(listener.accept()));
+        self.accept_connections(connections.filter_map(move |connection| {
+            let (stream, addr) = accepted(&responder, connection)?;
+            let _ = stream.set_write_timeout(Some(SESSION_WRITE_TIMEOUT));
+            let info = SessionInfo {
+                origin: Origin::Source(addr.to_string()),
+                address: addr.to_string(),
+                user: None,
+            };
+            if !filter.permits(addr.ip()) {
+                send_session_event(&watchers, SessionEvent::Rejected(info));
+                return None;
             }
-            for event in reader {
-                if closed.load(Ordering::Relaxed) {
+            Some((info, stream.try_clone().ok()?, stream))
+        }));
+    }
+    /// Accept commands from the connections to a Unix socket listener
+    ///
+    /// This works like [`Console::accept_tcp`]. Connections are named
+    /// `unix:0`, `unix:1`, and so on.
+    #[cfg(unix)]
+    pub fn accept_unix(&self, listener: std::os::unix::net::UnixListener) {
+        let path = listener.local_addr().ok();
+        let path = path.as_ref().and_then(|addr| addr.as_pathname());
+        let address = path.map(|path| path.display().to_string());
+        let responder = self.responder.clone();
+        let connections = iter::from_fn(move || Some(listener.accept()));
+        let connections = connections.filter_map(move |c| accepted(&responder, c));
+        self.accept_connections(connections.enumerate().filter_map(move |(i, (stream, _))| {
+            let _ = stream.set_write_timeout(Some(SESSION_WRITE_TIMEOUT));
+            let name = format!("unix:{}", i);
+            let info = SessionInfo {
+                address: address.clone().unwrap_or_else(|| name.clone()),
+                origin: Origin::Source(name),
+                user: None,
+            };
+            Some((info, stream.try_clone().ok()?, stream))
+        }));
+    }
+    /// Add an input source and a sink for each connection on a new thread
+    fn accept_connections<I, R, W>(&self, connections: I)
+    where
+        I: Iterator<Item = (SessionInfo, R, W)> + Send + 'static,
+        R: Read + Send + 'static,
+        W: Write + Send + 'static,
+    {
+        let events = self.events.clone();
+        let sinks = Arc::clone(&self.sinks);
+        let spawner = self.spawner.clone();
+        let watchers = Arc::clone(&self.session_watchers);
+        let authenticator = self.authenticator.clone();
+        let role = self.session_role;
+        self.spawner.spawn(move || {
+            for (mut info, reader, mut writer) in connections {
+                if events.send(Event::Opened).is_err() {
                     return;
                 }
-                if let InputEvent::Keyboard(key_event) = event {
-                    match key_event {
-                        KeyEvent::Backspace => {
-                            let reprint = if cursor >= input.len() {
-                                input.pop().is_some()
-                            } else if !input.is_empty() && cursor > 0 {
-                                input.remove(cursor - 1);
-                                true
-                            } else {
-                                false
-                            };
-                            if reprint {
-                                cursor -= 1;
-                                set_line!(input);
-                            }
-                        }
-                        KeyEvent::Delete => {
-                            let reprint = if cursor < input.len() {
-                                input.remove(cursor);
-                                true
-                            } else {
-                                false
-                            };
-                            if reprint {
-                                set_line!(input);
-                            }
-                        }
-                        KeyEvent::Up => {
-                            if curr.is_none() {
-                                curr = Some(history.len());
-                            }
-                            if let Some(ref mut curr) = curr {
-                                if *curr > 0 {
-                                    *curr -= 1;
-                                }
-                                let new_input = history[*curr].clone();
-                                cursor = new_input.len();
-                                set_line!(new_input);
-                            }
-                        }
-                        KeyEvent::Down => {
-                            if let Some(c) = curr {
-                                let new_input = if c < history.len() - 1 {
-                                    curr = Some(c + 1);
-                                    history[c + 1].clone()
-                                } else {
-                                    curr = None;
-                                    String::new()
-                                };
-                                cursor = new_input.len();
-                                set_line!(new_input);
-                            }
-                        }
-                        KeyEvent::Left => {
-                            if cursor > 0 {
-                                cursor -= 1;
-                                set_line!(input);
+                let events = events.clone();
+                let sinks = Arc::clone(&sinks);
+                let watchers = Arc::clone(&watchers);
+                let authenticator = authenticator.clone();
+                let source_spawner = spawner.clone();
+                // Log in on a thread of its own so that a slow connection
+                // does not hold up the others
+                spawner.spawn(move || {
+                    let mut reader = BufReader::new(reader);
+                    if let Some(authenticator) = &authenticator {
+                        match log_in(&mut reader, &mut writer, authenticator) {
+                            Login::Accepted(user) => info.user = Some(user),
+                            Login::Rejected(user) => {
+                                let origin = info.origin.clone();
+                                info.user = Some(user);
+                                send_session_event(&watchers, SessionEvent::AuthFailed(info));
+                                let _ = events.send(Event::Eof(origin));
+                                return;
                             }
-                        }
-                        KeyEvent::Right => {
-                            if cursor < input.len() {
-                                cursor += 1;
-                                set_line!(input);
+                            Login::Ended => {
+                                let _ = events.send(Event::Eof(info.origin));
+                                return;
                             }
                         }
-                        KeyEvent::Char(c) => {
-                            if c == '\n' {
-                                println!();
-                                // Submit
-                                let parsed = processor.parse(input.trim());
-                                history.push(input.trim().to_string());
-                                input.clear();
-                                cursor = 0;
-                                curr = None;
-                                if let Some(message) = process(parsed) {
-                                    let _ = send.send(message);
-                                } else {
-                                    closed.store(true, Ordering::Relaxed);
-                                    return;
-                                }
-                            } else {
-                                // Add character
-                                input.insert(cursor, c);
-                                cursor += 1;
-                                set_line!(input);
-                            }
-                        }
-                        _ => {}
                     }
-                }
+                    let sink = WriterSink::new(writer)
+                        .plain()
+                        .timeout(SESSION_WRITE_TIMEOUT);
+                    add_sink(&sinks, Some(info.origin.clone()), Box::new(sink));
+                    let source = LineSource::new(reader)
+                        .origin(info.origin.clone())
+                        .role(role);
+                    let source = SessionSource::open(source, info, watchers);
+                    spawn_source(&source_spawner, Box::new(source), events);
+                });
             }
         });
-        Console {
-            recv,
-            closed,
-            handle: Some(handle),
-        }
     }
-    /// Get a message from the `Console`
-    pub fn poll(&self) -> Option<M> {
-        self.recv.try_recv().ok()
+    /// Get a receiver for the opening and closing of remote sessions
+    ///
+    /// Sessions are the connections accepted with [`Console::accept_tcp`]
+    /// and [`Console::accept_unix`]. Only the events after this is called are
+    /// received, so it should be called before accepting connections.
+    pub fn session_events(&self) -> Receiver<SessionEvent> {
+        let (send, recv) = mpsc::channel();
+        self.session_watchers.lock().unwrap().push(send);
+        recv
     }
-    /// Check if the console is open
-    pub fn is_open(&self) -> bool {
-        !self.closed.load(Ordering::Relaxed)
+    /// Get a [`Responder`] for printing output from any thread
+    pub fn responder(&self) -> Responder {
+        self.responder.clone()
+    }
+    /// Print some output above the input line
+    pub fn print<D: std::fmt::Display>(&self, output: D) {
+        self.responder.print(output)
+    }
+    /// Print some output to the terminal and to every session
+    ///
+    /// See [`Responder::broadcast`] for details.
+    pub fn broadcast<D: std::fmt::Display>(&self, output: D) {
+        self.responder.broadcast(output)
+    }
+    /// Print some output for the given origin
+    ///
+    /// See [`Responder::print_to`] for details.
+    pub fn print_to<D: std::fmt::Display>(&self, origin: &Origin, output: D) {
+        self.responder.print_to(origin, output)
+    }
+    /// Print a message at the given level
+    ///
+    /// See [`Responder::log`] for details.
+    pub fn log<D: std::fmt::Display>(&self, level: Level, message: D) {
+        self.responder.log(level, message)
+    }
+    /// Get the least severe level of messages that are printed
+    pub fn log_level(&self) -> Level {
+        self.responder.log_level()
+    }
+    /// Set the least severe level of messages that are printed
+    pub fn set_log_level(&self, level: Level) {
+        self.responder.set_log_level(level)
+    }
+}
+
+impl<M> Console<M> {
+    /// Close the console and wait for it to restore the terminal
+    ///
+    /// The console stops reading input right away, even if an input source
+    /// is blocked waiting for input. Messages that were already sent can
+    /// still be polled afterwards. Returns `false` if the console was still
+    /// busy, such as with a shell command, when the timeout elapsed, in which
+    /// case it finishes on its own thread.
+    ///
+    /// Dropping a `Console` closes it the same way, waiting up to one second.
+    pub fn close_and_wait(&self, timeout: Duration) -> bool {
+        self.closed.store(true, Ordering::Relaxed);
+        // Wake the console thread in case it is waiting for input
+        let _ = self.events.send(Event::Wake);
+        match self.finished.recv_timeout(timeout) {
+            Ok(()) | Err(RecvTimeoutError::Disconnected) => true,
+            Err(RecvTimeoutError::Timeout) => false,
+        }
     }
 }
 
 impl<M> Drop for Console<M> {
     fn drop(&mut self) {
-        self.closed.store(true, Ordering::Relaxed);
-        let _ = self.handle.take().unwrap().join();
+        self.close_and_wait(DROP_TIMEOUT);
     }
 }
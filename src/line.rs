@@ -0,0 +1,509 @@
+use std::{
+    borrow::Cow,
+    io::{stdout, Write},
+    ops::Range,
+    time::Instant,
+};
+
+use colored::Colorize;
+use crossterm::{ClearType, Terminal, TerminalCursor};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::{matching_bracket, Frames, Pager, Rewrap};
+
+/// The line currently being edited
+#[derive(Default)]
+pub(crate) struct Line {
+    pub prompt: String,
+    pub input: String,
+    pub cursor: usize,
+    /// Text shown on the row below the input
+    pub below: Option<String>,
+    /// The end of the selection opposite the cursor
+    pub anchor: Option<usize>,
+    /// The pager that is shown instead of the line while it is open
+    pub pager: Option<Pager>,
+    /// An input and its rendering with highlighting
+    pub highlighted: Option<(String, String)>,
+    /// Whether each character of the input is drawn as `*`
+    pub mask: bool,
+    /// The character drawn in place of each character of masked input
+    pub mask_char: char,
+    /// The byte index of the grapheme of masked input that is shown anyway
+    pub revealed: Option<usize>,
+    /// Whether the input is a secret being read, which must not be shared
+    pub secret: bool,
+    /// Progress indicators shown below the input, by id
+    pub progress: Vec<(u64, String)>,
+    /// Whether the terminal is detached, so nothing is drawn
+    pub detached: bool,
+    /// The number of messages the app has not finished with, shown before
+    /// the prompt if there are any
+    pub pending: usize,
+    /// Whether typing replaces the character under the cursor instead of
+    /// inserting before it
+    pub overwrite: bool,
+    /// Whether the bracket matching the one at the cursor is highlighted
+    pub match_brackets: bool,
+    /// Why the input is locked, if it is, shown before the prompt
+    pub locked: Option<String>,
+    /// The most characters the input can have, shown with the current count
+    /// before the prompt
+    pub max_input: Option<usize>,
+    /// The output waiting for the next frame, if output is limited to a
+    /// frame rate
+    pub frames: Option<Frames>,
+    /// Whether the input is a command from the history that failed, which
+    /// is marked before the prompt
+    pub failed: bool,
+    /// The output kept for wrapping again, if output is wrapped
+    pub wrap: Option<Rewrap>,
+}
+
+impl Line {
+    /// Erase the line from the terminal
+    pub fn clear(&self) {
+        if self.detached {
+            return;
+        }
+        print!("\r");
+        let _ = Terminal::new().clear(ClearType::FromCursorDown);
+    }
+    /// Draw the line and place the cursor
+    pub fn draw(&self) {
+        if self.detached {
+            return;
+        }
+        if let Some(pager) = &self.pager {
+            pager.draw(terminal_height());
+            return;
+        }
+        match self.selection() {
+            Some(range) => print!(
+                "\r{}{}{}{}",
+                self.full_prompt(),
+                self.shown(0..range.start),
+                self.shown(range.clone()).reversed(),
+                self.shown(range.end..self.input.len())
+            ),
+            None => print!("\r{}{}", self.full_prompt(), self.rendered_input()),
+        }
+        let mut rows = 0;
+        if let Some(below) = &self.below {
+            print!("\r\n{}", below);
+            rows += below.lines().count().max(1);
+        }
+        for (_, progress) in &self.progress {
+            print!("\r\n{}", progress);
+            rows += 1;
+        }
+        if rows > 0 {
+            flush();
+            let _ = TerminalCursor::new().move_up(rows as u16);
+        }
+        let cursor = self.cursor.min(self.input.len());
+        if !self.mask && self.selection().is_none() && self.rendered_input() != self.input {
+            // Reprinting the input before the cursor would hide its highlighting
+            print!("\r{}{}", self.full_prompt(), self.rendered_input());
+            let after = self.input[cursor..].width();
+            if after > 0 {
+                flush();
+                let _ = TerminalCursor::new().move_left(after as u16);
+            }
+        } else {
+            print!("\r{}{}", self.full_prompt(), self.shown(0..cursor));
+        }
+        if self.match_brackets && !self.mask && self.selection().is_none() {
+            if let Some(index) = matching_bracket(&self.input, cursor) {
+                self.highlight_bracket(index, cursor);
+            }
+        }
+        flush();
+    }
+    /// Redraw the bracket at an index highlighted, leaving the terminal
+    /// cursor where it was
+    fn highlight_bracket(&self, index: usize, cursor: usize) {
+        let end = next_char(&self.input, index);
+        let mut terminal_cursor = TerminalCursor::new();
+        flush();
+        if index < cursor {
+            let _ = terminal_cursor.move_left(self.input[index..cursor].width() as u16);
+        } else if index > cursor {
+            let _ = terminal_cursor.move_right(self.input[cursor..index].width() as u16);
+        }
+        print!("{}", self.input[index..end].bold().underline());
+        flush();
+        if end < cursor {
+            let _ = terminal_cursor.move_right(self.input[end..cursor].width() as u16);
+        } else if end > cursor {
+            let _ = terminal_cursor.move_left(self.input[cursor..end].width() as u16);
+        }
+    }
+    /// Get the prompt with the reason the input is locked, the number of
+    /// pending messages, and the length of the input before it
+    fn full_prompt(&self) -> Cow<'_, str> {
+        let mut prefix = String::new();
+        if let Some(reason) = &self.locked {
+            prefix = format!("({}) ", reason);
+        }
+        if self.pending > 0 {
+            prefix.push_str(&format!("[{}]", self.pending));
+        }
+        if let Some(max) = self.max_input {
+            prefix.push_str(&format!("[{}/{}]", self.input.chars().count(), max));
+        }
+        if self.failed {
+            prefix.push_str(&format!("{} ", "✗".red()));
+        }
+        if prefix.is_empty() {
+            Cow::Borrowed(&self.prompt)
+        } else {
+            prefix.push_str(&self.prompt);
+            Cow::Owned(prefix)
+        }
+    }
+    /// Get the input with highlighting if it has been highlighted
+    fn rendered_input(&self) -> Cow<'_, str> {
+        match &self.highlighted {
+            _ if self.mask => self.shown(0..self.input.len()),
+            Some((input, highlighted)) if *input == self.input => Cow::Borrowed(highlighted),
+            _ => Cow::Borrowed(&self.input),
+        }
+    }
+    /// Get a range of the input as it is drawn, masking it if necessary
+    fn shown(&self, range: Range<usize>) -> Cow<'_, str> {
+        let text = &self.input[range.clone()];
+        if !self.mask {
+            return Cow::Borrowed(text);
+        }
+        let shown = text
+            .grapheme_indices(true)
+            .map(|(i, grapheme)| {
+                if self.revealed == Some(range.start + i) {
+                    grapheme.to_string()
+                } else {
+                    self.mask_char.to_string()
+                }
+            })
+            .collect();
+        Cow::Owned(shown)
+    }
+    /// Get the width of a grapheme of the input as it is drawn
+    fn grapheme_width(&self, index: usize, grapheme: &str) -> usize {
+        if self.mask && self.revealed != Some(index) {
+            self.mask_char.width().unwrap_or(1)
+        } else {
+            grapheme.width()
+        }
+    }
+    /// Get the byte range of the selected text
+    pub fn selection(&self) -> Option<Range<usize>> {
+        let anchor = self.anchor?;
+        if anchor < self.cursor {
+            Some(anchor..self.cursor)
+        } else if anchor > self.cursor {
+            Some(self.cursor..anchor)
+        } else {
+            None
+        }
+    }
+    /// Get the byte index in the input of the grapheme displayed at the given
+    /// terminal column
+    pub fn cursor_at_column(&self, column: usize) -> usize {
+        let mut width = self.full_prompt().width();
+        if column < width {
+            return 0;
+        }
+        for (i, grapheme) in self.input.grapheme_indices(true) {
+            let grapheme_width = self.grapheme_width(i, grapheme);
+            if column < width + grapheme_width {
+                return i;
+            }
+            width += grapheme_width;
+        }
+        self.input.len()
+    }
+    /// Remove the character before the cursor, returning whether there was
+    /// one
+    pub fn remove_prev_char(&mut self) -> bool {
+        let cursor = self.cursor.min(self.input.len());
+        if cursor == 0 {
+            return false;
+        }
+        let start = prev_char(&self.input, cursor);
+        self.input.replace_range(start..cursor, "");
+        self.cursor = start;
+        true
+    }
+    /// Remove the character after the cursor, returning whether there was
+    /// one
+    pub fn remove_next_char(&mut self) -> bool {
+        let end = next_char(&self.input, self.cursor);
+        self.input.replace_range(self.cursor..end, "");
+        end > self.cursor
+    }
+    /// Remove the selected text, returning it
+    pub fn take_selection(&mut self) -> Option<String> {
+        let range = self.selection()?;
+        self.anchor = None;
+        self.cursor = range.start;
+        Some(self.input.drain(range).collect())
+    }
+    /// Replace the input and redraw the line
+    pub fn set(&mut self, input: String) {
+        self.clear();
+        self.input = input;
+        self.anchor = None;
+        self.revealed = None;
+        self.draw();
+    }
+    /// Replace the prompt and redraw the line
+    pub fn set_prompt(&mut self, prompt: String) {
+        self.clear();
+        self.prompt = prompt;
+        self.draw();
+    }
+    /// Switch between overwriting and inserting
+    ///
+    /// The cursor is shown as an underline while overwriting.
+    pub fn set_overwrite(&mut self, overwrite: bool) {
+        self.overwrite = overwrite;
+        if self.detached {
+            return;
+        }
+        print!("{}", if overwrite { "\x1b[4 q" } else { "\x1b[0 q" });
+        flush();
+    }
+    /// Print some output above the line, or batch it for the next frame
+    ///
+    /// Returns `true` if the output started a batch that has to be printed
+    /// later.
+    pub fn print(&mut self, output: &str) -> bool {
+        let output = match &mut self.frames {
+            Some(frames) => {
+                let waiting = frames.due().is_some();
+                match frames.push(output) {
+                    Some(batch) => batch,
+                    None => return !waiting,
+                }
+            }
+            None => format!("{}\n", output),
+        };
+        self.clear();
+        print!("{}", output);
+        self.draw();
+        false
+    }
+    /// Print the output batched for the next frame
+    ///
+    /// Unless `all` is set, it is only printed if the frame is due.
+    pub fn print_frame(&mut self, now: Instant, all: bool) {
+        let batch = match &mut self.frames {
+            Some(frames) if all => frames.take_all(),
+            Some(frames) => frames.take(now).unwrap_or_default(),
+            None => return,
+        };
+        if batch.is_empty() || self.detached {
+            // Output printed while detached is replayed from the scrollback
+            return;
+        }
+        if let Some(pager) = &mut self.pager {
+            pager.extend(batch.trim_end_matches('\n'), terminal_width());
+        } else {
+            self.clear();
+            print!("{}", batch);
+        }
+        self.draw();
+    }
+    /// Print the recent output again, wrapped to the new width, if the
+    /// terminal was resized since it was wrapped
+    pub fn rewrap(&mut self) {
+        // Output waiting for the next frame was wrapped to the old width
+        let waiting = self
+            .frames
+            .as_ref()
+            .is_some_and(|frames| frames.due().is_some());
+        if self.detached || self.pager.is_some() || waiting {
+            return;
+        }
+        let height = terminal_height().saturating_sub(1);
+        let output = match &mut self.wrap {
+            Some(wrap) => match wrap.resize(terminal_width(), height) {
+                Some(output) => output,
+                None => return,
+            },
+            None => return,
+        };
+        let _ = Terminal::new().clear(ClearType::All);
+        println!("{}", output);
+        self.draw();
+    }
+    /// Clear the whole screen and draw the line at the top
+    pub fn clear_screen(&self) {
+        if self.detached {
+            return;
+        }
+        let _ = Terminal::new().clear(ClearType::All);
+        self.draw();
+    }
+    /// Redraw the line with its current input
+    pub fn redraw(&self) {
+        self.clear();
+        self.draw();
+    }
+}
+
+/// Get the byte index of the character before the cursor
+pub(crate) fn prev_char(input: &str, cursor: usize) -> usize {
+    input[..cursor]
+        .char_indices()
+        .next_back()
+        .map(|(i, _)| i)
+        .unwrap_or(0)
+}
+
+/// Get the byte index of the character after the cursor
+pub(crate) fn next_char(input: &str, cursor: usize) -> usize {
+    input[cursor..]
+        .chars()
+        .next()
+        .map(|c| cursor + c.len_utf8())
+        .unwrap_or(cursor)
+}
+
+/// Get the byte index of the start of the whitespace-delimited word before
+/// the cursor
+pub(crate) fn prev_word_start(input: &str, cursor: usize) -> usize {
+    let before = input[..cursor].trim_end();
+    before
+        .rfind(char::is_whitespace)
+        .map(|i| i + 1)
+        .unwrap_or(0)
+}
+
+/// Get the byte index of the end of the alphanumeric word after the cursor
+pub(crate) fn next_word_end(input: &str, cursor: usize) -> usize {
+    let mut chars = input[cursor..].char_indices().peekable();
+    while let Some(&(_, c)) = chars.peek() {
+        if c.is_alphanumeric() {
+            break;
+        }
+        chars.next();
+    }
+    while let Some(&(_, c)) = chars.peek() {
+        if !c.is_alphanumeric() {
+            break;
+        }
+        chars.next();
+    }
+    chars
+        .peek()
+        .map(|&(i, _)| cursor + i)
+        .unwrap_or(input.len())
+}
+
+/// Swap the character before the cursor with the one under it, or the two
+/// before it at the end of the input
+///
+/// Returns the new cursor, or `None` if there were not two characters to swap
+pub(crate) fn transpose_chars(input: &mut String, cursor: usize) -> Option<usize> {
+    let cursor = if cursor >= input.len() {
+        prev_char(input, input.len())
+    } else {
+        cursor
+    };
+    if cursor == 0 {
+        return None;
+    }
+    let start = prev_char(input, cursor);
+    let end = next_char(input, cursor);
+    let swapped = format!("{}{}", &input[cursor..end], &input[start..cursor]);
+    input.replace_range(start..end, &swapped);
+    Some(end)
+}
+
+/// Swap the alphanumeric word at or after the cursor with the one before it,
+/// or the last two words if there are none after the cursor
+///
+/// Returns the new cursor, or `None` if there were not two words to swap
+pub(crate) fn transpose_words(input: &mut String, cursor: usize) -> Option<usize> {
+    let mut words = Vec::new();
+    let mut start = None;
+    for (i, c) in input.char_indices().chain(Some((input.len(), ' '))) {
+        match (start, c.is_alphanumeric()) {
+            (None, true) => start = Some(i),
+            (Some(s), false) => {
+                words.push(s..i);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    let second = words
+        .iter()
+        .position(|word| word.end > cursor)
+        .unwrap_or_else(|| words.len().saturating_sub(1));
+    if second == 0 {
+        return None;
+    }
+    let (first, second) = (words[second - 1].clone(), words[second].clone());
+    let swapped = format!(
+        "{}{}{}",
+        &input[second.clone()],
+        &input[first.end..second.start],
+        &input[first.clone()]
+    );
+    input.replace_range(first.start..second.end, &swapped);
+    Some(second.end)
+}
+
+/// Get the height of the terminal
+pub(crate) fn terminal_height() -> usize {
+    Terminal::new().terminal_size().1 as usize
+}
+
+/// Get the width of the terminal
+pub(crate) fn terminal_width() -> usize {
+    Terminal::new().terminal_size().0 as usize
+}
+
+/// Set the title of the terminal window
+pub(crate) fn set_title(title: &str) {
+    let title: String = title.chars().filter(|c| !c.is_control()).collect();
+    print!("\x1b]0;{}\x07", title);
+    flush();
+}
+
+pub(crate) fn flush() {
+    let _ = stdout().flush();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remove_non_ascii() {
+        let mut line = Line {
+            input: "aé€b".into(),
+            ..Line::default()
+        };
+        line.cursor = line.input.len() - 1;
+        assert!(line.remove_prev_char());
+        assert_eq!((line.input.as_str(), line.cursor), ("aéb", 3));
+        assert!(line.remove_prev_char());
+        assert_eq!((line.input.as_str(), line.cursor), ("ab", 1));
+        line.input = "é".into();
+        line.cursor = 2;
+        assert!(line.remove_prev_char());
+        assert_eq!((line.input.as_str(), line.cursor), ("", 0));
+        assert!(!line.remove_prev_char());
+        line.input = "€é".into();
+        line.cursor = 0;
+        assert!(line.remove_next_char());
+        assert_eq!((line.input.as_str(), line.cursor), ("é", 0));
+        assert!(line.remove_next_char());
+        assert!(!line.remove_next_char());
+    }
+}
@@ -0,0 +1,95 @@
+use std::{env, fmt, path::Path};
+
+/// A clickable hyperlink in output
+///
+/// On terminals that support them, hyperlinks are printed with OSC 8 escape
+/// sequences so that the text can be clicked to open the URL. Elsewhere,
+/// the URL is printed after the text. Support is detected from the
+/// environment and can be forced on or off by setting `FORCE_HYPERLINK` to
+/// `1` or `0`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hyperlink {
+    /// The URL the link points to
+    pub url: String,
+    /// The text shown for the link
+    pub text: String,
+}
+
+impl Hyperlink {
+    /// Create a new `Hyperlink` with the given URL and text
+    pub fn new<U, T>(url: U, text: T) -> Self
+    where
+        U: Into<String>,
+        T: Into<String>,
+    {
+        Hyperlink {
+            url: url.into(),
+            text: text.into(),
+        }
+    }
+    /// Create a `Hyperlink` that shows a URL as its own text
+    pub fn url<U: Into<String>>(url: U) -> Self {
+        let url = url.into();
+        Hyperlink {
+            text: url.clone(),
+            url,
+        }
+    }
+    /// Create a `Hyperlink` to a file that shows the path as its text
+    ///
+    /// Relative paths are resolved against the current directory.
+    pub fn file<P: AsRef<Path>>(path: P) -> Self {
+        let path = path.as_ref();
+        let absolute = env::current_dir()
+            .map(|dir| dir.join(path))
+            .unwrap_or_else(|_| path.to_path_buf());
+        let mut url = String::from("file://");
+        for c in absolute.to_string_lossy().chars() {
+            match c {
+                '\\' => url.push('/'),
+                ' ' => url.push_str("%20"),
+                '%' => url.push_str("%25"),
+                c => url.push(c),
+            }
+        }
+        Hyperlink {
+            url,
+            text: path.to_string_lossy().into_owned(),
+        }
+    }
+}
+
+impl fmt::Display for Hyperlink {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if hyperlinks_supported() {
+            write!(f, "\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", self.url, self.text)
+        } else if self.text == self.url || self.url.starts_with("file://") {
+            write!(f, "{}", self.text)
+        } else {
+            write!(f, "{} ({})", self.text, self.url)
+        }
+    }
+}
+
+/// Check if the terminal is known to support OSC 8 hyperlinks
+pub fn hyperlinks_supported() -> bool {
+    let var = |name: &str| env::var(name).unwrap_or_default();
+    match var("FORCE_HYPERLINK").as_str() {
+        "" => {}
+        "0" => return false,
+        _ => return true,
+    }
+    if var("TERM") == "dumb" {
+        return false;
+    }
+    if env::var_os("WT_SESSION").is_some() || env::var_os("DOMTERM").is_some() {
+        return true;
+    }
+    if var("VTE_VERSION").parse::<u32>().is_ok_and(|v| v >= 5000) {
+        return true;
+    }
+    matches!(
+        var("TERM_PROGRAM").as_str(),
+        "iTerm.app" | "WezTerm" | "vscode" | "Hyper" | "ghostty"
+    ) || matches!(var("TERM").as_str(), "xterm-kitty" | "alacritty" | "foot")
+}
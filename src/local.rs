@@ -0,0 +1,103 @@
+use std::sync::atomic::Ordering;
+
+use crate::{
+    line::terminal_width, CommandProcessor, Console, Context, Envelope, Event, HookAction,
+    Tokenizer,
+};
+
+/// A [`Console`] whose inputs are parsed and processed on the thread that
+/// polls it
+///
+/// This is built with [`ConsoleBuilder::build_local`](crate::ConsoleBuilder::build_local).
+/// The console thread only forwards the lines that are submitted, so neither
+/// the processor nor the messages have to be `Send`. This allows parsed
+/// commands that hold `Rc`s, for example.
+///
+/// Since the processor is not on the console thread, it is not used for
+/// completion, hints, inline help, or "did you mean" suggestions.
+pub struct LocalConsole<P, F> {
+    console: Console<String>,
+    processor: P,
+    process: F,
+    tokenizer: Tokenizer,
+    render_errors: bool,
+}
+
+impl<P, F> LocalConsole<P, F> {
+    pub(crate) fn new(
+        console: Console<String>,
+        processor: P,
+        process: F,
+        tokenizer: Tokenizer,
+        render_errors: bool,
+    ) -> Self {
+        LocalConsole {
+            console,
+            processor,
+            process,
+            tokenizer,
+            render_errors,
+        }
+    }
+    /// Get the console that forwards the lines
+    ///
+    /// This can be used for everything but polling, like printing output or
+    /// changing the prompt.
+    pub fn console(&self) -> &Console<String> {
+        &self.console
+    }
+    /// Check if the console is open
+    pub fn is_open(&self) -> bool {
+        self.console.is_open()
+    }
+}
+
+impl<P, F, M> LocalConsole<P, F>
+where
+    P: CommandProcessor,
+    F: Fn(P::Parsed) -> Option<M>,
+{
+    /// Parse and process the next submitted line
+    ///
+    /// Lines that are rejected before they are parsed, or whose errors are
+    /// [rendered](crate::ConsoleBuilder::render_errors), are skipped. If the
+    /// process function returns `None`, the console is closed.
+    pub fn poll(&mut self) -> Option<M> {
+        self.poll_envelope().map(|envelope| envelope.message)
+    }
+    /// Parse and process the next submitted line, returning the message
+    /// along with information about the input
+    pub fn poll_envelope(&mut self) -> Option<Envelope<M>> {
+        loop {
+            let envelope = self.console.poll_envelope()?;
+            let mut input = envelope.message.clone();
+            if let HookAction::Reject(message) = self.processor.before_parse(&mut input) {
+                if let Some(message) = message {
+                    self.console.print(message);
+                }
+                continue;
+            }
+            let context = Context {
+                tokenizer: self.tokenizer,
+                origin: envelope.origin.clone(),
+                timestamp: envelope.timestamp,
+                ..Context::new(envelope.session, envelope.user.clone())
+            };
+            let parsed = self.processor.parse_with_context(&input, &context);
+            if self.render_errors {
+                if let Some(error) = self.processor.render_error(&parsed, terminal_width()) {
+                    self.console.print(error.trim_end());
+                    continue;
+                }
+            }
+            match (self.process)(parsed) {
+                Some(message) => return Some(envelope.map(|_| message)),
+                None => {
+                    self.console.closed.store(true, Ordering::Relaxed);
+                    let _ = self.console.events.send(Event::Wake);
+                    return None;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use std::{error::Error, fmt, str::FromStr};
+
+use colored::Colorize;
+
+use crate::Theme;
+
+/// The severity of a logged message
+///
+/// Levels are ordered from most to least severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Level {
+    /// Something failed
+    Error,
+    /// Something may be wrong
+    Warn,
+    /// Normal information
+    #[default]
+    Info,
+    /// Information useful for debugging
+    Debug,
+    /// Very detailed information
+    Trace,
+}
+
+impl Level {
+    /// All levels, from most to least severe
+    pub const ALL: [Level; 5] = [
+        Level::Error,
+        Level::Warn,
+        Level::Info,
+        Level::Debug,
+        Level::Trace,
+    ];
+    /// Get the lowercase name of the level
+    pub fn name(self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warn => "warn",
+            Level::Info => "info",
+            Level::Debug => "debug",
+            Level::Trace => "trace",
+        }
+    }
+    /// Render a message at this level, colored according to the theme
+    pub(crate) fn render(self, message: &str, theme: Option<&Theme>) -> String {
+        let label = format!("{:5}", self.name().to_uppercase());
+        match theme {
+            Some(theme) => {
+                let color = match self {
+                    Level::Error => theme.error,
+                    Level::Warn => theme.warn,
+                    Level::Info => theme.info,
+                    Level::Debug => theme.debug,
+                    Level::Trace => theme.trace,
+                };
+                format!("{} {}", label.color(color).bold(), message)
+            }
+            None => format!("{} {}", label, message),
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// An error indicating that a string is not the name of a [`Level`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownLevel(pub String);
+
+impl fmt::Display for UnknownLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "unknown log level: {} (expected one of {})",
+            self.0,
+            Level::ALL.map(Level::name).join(", ")
+        )
+    }
+}
+
+impl Error for UnknownLevel {}
+
+impl FromStr for Level {
+    type Err = UnknownLevel;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Level::ALL
+            .iter()
+            .copied()
+            .find(|level| level.name().eq_ignore_ascii_case(s))
+            .or_else(|| s.eq_ignore_ascii_case("warning").then_some(Level::Warn))
+            .ok_or_else(|| UnknownLevel(s.into()))
+    }
+}
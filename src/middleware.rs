@@ -0,0 +1,108 @@
+use crate::{CommandProcessor, Completion, Context, HelpEntry, Hint};
+
+/// What to do with an input after a pre-parse hook has seen it
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum HookAction {
+    /// Continue processing the input
+    #[default]
+    Continue,
+    /// Reject the input so that it is not parsed
+    ///
+    /// The contained message, if any, is printed.
+    Reject(Option<String>),
+}
+
+type PreParseHook = Box<dyn FnMut(&mut String) -> HookAction + Send>;
+type PostParseHook<T> = Box<dyn FnMut(&T) + Send>;
+
+/// A [`CommandProcessor`] that runs hooks before and after another
+/// processor parses an input
+///
+/// Pre-parse hooks can rewrite or reject the input. They are run in the
+/// order they were added, and the first one to reject the input stops the
+/// chain. Post-parse hooks see the parsed value before it is given to the
+/// process function, which makes them useful for logging.
+pub struct Hooks<P: CommandProcessor> {
+    processor: P,
+    pre_parse: Vec<PreParseHook>,
+    post_parse: Vec<PostParseHook<P::Parsed>>,
+}
+
+impl<P: CommandProcessor> Hooks<P> {
+    /// Wrap a processor with no hooks
+    pub fn new(processor: P) -> Self {
+        Hooks {
+            processor,
+            pre_parse: Vec::new(),
+            post_parse: Vec::new(),
+        }
+    }
+    /// Add a hook that is run before the input is parsed
+    pub fn pre_parse<F>(mut self, hook: F) -> Self
+    where
+        F: FnMut(&mut String) -> HookAction + Send + 'static,
+    {
+        self.pre_parse.push(Box::new(hook));
+        self
+    }
+    /// Add a hook that is run after the input is parsed
+    pub fn post_parse<F>(mut self, hook: F) -> Self
+    where
+        F: FnMut(&P::Parsed) + Send + 'static,
+    {
+        self.post_parse.push(Box::new(hook));
+        self
+    }
+    /// Get a reference to the wrapped processor
+    pub fn processor(&self) -> &P {
+        &self.processor
+    }
+    /// Get a mutable reference to the wrapped processor
+    pub fn processor_mut(&mut self) -> &mut P {
+        &mut self.processor
+    }
+    fn after_parse(&mut self, parsed: P::Parsed) -> P::Parsed {
+        for hook in &mut self.post_parse {
+            hook(&parsed);
+        }
+        parsed
+    }
+}
+
+impl<P: CommandProcessor> CommandProcessor for Hooks<P> {
+    type Parsed = P::Parsed;
+    fn before_parse(&mut self, input: &mut String) -> HookAction {
+        if let HookAction::Reject(message) = self.processor.before_parse(input) {
+            return HookAction::Reject(message);
+        }
+        for hook in &mut self.pre_parse {
+            if let HookAction::Reject(message) = hook(input) {
+                return HookAction::Reject(message);
+            }
+        }
+        HookAction::Continue
+    }
+    fn parse(&mut self, input: &str) -> Self::Parsed {
+        let parsed = self.processor.parse(input);
+        self.after_parse(parsed)
+    }
+    fn parse_with_context(&mut self, input: &str, context: &Context) -> Self::Parsed {
+        let parsed = self.processor.parse_with_context(input, context);
+        self.after_parse(parsed)
+    }
+    fn complete(&mut self, input: &str, cursor: usize) -> Completion {
+        self.processor.complete(input, cursor)
+    }
+    fn help(&mut self, input: &str) -> Vec<HelpEntry> {
+        self.processor.help(input)
+    }
+    fn hint(&mut self, input: &str, cursor: usize) -> Option<Hint> {
+        self.processor.hint(input, cursor)
+    }
+    fn commands(&mut self) -> Vec<String> {
+        self.processor.commands()
+    }
+    fn unrecognized(&mut self, input: &str, parsed: &Self::Parsed) -> bool {
+        self.processor.unrecognized(input, parsed)
+    }
+}
@@ -0,0 +1,68 @@
+use crate::{CommandProcessor, Completion, Context, HelpEntry, Hint, HookAction};
+
+/// A processor and process function for a [`Mode`]
+pub(crate) trait ModeProcessor<M>: Send {
+    fn before_parse(&mut self, input: &mut String) -> HookAction;
+    fn handle(&mut self, input: &str, context: &Context) -> Option<M>;
+    fn complete(&mut self, input: &str, cursor: usize) -> Completion;
+    fn help(&mut self, input: &str) -> Vec<HelpEntry>;
+    fn hint(&mut self, input: &str, cursor: usize) -> Option<Hint>;
+}
+
+struct Processing<P, F> {
+    processor: P,
+    process: F,
+}
+
+impl<M, P, F> ModeProcessor<M> for Processing<P, F>
+where
+    P: CommandProcessor + Send,
+    F: Fn(P::Parsed) -> Option<M> + Send,
+{
+    fn before_parse(&mut self, input: &mut String) -> HookAction {
+        self.processor.before_parse(input)
+    }
+    fn handle(&mut self, input: &str, context: &Context) -> Option<M> {
+        (self.process)(self.processor.parse_with_context(input, context))
+    }
+    fn complete(&mut self, input: &str, cursor: usize) -> Completion {
+        self.processor.complete(input, cursor)
+    }
+    fn help(&mut self, input: &str) -> Vec<HelpEntry> {
+        self.processor.help(input)
+    }
+    fn hint(&mut self, input: &str, cursor: usize) -> Option<Hint> {
+        self.processor.hint(input, cursor)
+    }
+}
+
+/// A nested mode with its own prompt and processor
+///
+/// Modes are pushed with [`Console::push_mode`](crate::Console::push_mode).
+/// While a mode is active, all input is handled by its processor. Entering
+/// `exit` or returning `None` from the mode's process function leaves the mode.
+pub(crate) struct Mode<M> {
+    pub prompt: String,
+    pub processor: Box<dyn ModeProcessor<M>>,
+}
+
+impl<M> Mode<M> {
+    pub fn new<P, F>(prompt: String, processor: P, process: F) -> Self
+    where
+        P: CommandProcessor + Send + 'static,
+        F: Fn(P::Parsed) -> Option<M> + Send + 'static,
+    {
+        Mode {
+            prompt,
+            processor: Box::new(Processing { processor, process }),
+        }
+    }
+}
+
+/// Get the prompt for the top of the mode stack
+pub(crate) fn current_prompt<M>(base: &str, modes: &[Mode<M>]) -> String {
+    modes
+        .last()
+        .map(|mode| mode.prompt.clone())
+        .unwrap_or_else(|| base.to_string())
+}
@@ -0,0 +1,313 @@
+use std::{
+    convert::TryFrom,
+    io::{self, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use crate::{Console, Input, InputSource, Origin, OutputSink, Role};
+
+/// A bridge that drives a [`Console`] through an MQTT broker
+///
+/// The bridge subscribes to a command topic and runs every message published
+/// to it as a command. Output printed with [`Console::print_to`] for the
+/// bridge's [origin](MqttBridge::origin) is published to the response topic.
+///
+/// This is a small MQTT 3.1.1 client. Messages are sent and received with
+/// QoS 0, the connection is plain TCP without TLS, and packets larger than
+/// 1 MiB close the connection. The command topic may use the `+` and `#`
+/// wildcards.
+#[derive(Debug, Clone)]
+pub struct MqttBridge {
+    client_id: String,
+    command_topic: String,
+    response_topic: String,
+    keep_alive: Duration,
+    credentials: Option<(String, String)>,
+    role: Role,
+}
+
+impl MqttBridge {
+    /// Create a bridge that reads commands from one topic and publishes
+    /// responses to another
+    pub fn new<C, R>(command_topic: C, response_topic: R) -> Self
+    where
+        C: Into<String>,
+        R: Into<String>,
+    {
+        MqttBridge {
+            client_id: format!("encore-{}", std::process::id()),
+            command_topic: command_topic.into(),
+            response_topic: response_topic.into(),
+            keep_alive: Duration::from_secs(60),
+            credentials: None,
+            role: Role::Restricted,
+        }
+    }
+    /// Set the id the bridge identifies itself to the broker with
+    pub fn client_id<S: Into<String>>(mut self, client_id: S) -> Self {
+        self.client_id = client_id.into();
+        self
+    }
+    /// Set the interval in which the bridge lets the broker know it is alive
+    ///
+    /// This is 60 seconds by default.
+    pub fn keep_alive(mut self, keep_alive: Duration) -> Self {
+        self.keep_alive = keep_alive;
+        self
+    }
+    /// Set the user name and password the bridge connects with
+    ///
+    /// They are sent in plain text, so the connection to the broker should
+    /// be trusted. By default, no credentials are sent.
+    pub fn credentials<U, P>(mut self, user: U, password: P) -> Self
+    where
+        U: Into<String>,
+        P: Into<String>,
+    {
+        self.credentials = Some((user.into(), password.into()));
+        self
+    }
+    /// Set what the commands from the bridge are allowed to do
+    ///
+    /// Anyone who can publish to the command topic can send commands, so
+    /// this is [`Role::Restricted`] by default.
+    pub fn role(mut self, role: Role) -> Self {
+        self.role = role;
+        self
+    }
+    /// Get the origin of the commands from the bridge
+    pub fn origin(&self) -> Origin {
+        Origin::Source(format!("mqtt:{}", self.command_topic))
+    }
+}
+
+impl<M> Console<M>
+where
+    M: Send + 'static,
+{
+    /// Connect to an MQTT broker and take commands from it
+    pub fn connect_mqtt<A: ToSocketAddrs>(&self, broker: A, bridge: &MqttBridge) -> io::Result<()> {
+        let mut stream = TcpStream::connect(broker)?;
+        // Connect with a clean session
+        let mut connect = string(b"MQTT")?;
+        let flags = if bridge.credentials.is_some() {
+            0xC2
+        } else {
+            0x02
+        };
+        connect.extend([4, flags]);
+        connect.extend((bridge.keep_alive.as_secs().min(u16::MAX as u64) as u16).to_be_bytes());
+        connect.extend(string(bridge.client_id.as_bytes())?);
+        if let Some((user, password)) = &bridge.credentials {
+            connect.extend(string(user.as_bytes())?);
+            connect.extend(string(password.as_bytes())?);
+        }
+        write_packet(&mut stream, 0x10, &connect)?;
+        let (kind, body) = read_packet(&mut stream)?;
+        if kind >> 4 != 2 || body.len() < 2 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "expected a CONNACK packet",
+            ));
+        }
+        if body[1] != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::ConnectionRefused,
+                format!("broker refused the connection with code {}", body[1]),
+            ));
+        }
+        // Subscribe to the command topic with packet id 1
+        let mut subscribe = vec![0, 1];
+        subscribe.extend(string(bridge.command_topic.as_bytes())?);
+        subscribe.push(0);
+        write_packet(&mut stream, 0x82, &subscribe)?;
+        let writer = Arc::new(Mutex::new(stream.try_clone()?));
+        // Ping the broker so that it keeps the connection open
+        if bridge.keep_alive > Duration::ZERO {
+            let writer = Arc::clone(&writer);
+            let interval = bridge.keep_alive / 2;
+            thread::spawn(move || loop {
+                thread::sleep(interval);
+                if write_packet(&mut *writer.lock().unwrap(), 0xC0, &[]).is_err() {
+                    return;
+                }
+            });
+        }
+        let origin = bridge.origin();
+        self.add_origin_sink(
+            origin.clone(),
+            MqttSink {
+                writer,
+                topic: bridge.response_topic.clone(),
+            },
+        );
+        self.add_input_source(MqttSource {
+            stream,
+            topic: bridge.command_topic.clone(),
+            origin,
+            role: bridge.role,
+        });
+        Ok(())
+    }
+}
+
+/// Reads the commands published to the command topic
+struct MqttSource {
+    stream: TcpStream,
+    topic: String,
+    origin: Origin,
+    role: Role,
+}
+
+impl InputSource for MqttSource {
+    fn next_input(&mut self) -> Option<Input> {
+        loop {
+            let (kind, body) = read_packet(&mut self.stream).ok()?;
+            if kind >> 4 != 3 || body.len() < 2 {
+                continue;
+            }
+            let topic_len = u16::from_be_bytes([body[0], body[1]]) as usize;
+            let topic = body.get(2..2 + topic_len)?;
+            if !topic_matches(&self.topic, &String::from_utf8_lossy(topic)) {
+                continue;
+            }
+            // Messages with a QoS above 0 have a packet id before the payload
+            let mut payload_start = 2 + topic_len;
+            if (kind >> 1) & 3 > 0 {
+                payload_start += 2;
+            }
+            let payload = body.get(payload_start..)?;
+            return Some(Input::Line(String::from_utf8_lossy(payload).trim().into()));
+        }
+    }
+    fn origin(&self) -> Origin {
+        self.origin.clone()
+    }
+    fn role(&self) -> Role {
+        self.role
+    }
+}
+
+/// Publishes output to the response topic
+struct MqttSink {
+    writer: Arc<Mutex<TcpStream>>,
+    topic: String,
+}
+
+impl OutputSink for MqttSink {
+    fn write(&mut self, output: &str) -> io::Result<()> {
+        let mut publish = string(self.topic.as_bytes())?;
+        publish.extend(output.as_bytes());
+        write_packet(&mut *self.writer.lock().unwrap(), 0x30, &publish)
+    }
+    fn ansi(&self) -> bool {
+        false
+    }
+}
+
+/// Check if a topic matches a topic filter, which may have wildcards
+///
+/// `+` matches one level and `#` matches any number of levels at the end.
+/// Wildcards at the start do not match topics starting with `$`.
+fn topic_matches(filter: &str, topic: &str) -> bool {
+    if topic.starts_with('$') && filter.starts_with(['+', '#']) {
+        return false;
+    }
+    let mut filter = filter.split('/');
+    let mut topic = topic.split('/');
+    loop {
+        match (filter.next(), topic.next()) {
+            (Some("#"), _) => return true,
+            (Some("+"), Some(_)) => {}
+            (Some(f), Some(t)) if f == t => {}
+            (None, None) => return true,
+            _ => return false,
+        }
+    }
+}
+
+/// Encode a length-prefixed string
+///
+/// Strings longer than 65535 bytes cannot be encoded.
+fn string(bytes: &[u8]) -> io::Result<Vec<u8>> {
+    let len = u16::try_from(bytes.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "string is too long for MQTT"))?;
+    let mut encoded = len.to_be_bytes().to_vec();
+    encoded.extend(bytes);
+    Ok(encoded)
+}
+
+/// Write a packet with the given first byte and body
+fn write_packet<W: Write>(writer: &mut W, kind: u8, body: &[u8]) -> io::Result<()> {
+    let mut packet = vec![kind];
+    // The remaining length is encoded 7 bits at a time
+    let mut len = body.len();
+    loop {
+        let mut byte = (len % 128) as u8;
+        len /= 128;
+        if len > 0 {
+            byte |= 0x80;
+        }
+        packet.push(byte);
+        if len == 0 {
+            break;
+        }
+    }
+    packet.extend(body);
+    writer.write_all(&packet)?;
+    writer.flush()
+}
+
+/// The largest packet body that is read
+const MAX_PACKET: usize = 1024 * 1024;
+
+/// Read a packet, returning its first byte and body
+fn read_packet<R: Read>(reader: &mut R) -> io::Result<(u8, Vec<u8>)> {
+    let mut byte = [0];
+    reader.read_exact(&mut byte)?;
+    let kind = byte[0];
+    let mut len = 0;
+    for shift in (0..28).step_by(7) {
+        reader.read_exact(&mut byte)?;
+        len |= ((byte[0] & 0x7F) as usize) << shift;
+        if byte[0] & 0x80 == 0 {
+            break;
+        }
+    }
+    if len > MAX_PACKET {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "packet is too large",
+        ));
+    }
+    let mut body = vec![0; len];
+    reader.read_exact(&mut body)?;
+    Ok((kind, body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wildcards() {
+        assert!(topic_matches("a/b", "a/b"));
+        assert!(!topic_matches("a/b", "a/c"));
+        assert!(topic_matches("a/+/c", "a/b/c"));
+        assert!(!topic_matches("a/+", "a/b/c"));
+        assert!(topic_matches("a/#", "a"));
+        assert!(topic_matches("a/#", "a/b/c"));
+        assert!(topic_matches("#", "a/b"));
+        assert!(!topic_matches("#", "$SYS/uptime"));
+    }
+
+    #[test]
+    fn string_lengths() {
+        assert_eq!(string(b"ab").unwrap(), [0, 2, b'a', b'b']);
+        assert_eq!(string(&[0; 65535]).unwrap().len(), 65537);
+        assert!(string(&[0; 65536]).is_err());
+    }
+}
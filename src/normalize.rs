@@ -0,0 +1,48 @@
+/// What happens when empty input is submitted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EmptyInput {
+    /// Submit the empty input like any other
+    #[default]
+    Submit,
+    /// Ignore the input
+    Ignore,
+    /// Run the previous command in the history again
+    RepeatLast,
+    /// Send the message set with
+    /// [`Console::set_tick_message`](crate::Console::set_tick_message)
+    Tick,
+}
+
+/// The way the letter case of submitted input is changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CaseNormalization {
+    /// Keep the input as it was entered
+    #[default]
+    Preserve,
+    /// Make the whole input lowercase
+    Lowercase,
+    /// Make only the command name at the start of the input lowercase
+    LowercaseCommand,
+}
+
+impl CaseNormalization {
+    /// Change the case of an input
+    pub fn apply(self, input: String) -> String {
+        match self {
+            CaseNormalization::Preserve => input,
+            CaseNormalization::Lowercase => input.to_lowercase(),
+            CaseNormalization::LowercaseCommand => {
+                let start = input.len() - input.trim_start().len();
+                let end = input[start..]
+                    .find(char::is_whitespace)
+                    .map_or(input.len(), |end| start + end);
+                format!(
+                    "{}{}{}",
+                    &input[..start],
+                    input[start..end].to_lowercase(),
+                    &input[end..]
+                )
+            }
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use std::{sync::Arc, thread};
+
+use crate::{strip_ansi, Level};
+
+/// A function that decides which output shows a desktop notification
+pub(crate) type NotifyFilter = Arc<dyn Fn(Option<Level>, &str) -> bool + Send + Sync>;
+
+/// Show a desktop notification for some output if the filter allows it
+///
+/// The notification is shown on its own thread, since showing it can block.
+pub(crate) fn notify(filter: &Option<NotifyFilter>, level: Option<Level>, output: &str) {
+    let filter = match filter {
+        Some(filter) => filter,
+        None => return,
+    };
+    let body = strip_ansi(output);
+    if !filter(level, &body) {
+        return;
+    }
+    let summary = app_name();
+    let summary = match level {
+        Some(level) => format!("{}: {}", summary, level.name()),
+        None => summary,
+    };
+    thread::spawn(move || {
+        let _ = notify_rust::Notification::new()
+            .summary(&summary)
+            .body(&body)
+            .show();
+    });
+}
+
+/// Get the name of the running program
+fn app_name() -> String {
+    std::env::current_exe()
+        .ok()
+        .and_then(|path| path.file_stem().map(|stem| stem.to_string_lossy().into()))
+        .unwrap_or_else(|| "console".into())
+}
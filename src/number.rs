@@ -0,0 +1,136 @@
+use std::{fmt::Display, str::FromStr, sync::Arc};
+
+use crate::ConsoleBuilder;
+
+/// A number type that can be read with a [`NumberPrompt`]
+pub trait Number: FromStr + Display + PartialOrd + Copy + Send + Sync + 'static {
+    /// Zero
+    const ZERO: Self;
+    /// One, the default step
+    const ONE: Self;
+    /// Add two numbers, saturating at the type's bounds
+    fn step_up(self, step: Self) -> Self;
+    /// Subtract two numbers, saturating at the type's bounds
+    fn step_down(self, step: Self) -> Self;
+}
+
+macro_rules! int_number {
+    ($($ty:ty),*) => {
+        $(
+            impl Number for $ty {
+                const ZERO: Self = 0;
+                const ONE: Self = 1;
+                fn step_up(self, step: Self) -> Self {
+                    self.saturating_add(step)
+                }
+                fn step_down(self, step: Self) -> Self {
+                    self.saturating_sub(step)
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! float_number {
+    ($($ty:ty),*) => {
+        $(
+            impl Number for $ty {
+                const ZERO: Self = 0.0;
+                const ONE: Self = 1.0;
+                fn step_up(self, step: Self) -> Self {
+                    self + step
+                }
+                fn step_down(self, step: Self) -> Self {
+                    self - step
+                }
+            }
+        )*
+    };
+}
+
+int_number!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+float_number!(f32, f64);
+
+/// A prompt that reads a number
+///
+/// Up and Down add and subtract the step from the number being typed, and
+/// numbers outside of the bounds are rejected.
+///
+/// ```no_run
+/// use encore::NumberPrompt;
+///
+/// let port: Option<u16> = NumberPrompt::new("Port: ").min(1024).read();
+/// ```
+pub struct NumberPrompt<T> {
+    prompt: String,
+    min: Option<T>,
+    max: Option<T>,
+    step: T,
+}
+
+impl<T: Number> NumberPrompt<T> {
+    /// Create a new `NumberPrompt` with the given prompt
+    pub fn new<P: Into<String>>(prompt: P) -> Self {
+        NumberPrompt {
+            prompt: prompt.into(),
+            min: None,
+            max: None,
+            step: T::ONE,
+        }
+    }
+    /// Set the smallest number that is accepted
+    pub fn min(mut self, min: T) -> Self {
+        self.min = Some(min);
+        self
+    }
+    /// Set the largest number that is accepted
+    pub fn max(mut self, max: T) -> Self {
+        self.max = Some(max);
+        self
+    }
+    /// Set the amount that Up and Down change the number by
+    ///
+    /// This is one by default.
+    pub fn step(mut self, step: T) -> Self {
+        self.step = step;
+        self
+    }
+    /// Keep a number within the bounds
+    fn clamp(&self, n: T) -> T {
+        match (self.min, self.max) {
+            (Some(min), _) if n < min => min,
+            (_, Some(max)) if n > max => max,
+            _ => n,
+        }
+    }
+    /// Get the input after pressing Up or Down
+    fn stepped(&self, input: &str, up: bool) -> String {
+        let n = match input.trim().parse::<T>() {
+            Ok(n) if up => n.step_up(self.step),
+            Ok(n) => n.step_down(self.step),
+            Err(_) => T::ZERO,
+        };
+        self.clamp(n).to_string()
+    }
+    /// Check that an answer is a number within the bounds
+    fn parse(&self, input: &str) -> Result<T, String> {
+        let n = input
+            .parse::<T>()
+            .map_err(|_| format!("{} is not a valid number", input))?;
+        match (self.min, self.max) {
+            (Some(min), _) if n < min => Err(format!("the number must be at least {}", min)),
+            (_, Some(max)) if n > max => Err(format!("the number must be at most {}", max)),
+            _ => Ok(n),
+        }
+    }
+    /// Ask for the number, blocking until a valid one is submitted
+    ///
+    /// Returns `None` if the input ends or the user quits.
+    pub fn read(self) -> Option<T> {
+        let mut builder = ConsoleBuilder::new().prompt(self.prompt.clone());
+        let prompt = Arc::new(self);
+        let stepper = Arc::clone(&prompt);
+        builder.stepper = Some(Box::new(move |input, up| stepper.stepped(input, up)));
+        builder.read_with(move |input| prompt.parse(input)).ok()
+    }
+}
@@ -0,0 +1,177 @@
+use std::{
+    fmt::Display,
+    sync::{mpsc::Sender, Arc, Mutex},
+};
+
+use crate::{
+    line::{flush, terminal_height, terminal_width, Line},
+    plugin::{filter_output, Plugins},
+    sink::{write_to_all_sinks, write_to_sinks, Sinks},
+    Event, Level, Origin, OutputCounts, Pager, Scrollback, Theme,
+};
+
+/// The way a [`Console`](crate::Console) formats its output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputMode {
+    /// Output is meant to be read by a human at the terminal
+    #[default]
+    Interactive,
+    /// Output is meant to be read by other programs
+    Machine,
+}
+
+/// A handle for printing output without disturbing the line being edited
+///
+/// `Responder`s are cheap to clone and can be sent to other threads.
+#[derive(Clone)]
+pub struct Responder {
+    pub(crate) line: Arc<Mutex<Line>>,
+    pub(crate) mode: OutputMode,
+    pub(crate) pager: bool,
+    pub(crate) scrollback: Option<Arc<Mutex<Scrollback>>>,
+    pub(crate) theme: Theme,
+    pub(crate) log_level: Arc<Mutex<Level>>,
+    pub(crate) plugins: Plugins,
+    pub(crate) sinks: Sinks,
+    pub(crate) outputs: Arc<OutputCounts>,
+    pub(crate) events: Sender<Event>,
+    #[cfg(feature = "notifications")]
+    pub(crate) notify: Option<crate::NotifyFilter>,
+}
+
+impl Responder {
+    /// Get the output mode
+    pub fn mode(&self) -> OutputMode {
+        self.mode
+    }
+    /// Print some output above the input line
+    pub fn print<D: Display>(&self, output: D) {
+        self.print_at(None, output.to_string());
+    }
+    /// Print some output to the terminal and to every session
+    ///
+    /// The output is written to the sinks of every
+    /// [input source](crate::InputSource), like the connections accepted with
+    /// [`Console::accept_tcp`](crate::Console::accept_tcp), as well as the
+    /// usual ones. This is useful for announcements like "server restarting
+    /// in 5 minutes". A session that does not take its output can hold up
+    /// the call for a few seconds, after which the session is dropped.
+    pub fn broadcast<D: Display>(&self, output: D) {
+        let output = match filter_output(&self.plugins, output.to_string()) {
+            Some(output) => output,
+            None => return,
+        };
+        write_to_all_sinks(&self.sinks, &output);
+        self.display(None, output);
+    }
+    /// Print some output, which was logged at a level if there is one
+    fn print_at(&self, level: Option<Level>, output: String) {
+        if let Some(output) = filter_output(&self.plugins, output) {
+            self.show(level, output);
+        }
+    }
+    /// Show some output that has been filtered by the plugins
+    fn show(&self, level: Option<Level>, output: String) {
+        // The sinks are written to before taking the line, so that a slow
+        // sink does not hold up the editor
+        write_to_sinks(&self.sinks, None, &output);
+        self.display(level, output);
+    }
+    /// Show some output on the terminal without writing it to the sinks
+    fn display(&self, level: Option<Level>, output: String) {
+        let mut line = self.line.lock().unwrap();
+        #[cfg(feature = "notifications")]
+        crate::notify(&self.notify, level, &output);
+        #[cfg(not(feature = "notifications"))]
+        let _ = level;
+        self.outputs.add(&Origin::Terminal);
+        match self.mode {
+            OutputMode::Interactive => {
+                if let Some(scrollback) = &self.scrollback {
+                    scrollback.lock().unwrap().push(&output);
+                }
+                if line.detached {
+                    // The output is replayed from the scrollback when the
+                    // terminal is attached again
+                } else if let Some(pager) = &mut line.pager {
+                    pager.extend(&output, terminal_width());
+                    line.draw();
+                } else if self.pager && output.lines().count() >= terminal_height() {
+                    line.pager = Some(Pager::new(&output, terminal_width()));
+                    line.draw();
+                } else {
+                    let output = match &mut line.wrap {
+                        Some(wrap) => wrap.wrap(&output, terminal_width(), terminal_height()),
+                        None => output,
+                    };
+                    if line.print(&output) {
+                        // Wake the editor to print the batch when the frame
+                        // is due
+                        let _ = self.events.send(Event::Wake);
+                    }
+                }
+            }
+            OutputMode::Machine => {
+                println!("{}", output);
+                flush();
+            }
+        }
+    }
+    /// Print some output for the given origin
+    ///
+    /// Output for the terminal or the console itself is printed as usual.
+    /// Output for an [input source](crate::InputSource) is only written to
+    /// the sinks that were added for it.
+    pub fn print_to<D: Display>(&self, origin: &Origin, output: D) {
+        if let Origin::Terminal | Origin::Console = origin {
+            return self.print(output);
+        }
+        if let Some(output) = filter_output(&self.plugins, output.to_string()) {
+            self.outputs.add(origin);
+            write_to_sinks(&self.sinks, Some(origin), &output);
+        }
+    }
+    /// Print a message at the given level
+    ///
+    /// The message is only printed if its level is at least as severe as
+    /// the [log level](Responder::log_level). In interactive mode, the level
+    /// is colored according to the console's [`Theme`].
+    pub fn log<D: Display>(&self, level: Level, message: D) {
+        if level > self.log_level() {
+            return;
+        }
+        let theme = match self.mode {
+            OutputMode::Interactive => Some(&self.theme),
+            OutputMode::Machine => None,
+        };
+        self.print_at(Some(level), level.render(&message.to_string(), theme));
+    }
+    /// Get the least severe level of messages that are printed
+    ///
+    /// This can also be changed by the user with the `loglevel`
+    /// [built-in](crate::ConsoleBuilder::builtins) command if built-ins are
+    /// enabled.
+    pub fn log_level(&self) -> Level {
+        *self.log_level.lock().unwrap()
+    }
+    /// Set the least severe level of messages that are printed
+    pub fn set_log_level(&self, level: Level) {
+        *self.log_level.lock().unwrap() = level;
+    }
+    /// Print a serializable value
+    ///
+    /// In interactive mode the value is pretty-printed. In machine mode it is
+    /// printed as a single line of JSON.
+    #[cfg(feature = "json")]
+    pub fn print_json<T>(&self, value: &T) -> serde_json::Result<()>
+    where
+        T: serde::Serialize + ?Sized,
+    {
+        let output = match self.mode {
+            OutputMode::Interactive => serde_json::to_string_pretty(value)?,
+            OutputMode::Machine => serde_json::to_string(value)?,
+        };
+        self.print(output);
+        Ok(())
+    }
+}
@@ -0,0 +1,282 @@
+use colored::Colorize;
+use crossterm::{ClearType, KeyEvent, Terminal};
+
+use crate::{line::flush, wrap_line};
+
+/// A row of the pager
+struct Row {
+    /// The index of the line the row was wrapped from
+    line: usize,
+    text: String,
+}
+
+/// What the pager is asking the user to type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Prompt {
+    Search,
+    SavePath,
+}
+
+/// Something the pager asks the editor to do after a key press
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum PagerAction {
+    /// Keep the pager open
+    Continue,
+    /// Close the pager
+    Close,
+    /// Copy the contained text to the clipboard
+    Copy(String),
+    /// Save the text to the file at the path
+    Save { path: String, text: String },
+}
+
+/// A less-style view of output that is too long to fit on the screen or of
+/// the scrollback
+pub(crate) struct Pager {
+    lines: Vec<String>,
+    rows: Vec<Row>,
+    /// The index of the first row shown
+    top: usize,
+    /// The text being typed at a prompt, if any
+    typing: Option<(Prompt, String)>,
+    /// The last search query
+    query: Option<String>,
+    /// Whether the last search was toward the top
+    backward: bool,
+    /// The rows at either end of the marked range, with the second one
+    /// being the one that moves
+    mark: Option<(usize, usize)>,
+    /// A message shown in the status row instead of the position
+    message: Option<String>,
+}
+
+impl Pager {
+    /// Create a `Pager` for some text, wrapped to the given width
+    pub fn new(text: &str, width: usize) -> Self {
+        let mut pager = Pager {
+            lines: Vec::new(),
+            rows: Vec::new(),
+            top: 0,
+            typing: None,
+            query: None,
+            backward: false,
+            mark: None,
+            message: None,
+        };
+        pager.extend(text, width);
+        pager
+    }
+    /// Add more text to the end
+    pub fn extend(&mut self, text: &str, width: usize) {
+        for line in text.lines() {
+            let index = self.lines.len();
+            self.rows.extend(
+                wrap_line(line, width, 0)
+                    .into_iter()
+                    .map(|text| Row { line: index, text }),
+            );
+            self.lines.push(line.into());
+        }
+    }
+    /// Scroll so that the last rows are shown
+    pub fn scroll_to_bottom(&mut self, height: usize) {
+        let page = height.saturating_sub(1).max(1);
+        self.top = self.rows.len().saturating_sub(page);
+    }
+    /// Start typing a search query
+    pub fn start_search(&mut self, backward: bool) {
+        self.typing = Some((Prompt::Search, String::new()));
+        self.backward = backward;
+    }
+    /// Show a message in the status row until the next key press
+    pub fn set_message<S: Into<String>>(&mut self, message: S) {
+        self.message = Some(message.into());
+    }
+    /// Handle a key press
+    pub fn handle_key(&mut self, key: KeyEvent, height: usize) -> PagerAction {
+        let page = height.saturating_sub(1).max(1);
+        self.message = None;
+        if let Some((prompt, typing)) = &mut self.typing {
+            match key {
+                KeyEvent::Char('\n') => {
+                    let prompt = *prompt;
+                    let typed = std::mem::take(typing);
+                    self.typing = None;
+                    match prompt {
+                        Prompt::Search => {
+                            if !typed.is_empty() {
+                                self.query = Some(typed);
+                            }
+                            self.find(false, false, page);
+                        }
+                        Prompt::SavePath if typed.is_empty() => {}
+                        Prompt::SavePath => {
+                            if let Some(text) = self.take_marked() {
+                                return PagerAction::Save { path: typed, text };
+                            }
+                        }
+                    }
+                }
+                KeyEvent::Esc | KeyEvent::Ctrl('c') => self.typing = None,
+                KeyEvent::Backspace if typing.is_empty() => self.typing = None,
+                KeyEvent::Backspace => {
+                    typing.pop();
+                }
+                KeyEvent::Char(c) => typing.push(c),
+                _ => {}
+            }
+            return PagerAction::Continue;
+        }
+        if let Some((_, cursor)) = &mut self.mark {
+            // Move the end of the marked range
+            let last = self.rows.len().saturating_sub(1);
+            let moved = match key {
+                KeyEvent::Char(' ') | KeyEvent::Char('f') | KeyEvent::PageDown => {
+                    Some((*cursor + page).min(last))
+                }
+                KeyEvent::Char('b') | KeyEvent::PageUp => Some(cursor.saturating_sub(page)),
+                KeyEvent::Char('j') | KeyEvent::Down => Some((*cursor + 1).min(last)),
+                KeyEvent::Char('k') | KeyEvent::Up => Some(cursor.saturating_sub(1)),
+                KeyEvent::Char('g') | KeyEvent::Home => Some(0),
+                KeyEvent::Char('G') | KeyEvent::End => Some(last),
+                _ => None,
+            };
+            if let Some(moved) = moved {
+                *cursor = moved;
+                if moved < self.top {
+                    self.top = moved;
+                } else if moved >= self.top + page {
+                    self.top = moved + 1 - page;
+                }
+                return PagerAction::Continue;
+            }
+            match key {
+                KeyEvent::Char('y') | KeyEvent::Char('\n') => {
+                    if let Some(text) = self.take_marked() {
+                        return PagerAction::Copy(text);
+                    }
+                }
+                KeyEvent::Char('w') => self.typing = Some((Prompt::SavePath, String::new())),
+                KeyEvent::Char('v') | KeyEvent::Esc | KeyEvent::Ctrl('c') => self.mark = None,
+                _ => {}
+            }
+            return PagerAction::Continue;
+        }
+        let last_top = self.rows.len().saturating_sub(page);
+        match key {
+            KeyEvent::Char('q') | KeyEvent::Esc | KeyEvent::Ctrl('c') => return PagerAction::Close,
+            KeyEvent::Char(' ') | KeyEvent::Char('f') | KeyEvent::PageDown => {
+                self.top = (self.top + page).min(last_top)
+            }
+            KeyEvent::Char('b') | KeyEvent::PageUp => self.top = self.top.saturating_sub(page),
+            KeyEvent::Char('\n') | KeyEvent::Char('j') | KeyEvent::Down => {
+                self.top = (self.top + 1).min(last_top)
+            }
+            KeyEvent::Char('k') | KeyEvent::Up => self.top = self.top.saturating_sub(1),
+            KeyEvent::Char('g') | KeyEvent::Home => self.top = 0,
+            KeyEvent::Char('G') | KeyEvent::End => self.top = last_top,
+            KeyEvent::Char('/') => self.start_search(false),
+            KeyEvent::Char('?') => self.start_search(true),
+            KeyEvent::Char('n') => self.find(false, true, page),
+            KeyEvent::Char('N') => self.find(true, true, page),
+            KeyEvent::Char('v') if !self.rows.is_empty() => {
+                self.mark = Some((self.top, self.top));
+            }
+            _ => {}
+        }
+        PagerAction::Continue
+    }
+    /// Get the rows in the marked range
+    fn marked_rows(&self) -> Option<(usize, usize)> {
+        let (anchor, cursor) = self.mark?;
+        Some((anchor.min(cursor), anchor.max(cursor)))
+    }
+    /// Stop marking, returning the lines that the marked rows were wrapped
+    /// from
+    fn take_marked(&mut self) -> Option<String> {
+        let (first, last) = self.marked_rows()?;
+        self.mark = None;
+        let lines = &self.lines[self.rows[first].line..=self.rows[last].line];
+        Some(lines.join("\n"))
+    }
+    /// Scroll to the next row that matches the query in the direction of
+    /// the last search, or in the opposite direction if `reverse` is set
+    ///
+    /// If `skip_current` is set, rows that are already shown are skipped.
+    fn find(&mut self, reverse: bool, skip_current: bool, page: usize) {
+        let query = match &self.query {
+            Some(query) => query,
+            None => return,
+        };
+        let matches = |row: &Row| row.text.contains(query.as_str());
+        let found = if self.backward == reverse {
+            let start = self.top + skip_current as usize;
+            self.rows
+                .iter()
+                .skip(start)
+                .position(matches)
+                .map(|i| start + i)
+        } else {
+            let end = if skip_current {
+                self.top
+            } else {
+                self.top + page
+            };
+            self.rows[..end.min(self.rows.len())]
+                .iter()
+                .rposition(matches)
+        };
+        match found {
+            Some(i) => self.top = i.min(self.rows.len().saturating_sub(page)),
+            None => self.message = Some(format!("pattern not found: {}", query)),
+        }
+    }
+    /// Draw the visible rows and the status row, filling the screen
+    pub fn draw(&self, height: usize) {
+        let page = height.saturating_sub(1).max(1);
+        let marked = self.marked_rows();
+        let _ = Terminal::new().clear(ClearType::All);
+        for (i, row) in self.rows.iter().enumerate().skip(self.top).take(page) {
+            if marked.is_some_and(|(first, last)| first <= i && i <= last) {
+                print!("{}\r\n", row.text.reversed());
+                continue;
+            }
+            match &self.query {
+                Some(query) if !query.is_empty() => {
+                    let highlighted = query.reversed().to_string();
+                    print!("{}\r\n", row.text.replace(query.as_str(), &highlighted));
+                }
+                _ => print!("{}\r\n", row.text),
+            }
+        }
+        let status = if let Some((prompt, typing)) = &self.typing {
+            match prompt {
+                Prompt::Search if self.backward => format!("?{}", typing),
+                Prompt::Search => format!("/{}", typing),
+                Prompt::SavePath => format!("save to: {}", typing),
+            }
+        } else if let Some(message) = &self.message {
+            message.reversed().to_string()
+        } else if let Some((first, last)) = marked {
+            format!(
+                "marked lines {}-{} (arrows to extend, y to copy, w to save, v to cancel)",
+                first + 1,
+                last + 1
+            )
+            .reversed()
+            .to_string()
+        } else {
+            let bottom = (self.top + page).min(self.rows.len());
+            format!(
+                "lines {}-{}/{} (space, arrows, / to search, v to mark, q to quit)",
+                self.top + 1,
+                bottom,
+                self.rows.len()
+            )
+            .reversed()
+            .to_string()
+        };
+        print!("{}", status);
+        flush();
+    }
+}
@@ -0,0 +1,104 @@
+use std::{fs, path::PathBuf};
+
+use crate::{home_dir, word_start, Candidate, CaseMatching, Completer, Completion, Matching};
+
+/// A [`Completer`] that completes the word at the cursor as a file path
+///
+/// Directories are completed with a trailing `/` so that pressing Tab again
+/// completes their contents. A `~` at the start of the word refers to the
+/// home directory. Hidden files are only completed if hidden files are
+/// enabled or the typed file name starts with `.`.
+#[derive(Debug, Clone, Default)]
+pub struct PathCompleter {
+    root: Option<PathBuf>,
+    hidden: bool,
+    directories_only: bool,
+    matching: Matching,
+}
+
+impl PathCompleter {
+    /// Create a new `PathCompleter` that completes paths relative to the
+    /// current directory
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Set the directory that relative paths are completed from
+    pub fn root<P: Into<PathBuf>>(mut self, root: P) -> Self {
+        self.root = Some(root.into());
+        self
+    }
+    /// Set whether hidden files are always completed
+    ///
+    /// This is disabled by default.
+    pub fn hidden(mut self, hidden: bool) -> Self {
+        self.hidden = hidden;
+        self
+    }
+    /// Set whether only directories are completed
+    ///
+    /// This is disabled by default.
+    pub fn directories_only(mut self, directories_only: bool) -> Self {
+        self.directories_only = directories_only;
+        self
+    }
+    /// Set the way the typed path is matched against the file names
+    pub fn matching(mut self, matching: Matching) -> Self {
+        self.matching = matching;
+        self
+    }
+    /// Get the directory to list for the typed directory part of a path
+    fn directory(&self, typed_dir: &str) -> PathBuf {
+        let dir = if typed_dir.starts_with("~/") {
+            match home_dir() {
+                Some(home) => format!("{}{}", home, &typed_dir[1..]),
+                None => typed_dir.to_string(),
+            }
+        } else {
+            typed_dir.to_string()
+        };
+        let dir = PathBuf::from(if dir.is_empty() { "." } else { &dir });
+        match &self.root {
+            Some(root) if dir.is_relative() => root.join(dir),
+            _ => dir,
+        }
+    }
+}
+
+impl Completer for PathCompleter {
+    fn complete(&mut self, input: &str, cursor: usize) -> Completion {
+        let case = self.matching.default_case();
+        self.complete_with_case(input, cursor, case)
+    }
+    fn complete_with_case(&mut self, input: &str, cursor: usize, case: CaseMatching) -> Completion {
+        let start = word_start(input, cursor);
+        let typed = &input[start..cursor];
+        // The typed directory keeps its trailing separator
+        let split = typed.rfind(['/', '\\']).map(|i| i + 1).unwrap_or(0);
+        let (typed_dir, typed_name) = typed.split_at(split);
+        let show_hidden = self.hidden || typed_name.starts_with('.');
+        let entries = match fs::read_dir(self.directory(typed_dir)) {
+            Ok(entries) => entries,
+            Err(_) => return Completion::default(),
+        };
+        let mut candidates: Vec<Candidate> = entries
+            .filter_map(Result::ok)
+            .filter_map(|entry| {
+                let name = entry.file_name().into_string().ok()?;
+                if name.starts_with('.') && !show_hidden {
+                    return None;
+                }
+                // Follow symlinks so that links to directories count as directories
+                let is_dir = fs::metadata(entry.path())
+                    .map(|meta| meta.is_dir())
+                    .unwrap_or(false);
+                if self.directories_only && !is_dir {
+                    return None;
+                }
+                let slash = if is_dir { "/" } else { "" };
+                Some(Candidate::new(format!("{}{}{}", typed_dir, name, slash)))
+            })
+            .collect();
+        candidates.sort_by(|a, b| a.replacement.cmp(&b.replacement));
+        Completion::from_candidates_with_case(input, cursor, candidates, self.matching, case)
+    }
+}
@@ -0,0 +1,87 @@
+use std::{
+    marker::PhantomData,
+    sync::{Arc, Mutex},
+};
+
+use crate::{Completion, RegistryHandle};
+
+/// A reusable extension for a console
+///
+/// A plugin is added with
+/// [`ConsoleBuilder::plugin`](crate::ConsoleBuilder::plugin), which registers
+/// its commands with a [`CommandRegistry`](crate::CommandRegistry). Every
+/// other method has a default that does nothing, so a plugin only implements
+/// the ones it needs. When there are several plugins, they are consulted in
+/// the order they were added.
+pub trait ConsolePlugin<T>: Send {
+    /// Get the name of the plugin
+    fn name(&self) -> &str;
+    /// Register the plugin's commands
+    fn register(&mut self, commands: &RegistryHandle<T>) {
+        let _ = commands;
+    }
+    /// Complete the word at the cursor
+    ///
+    /// If this returns `None`, the input is completed as usual.
+    fn complete(&mut self, input: &str, cursor: usize) -> Option<Completion> {
+        let _ = (input, cursor);
+        None
+    }
+    /// Render the input line with highlighting
+    ///
+    /// The rendering must show the same text as the input, with only ANSI
+    /// escape codes added.
+    fn highlight(&mut self, input: &str) -> Option<String> {
+        let _ = input;
+        None
+    }
+    /// Filter some output before it is printed
+    ///
+    /// The output can be rewritten, or dropped by returning `None`.
+    fn filter_output(&mut self, output: String) -> Option<String> {
+        Some(output)
+    }
+}
+
+/// A registered plugin whose commands are no longer needed by the console
+pub(crate) trait Extension: Send {
+    fn name(&self) -> &str;
+    fn complete(&mut self, input: &str, cursor: usize) -> Option<Completion>;
+    fn highlight(&mut self, input: &str) -> Option<String>;
+    fn filter_output(&mut self, output: String) -> Option<String>;
+}
+
+pub(crate) struct Registered<P, T> {
+    pub plugin: P,
+    pub commands: PhantomData<fn() -> T>,
+}
+
+impl<P, T> Extension for Registered<P, T>
+where
+    P: ConsolePlugin<T>,
+{
+    fn name(&self) -> &str {
+        self.plugin.name()
+    }
+    fn complete(&mut self, input: &str, cursor: usize) -> Option<Completion> {
+        self.plugin.complete(input, cursor)
+    }
+    fn highlight(&mut self, input: &str) -> Option<String> {
+        self.plugin.highlight(input)
+    }
+    fn filter_output(&mut self, output: String) -> Option<String> {
+        self.plugin.filter_output(output)
+    }
+}
+
+/// The plugins of a console
+pub(crate) type Plugins = Arc<Mutex<Vec<Box<dyn Extension>>>>;
+
+/// Run some output through the filters of the plugins
+pub(crate) fn filter_output(plugins: &Plugins, output: String) -> Option<String> {
+    plugins
+        .lock()
+        .unwrap()
+        .iter_mut()
+        .try_fold(output, |output, plugin| plugin.filter_output(output))
+}
@@ -0,0 +1,150 @@
+use std::sync::{
+    mpsc::{self, Sender},
+    Arc, Mutex,
+};
+
+use crate::{
+    editor::Event,
+    line::{terminal_width, Line},
+    spawn::Spawner,
+    CommandProcessor, Context, Envelope, MessageSender,
+};
+
+/// An input to be parsed and processed by a worker
+pub(crate) struct Job {
+    pub input: String,
+    pub context: Context,
+    /// The envelope to deliver the message in
+    pub envelope: Envelope<()>,
+}
+
+/// What the workers need to deliver their results
+pub(crate) struct WorkerLink<M> {
+    pub send: MessageSender<M>,
+    pub line: Arc<Mutex<Line>>,
+    pub events: Sender<Event>,
+    pub render_errors: bool,
+    pub spawner: Spawner,
+}
+
+impl<M> Clone for WorkerLink<M> {
+    fn clone(&self) -> Self {
+        WorkerLink {
+            send: self.send.clone(),
+            line: Arc::clone(&self.line),
+            events: self.events.clone(),
+            render_errors: self.render_errors,
+            spawner: self.spawner.clone(),
+        }
+    }
+}
+
+impl<M> WorkerLink<M> {
+    /// Print the error for a parsed input if it should be shown
+    ///
+    /// Returns whether there was an error
+    fn render_error<P: CommandProcessor>(&self, processor: &mut P, parsed: &P::Parsed) -> bool {
+        if !self.render_errors {
+            return false;
+        }
+        match processor.render_error(parsed, terminal_width()) {
+            Some(error) => {
+                let line = self.line.lock().unwrap();
+                line.clear();
+                print!("{}", error);
+                line.draw();
+                true
+            }
+            None => false,
+        }
+    }
+    /// Send the message from processing an input, or close the console if
+    /// there is none
+    fn deliver(&self, message: Option<M>, envelope: Envelope<()>) {
+        match message {
+            Some(message) => {
+                self.send.send(envelope.map(|()| message));
+            }
+            None => {
+                let _ = self.events.send(Event::Quit);
+            }
+        }
+    }
+}
+
+/// A function that starts the worker threads and returns a sender for their
+/// jobs
+pub(crate) type PoolStarter<M> = Box<dyn FnOnce(WorkerLink<M>) -> Sender<Job> + Send>;
+
+/// Create a function that starts the given number of workers, each with its
+/// own processor
+pub(crate) fn pool_starter<M, B, F, P>(
+    workers: usize,
+    builder: Arc<B>,
+    process: Arc<F>,
+) -> PoolStarter<M>
+where
+    M: Send + 'static,
+    B: Fn() -> P + Send + Sync + 'static,
+    P: CommandProcessor,
+    F: Fn(P::Parsed) -> Option<M> + Send + Sync + 'static,
+{
+    Box::new(move |link| {
+        let (send, recv) = mpsc::channel::<Job>();
+        let recv = Arc::new(Mutex::new(recv));
+        for _ in 0..workers.max(1) {
+            let builder = Arc::clone(&builder);
+            let process = Arc::clone(&process);
+            let recv = Arc::clone(&recv);
+            let link = link.clone();
+            link.spawner.clone().spawn(move || {
+                let mut processor = builder();
+                loop {
+                    // The workers stop once the editor drops the job sender
+                    let job = match recv.lock().unwrap().recv() {
+                        Ok(job) => job,
+                        Err(_) => return,
+                    };
+                    let parsed = processor.parse_with_context(&job.input, &job.context);
+                    if !link.render_error(&mut processor, &parsed) {
+                        link.deliver(process(parsed), job.envelope);
+                    }
+                }
+            });
+        }
+        send
+    })
+}
+
+/// Create a function that starts a worker that parses inputs and spawns
+/// their handlers onto a Tokio runtime
+#[cfg(feature = "tokio")]
+pub(crate) fn async_pool_starter<M, B, F, P, Fut>(
+    builder: Arc<B>,
+    handler: F,
+    runtime: tokio::runtime::Handle,
+) -> PoolStarter<M>
+where
+    M: Send + 'static,
+    B: Fn() -> P + Send + Sync + 'static,
+    P: CommandProcessor,
+    F: Fn(P::Parsed) -> Fut + Send + Sync + 'static,
+    Fut: std::future::Future<Output = Option<M>> + Send + 'static,
+{
+    Box::new(move |link| {
+        let (send, recv) = mpsc::channel::<Job>();
+        link.spawner.clone().spawn(move || {
+            let mut processor = builder();
+            for job in recv {
+                let parsed = processor.parse_with_context(&job.input, &job.context);
+                if link.render_error(&mut processor, &parsed) {
+                    continue;
+                }
+                let handled = handler(parsed);
+                let link = link.clone();
+                runtime.spawn(async move { link.deliver(handled.await, job.envelope) });
+            }
+        });
+        send
+    })
+}
@@ -0,0 +1,352 @@
+use std::iter;
+
+use clap::{App, ArgMatches, ArgSettings, ErrorKind, Result as ClapResult};
+use colored::Colorize;
+
+use crate::{
+    strip_ansi, word_start, wrap_line, Candidate, Completion, Context, HelpEntry, Hint, HookAction,
+    Matching, SessionId,
+};
+
+/// Behavior for processing commands
+pub trait CommandProcessor: Sized {
+    /// The type output after the command has been parsed
+    type Parsed;
+    /// Inspect, rewrite, or reject an input before it is parsed
+    ///
+    /// The console calls this before parsing every input. If the input is
+    /// rejected, it is not parsed. By default, the input is left as it is.
+    fn before_parse(&mut self, input: &mut String) -> HookAction {
+        let _ = input;
+        HookAction::Continue
+    }
+    /// Parse an input
+    fn parse(&mut self, input: &str) -> Self::Parsed;
+    /// Parse an input with knowledge of the [`Context`] it was entered in
+    ///
+    /// By default, this ignores the context and calls [`CommandProcessor::parse`].
+    fn parse_with_context(&mut self, input: &str, context: &Context) -> Self::Parsed {
+        let _ = context;
+        self.parse(input)
+    }
+    /// Complete the word at the cursor
+    ///
+    /// By default, nothing is completed.
+    fn complete(&mut self, input: &str, cursor: usize) -> Completion {
+        let _ = (input, cursor);
+        Completion::default()
+    }
+    /// Get help for what can come next in the input
+    ///
+    /// This is shown when `?` is typed at the end of the input if inline help
+    /// is enabled. By default, it lists the completions of the last word.
+    fn help(&mut self, input: &str) -> Vec<HelpEntry> {
+        self.complete(input, input.len())
+            .candidates
+            .into_iter()
+            .map(HelpEntry::from)
+            .collect()
+    }
+    /// Get a hint about the expected arguments
+    ///
+    /// This is shown below the input line while typing if hints are enabled.
+    /// By default, there is no hint.
+    fn hint(&mut self, input: &str, cursor: usize) -> Option<Hint> {
+        let _ = (input, cursor);
+        None
+    }
+    /// Render an error message if parsing failed
+    ///
+    /// The message should fit in the given width. If
+    /// [error rendering](crate::ConsoleBuilder::render_errors) is enabled,
+    /// the console prints the rendered message itself instead of passing
+    /// the parsed value to the process function. By default, no error is
+    /// rendered.
+    fn render_error(&mut self, parsed: &Self::Parsed, width: usize) -> Option<String> {
+        let _ = (parsed, width);
+        None
+    }
+    /// Get the names of the known commands
+    ///
+    /// These are used to suggest a command when an unrecognized one is entered.
+    /// By default, there are no known commands.
+    fn commands(&mut self) -> Vec<String> {
+        Vec::new()
+    }
+    /// Check if parsing failed because the command was not recognized
+    ///
+    /// By default, this is true if there are known [`commands`](CommandProcessor::commands)
+    /// and the first word of the input is not one of them.
+    fn unrecognized(&mut self, input: &str, parsed: &Self::Parsed) -> bool {
+        let _ = parsed;
+        let commands = self.commands();
+        match input.split_whitespace().next() {
+            Some(command) => !commands.is_empty() && !commands.iter().any(|c| c == command),
+            None => false,
+        }
+    }
+}
+
+impl<F, R> CommandProcessor for F
+where
+    F: Fn(&str) -> R,
+{
+    type Parsed = R;
+    fn parse(&mut self, input: &str) -> Self::Parsed {
+        self(input)
+    }
+}
+
+impl<'a, 'b> CommandProcessor for App<'a, 'b> {
+    type Parsed = ClapResult<ArgMatches<'a>>;
+    fn parse(&mut self, input: &str) -> Self::Parsed {
+        self.get_matches_from_safe_borrow(
+            iter::once(env!("CARGO_PKG_NAME")).chain(input.split_whitespace()),
+        )
+    }
+    fn parse_with_context(&mut self, input: &str, context: &Context) -> Self::Parsed {
+        let args = context
+            .tokenizer
+            .split(input)
+            .map_err(|e| clap::Error::with_description(&e.to_string(), ErrorKind::InvalidValue))?;
+        self.get_matches_from_safe_borrow(
+            iter::once(env!("CARGO_PKG_NAME").to_string()).chain(args),
+        )
+    }
+    fn complete(&mut self, input: &str, cursor: usize) -> Completion {
+        let start = word_start(input, cursor);
+        let app = current_subcommand(self, &input[..start]);
+        let candidates: Vec<Candidate> = if input[start..cursor].starts_with('-') {
+            let flags = app.p.flags.iter().map(|flag| (flag.s.long, flag.b.help));
+            let opts = app.p.opts.iter().map(|opt| (opt.s.long, opt.b.help));
+            flags
+                .chain(opts)
+                .filter_map(|(long, help)| {
+                    let candidate = Candidate::new(format!("--{}", long?));
+                    Some(match help {
+                        Some(help) => candidate.description(help),
+                        None => candidate,
+                    })
+                })
+                .collect()
+        } else {
+            app.p
+                .subcommands
+                .iter()
+                .map(|sub| {
+                    let candidate = Candidate::new(sub.p.meta.name.clone());
+                    match sub.p.meta.about {
+                        Some(about) => candidate.description(about),
+                        None => candidate,
+                    }
+                })
+                .collect()
+        };
+        Completion::from_candidates(input, cursor, candidates, Matching::Prefix)
+    }
+    fn help(&mut self, input: &str) -> Vec<HelpEntry> {
+        let start = word_start(input, input.len());
+        let prefix = &input[start..];
+        let app = current_subcommand(self, &input[..start]);
+        let subcommands = app.p.subcommands.iter().map(|sub| {
+            let entry = HelpEntry::new(sub.p.meta.name.clone());
+            match sub.p.meta.about {
+                Some(about) => entry.description(about),
+                None => entry,
+            }
+        });
+        let positionals = app.p.positionals.values().map(|pos| {
+            let entry = HelpEntry::new(format!("<{}>", pos.b.name));
+            match pos.b.help {
+                Some(help) => entry.description(help),
+                None => entry,
+            }
+        });
+        let flags = app.p.flags.iter().map(|flag| {
+            let entry = HelpEntry::new(switch_name(flag.s.short, flag.s.long, flag.b.name));
+            match flag.b.help {
+                Some(help) => entry.description(help),
+                None => entry,
+            }
+        });
+        let opts = app.p.opts.iter().map(|opt| {
+            let entry = HelpEntry::new(format!(
+                "{} <{}>",
+                switch_name(opt.s.short, opt.s.long, opt.b.name),
+                opt.b.name
+            ));
+            match opt.b.help {
+                Some(help) => entry.description(help),
+                None => entry,
+            }
+        });
+        subcommands
+            .chain(positionals)
+            .chain(flags)
+            .chain(opts)
+            .filter(|entry| entry.name.starts_with(prefix))
+            .collect()
+    }
+    fn commands(&mut self) -> Vec<String> {
+        self.p
+            .subcommands
+            .iter()
+            .map(|sub| sub.p.meta.name.clone())
+            .collect()
+    }
+    fn unrecognized(&mut self, input: &str, parsed: &Self::Parsed) -> bool {
+        // Clap already makes its own suggestions in its errors
+        let _ = (input, parsed);
+        false
+    }
+    fn render_error(&mut self, parsed: &Self::Parsed, width: usize) -> Option<String> {
+        parsed
+            .as_ref()
+            .err()
+            .map(|error| render_clap_error(error, width))
+    }
+    fn hint(&mut self, input: &str, cursor: usize) -> Option<Hint> {
+        let start = word_start(input, cursor);
+        let (app, args) = split_subcommand(self, &input[..start]);
+        if app.p.positionals.is_empty() && app.p.opts.is_empty() {
+            return None;
+        }
+        // Find the positional argument under the cursor
+        let current = args.iter().filter(|arg| !arg.starts_with('-')).count();
+        let mut parts = Vec::new();
+        if !std::ptr::eq(app, &*self) {
+            parts.push(app.p.meta.name.clone());
+        }
+        let mut highlight = None;
+        for (i, pos) in app.p.positionals.values().enumerate() {
+            let multiple = pos.b.is_set(ArgSettings::Multiple);
+            if i == current || multiple && i < current {
+                highlight = Some(parts.len());
+            }
+            let (open, close) = if pos.b.is_set(ArgSettings::Required) {
+                ('<', '>')
+            } else {
+                ('[', ']')
+            };
+            let dots = if multiple { "..." } else { "" };
+            parts.push(format!("{}{}{}{}", open, pos.b.name, close, dots));
+        }
+        if !app.p.opts.is_empty() {
+            parts.push("[OPTIONS]".into());
+        }
+        let text = parts.join(" ");
+        let hint = Hint::new(text);
+        Some(match highlight {
+            Some(i) => {
+                let start = parts[..i].iter().map(|part| part.len() + 1).sum::<usize>();
+                hint.highlight(start..start + parts[i].len())
+            }
+            None => hint,
+        })
+    }
+}
+
+/// Render a clap error with color, wrapped to the given width
+///
+/// Help and version messages are left uncolored.
+fn render_clap_error(error: &clap::Error, width: usize) -> String {
+    let message = strip_ansi(&error.message);
+    if let ErrorKind::HelpDisplayed | ErrorKind::VersionDisplayed = error.kind {
+        return message;
+    }
+    let mut rendered = String::new();
+    for line in message.trim_end().lines() {
+        let indent = if line.starts_with("error:") {
+            "error: ".len()
+        } else {
+            line.len() - line.trim_start().len()
+        };
+        for (i, row) in wrap_line(line, width, indent).into_iter().enumerate() {
+            let row = if i == 0 && row.starts_with("error:") {
+                format!("{}{}", "error:".red().bold(), &row["error:".len()..])
+            } else if row == "USAGE:" {
+                row.yellow().bold().to_string()
+            } else if line.starts_with("For more information") {
+                row.dimmed().to_string()
+            } else {
+                row
+            };
+            rendered.push_str(&row);
+            rendered.push('\n');
+        }
+    }
+    rendered
+}
+
+/// Find the deepest subcommand named in the input
+fn current_subcommand<'x, 'a, 'b>(app: &'x App<'a, 'b>, input: &str) -> &'x App<'a, 'b> {
+    split_subcommand(app, input).0
+}
+
+/// Find the deepest subcommand named in the input along with the tokens
+/// that follow it
+fn split_subcommand<'x, 'a, 'b, 'i>(
+    app: &'x App<'a, 'b>,
+    input: &'i str,
+) -> (&'x App<'a, 'b>, Vec<&'i str>) {
+    let mut app = app;
+    let mut args = Vec::new();
+    for token in input.split_whitespace() {
+        if let Some(sub) = app
+            .p
+            .subcommands
+            .iter()
+            .find(|sub| sub.p.meta.name == token)
+        {
+            app = sub;
+            args.clear();
+        } else {
+            args.push(token);
+        }
+    }
+    (app, args)
+}
+
+/// Get the name of a flag or option as it would be typed
+fn switch_name(short: Option<char>, long: Option<&str>, name: &str) -> String {
+    match (short, long) {
+        (_, Some(long)) => format!("--{}", long),
+        (Some(short), None) => format!("-{}", short),
+        (None, None) => name.to_string(),
+    }
+}
+
+/// A [`CommandProcessor`] that wraps a function that also takes the [`Context`]
+///
+/// Created with [`with_context`]
+pub struct WithContext<F> {
+    f: F,
+    /// The session reported when the processor is used without a context
+    session: SessionId,
+}
+
+/// Create a [`CommandProcessor`] from a function that takes the [`Context`]
+/// as well as the input
+pub fn with_context<F, R>(f: F) -> WithContext<F>
+where
+    F: Fn(&str, &Context) -> R,
+{
+    WithContext {
+        f,
+        session: SessionId::next(),
+    }
+}
+
+impl<F, R> CommandProcessor for WithContext<F>
+where
+    F: Fn(&str, &Context) -> R,
+{
+    type Parsed = R;
+    fn parse(&mut self, input: &str) -> Self::Parsed {
+        let context = Context::new(self.session, None);
+        (self.f)(input, &context)
+    }
+    fn parse_with_context(&mut self, input: &str, context: &Context) -> Self::Parsed {
+        (self.f)(input, context)
+    }
+}
@@ -0,0 +1,108 @@
+use std::{
+    fmt::Display,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{line::terminal_width, Origin, OutputMode, Responder};
+
+/// The number of characters in a progress bar
+const BAR_WIDTH: usize = 20;
+
+/// The id of the next progress indicator
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+impl Responder {
+    /// Start showing the progress of a long-running command
+    ///
+    /// The progress is shown below the input line and updated in place until
+    /// the returned [`Progress`] is finished or dropped. In machine mode, only
+    /// the finishing output is printed.
+    pub fn progress<D: Display>(&self, label: D) -> Progress {
+        let progress = Progress {
+            responder: self.clone(),
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            label: label.to_string(),
+        };
+        progress.show(String::new());
+        progress
+    }
+}
+
+/// A progress indicator that is updated in place
+///
+/// `Progress` is created with [`Responder::progress`].
+pub struct Progress {
+    responder: Responder,
+    id: u64,
+    label: String,
+}
+
+impl Progress {
+    /// Show a status message next to the label
+    pub fn set_message<D: Display>(&self, message: D) {
+        self.show(message.to_string());
+    }
+    /// Show a progress bar for a fraction between 0 and 1
+    pub fn set_fraction(&self, fraction: f64) {
+        let fraction = fraction.clamp(0.0, 1.0);
+        let filled = (fraction * BAR_WIDTH as f64).round() as usize;
+        self.show(format!(
+            "[{}{}] {:>3}%",
+            "#".repeat(filled),
+            "-".repeat(BAR_WIDTH - filled),
+            (fraction * 100.0).round()
+        ));
+    }
+    /// Show a progress bar for some number of steps out of a total
+    pub fn set_steps(&self, done: u64, total: u64) {
+        let fraction = if total == 0 {
+            1.0
+        } else {
+            done as f64 / total as f64
+        };
+        self.set_fraction(fraction);
+    }
+    /// Stop showing the progress and print some final output
+    pub fn finish<D: Display>(self, output: D) {
+        self.hide();
+        self.responder.print(output);
+    }
+    fn show(&self, status: String) {
+        self.responder.outputs.add(&Origin::Terminal);
+        if self.responder.mode == OutputMode::Machine {
+            return;
+        }
+        let text = if status.is_empty() {
+            self.label.clone()
+        } else {
+            format!("{} {}", self.label, status)
+        };
+        // Keep the progress on one row so it can be redrawn in place
+        let text = text
+            .graphemes(true)
+            .take(terminal_width().saturating_sub(1))
+            .collect();
+        let mut line = self.responder.line.lock().unwrap();
+        match line.progress.iter_mut().find(|(id, _)| *id == self.id) {
+            Some((_, shown)) => *shown = text,
+            None => line.progress.push((self.id, text)),
+        }
+        line.redraw();
+    }
+    fn hide(&self) {
+        let mut line = self.responder.line.lock().unwrap();
+        let count = line.progress.len();
+        line.progress.retain(|(id, _)| *id != self.id);
+        if line.progress.len() != count {
+            line.redraw();
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        self.hide();
+    }
+}
@@ -0,0 +1,59 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    mpsc::Sender,
+    Arc,
+};
+
+use crate::Envelope;
+
+/// Counts of the messages waiting to be polled
+#[derive(Default)]
+pub(crate) struct QueueStats {
+    queued: AtomicUsize,
+    max: AtomicUsize,
+}
+
+impl QueueStats {
+    /// Get the number of messages waiting to be polled
+    pub fn queued(&self) -> usize {
+        self.queued.load(Ordering::Relaxed)
+    }
+    /// Get the most messages that have been waiting at once
+    pub fn max(&self) -> usize {
+        self.max.load(Ordering::Relaxed)
+    }
+    /// Record that a message was polled
+    pub fn polled(&self) {
+        self.queued.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// A sender of messages to the app that keeps count of the queued messages
+pub(crate) struct MessageSender<M> {
+    send: Sender<Envelope<M>>,
+    stats: Arc<QueueStats>,
+}
+
+impl<M> MessageSender<M> {
+    pub fn new(send: Sender<Envelope<M>>, stats: Arc<QueueStats>) -> Self {
+        MessageSender { send, stats }
+    }
+    /// Queue a message for the app
+    pub fn send(&self, envelope: Envelope<M>) {
+        // Count the message first so that polling it never goes below zero
+        let queued = self.stats.queued.fetch_add(1, Ordering::Relaxed) + 1;
+        self.stats.max.fetch_max(queued, Ordering::Relaxed);
+        if self.send.send(envelope).is_err() {
+            self.stats.polled();
+        }
+    }
+}
+
+impl<M> Clone for MessageSender<M> {
+    fn clone(&self) -> Self {
+        MessageSender {
+            send: self.send.clone(),
+            stats: Arc::clone(&self.stats),
+        }
+    }
+}
@@ -0,0 +1,185 @@
+use std::{
+    error::Error,
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use crate::{word_start, Candidate, CommandProcessor, Completion, Context, Matching};
+
+/// A command registered with a [`CommandRegistry`]
+struct RegisteredCommand<T> {
+    name: String,
+    help: Option<String>,
+    handler: Handler<T>,
+}
+
+type Handler<T> = Box<dyn Fn(&[String]) -> T + Send>;
+type Commands<T> = Arc<Mutex<Vec<RegisteredCommand<T>>>>;
+
+/// A [`CommandProcessor`] whose commands can be added and removed at runtime
+///
+/// Commands are registered through a [`RegistryHandle`], which can be cloned
+/// and sent to other threads, so plugins that are loaded after the console
+/// starts can contribute their own commands. Each command has a handler that
+/// turns the command's arguments into the parsed value.
+pub struct CommandRegistry<T> {
+    commands: Commands<T>,
+}
+
+impl<T> Default for CommandRegistry<T> {
+    fn default() -> Self {
+        CommandRegistry {
+            commands: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+}
+
+impl<T> CommandRegistry<T> {
+    /// Create a new `CommandRegistry` with no commands
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Get a handle for adding and removing commands
+    pub fn handle(&self) -> RegistryHandle<T> {
+        RegistryHandle {
+            commands: Arc::clone(&self.commands),
+        }
+    }
+    /// Run the handler of the command named in the given arguments
+    fn dispatch(&self, args: &[String]) -> Result<T, RegistryError> {
+        let (name, args) = args.split_first().ok_or(RegistryError::Empty)?;
+        let commands = self.commands.lock().unwrap();
+        let command = commands
+            .iter()
+            .find(|command| command.name == *name)
+            .ok_or_else(|| RegistryError::UnknownCommand(name.clone()))?;
+        Ok((command.handler)(args))
+    }
+}
+
+/// A handle for adding and removing the commands of a [`CommandRegistry`]
+pub struct RegistryHandle<T> {
+    commands: Commands<T>,
+}
+
+impl<T> Clone for RegistryHandle<T> {
+    fn clone(&self) -> Self {
+        RegistryHandle {
+            commands: Arc::clone(&self.commands),
+        }
+    }
+}
+
+impl<T> RegistryHandle<T> {
+    /// Register a command
+    ///
+    /// The handler is called with the arguments that follow the command's
+    /// name. A command that is already registered with the same name is
+    /// replaced.
+    pub fn register<S, F>(&self, name: S, help: Option<&str>, handler: F)
+    where
+        S: Into<String>,
+        F: Fn(&[String]) -> T + Send + 'static,
+    {
+        let command = RegisteredCommand {
+            name: name.into(),
+            help: help.map(Into::into),
+            handler: Box::new(handler),
+        };
+        let mut commands = self.commands.lock().unwrap();
+        match commands.iter_mut().find(|c| c.name == command.name) {
+            Some(existing) => *existing = command,
+            None => commands.push(command),
+        }
+    }
+    /// Unregister a command
+    ///
+    /// Returns `false` if no command with the given name was registered
+    pub fn unregister(&self, name: &str) -> bool {
+        let mut commands = self.commands.lock().unwrap();
+        let len = commands.len();
+        commands.retain(|command| command.name != name);
+        commands.len() != len
+    }
+    /// Check if a command is registered
+    pub fn contains(&self, name: &str) -> bool {
+        self.commands
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|command| command.name == name)
+    }
+    /// Get the names of the registered commands
+    pub fn names(&self) -> Vec<String> {
+        self.commands
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|command| command.name.clone())
+            .collect()
+    }
+}
+
+/// An error from parsing an input with a [`CommandRegistry`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegistryError {
+    /// The input was empty
+    Empty,
+    /// No command with the contained name is registered
+    UnknownCommand(String),
+    /// The input could not be split into arguments
+    InvalidInput(String),
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RegistryError::Empty => write!(f, "no command entered"),
+            RegistryError::UnknownCommand(name) => write!(f, "unknown command: {}", name),
+            RegistryError::InvalidInput(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl Error for RegistryError {}
+
+impl<T> CommandProcessor for CommandRegistry<T> {
+    type Parsed = Result<T, RegistryError>;
+    fn parse(&mut self, input: &str) -> Self::Parsed {
+        let args: Vec<String> = input.split_whitespace().map(Into::into).collect();
+        self.dispatch(&args)
+    }
+    fn parse_with_context(&mut self, input: &str, context: &Context) -> Self::Parsed {
+        let args = context
+            .tokenizer
+            .split(input)
+            .map_err(|e| RegistryError::InvalidInput(e.to_string()))?;
+        self.dispatch(&args)
+    }
+    fn complete(&mut self, input: &str, cursor: usize) -> Completion {
+        // Only the command name is completed
+        if !input[..word_start(input, cursor)].trim().is_empty() {
+            return Completion::default();
+        }
+        let candidates: Vec<Candidate> = self
+            .commands
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|command| {
+                let candidate = Candidate::new(command.name.clone());
+                match &command.help {
+                    Some(help) => candidate.description(help.clone()),
+                    None => candidate,
+                }
+            })
+            .collect();
+        Completion::from_candidates(input, cursor, candidates, Matching::Prefix)
+    }
+    fn commands(&mut self) -> Vec<String> {
+        self.handle().names()
+    }
+    fn unrecognized(&mut self, _input: &str, parsed: &Self::Parsed) -> bool {
+        matches!(parsed, Err(RegistryError::UnknownCommand(_)))
+    }
+}
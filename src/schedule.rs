@@ -0,0 +1,217 @@
+use std::{
+    convert::TryFrom,
+    fmt,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// The shortest interval or delay that a command can be scheduled with
+pub(crate) const MIN_INTERVAL: Duration = Duration::from_millis(1);
+
+/// The longest duration that can be parsed
+const MAX_DURATION: Duration = Duration::from_secs(366 * 86400);
+
+/// A unique identifier for a scheduled job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct JobId(pub u64);
+
+impl fmt::Display for JobId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A command that is scheduled to be run by the console
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScheduledJob {
+    /// The job's id, used to cancel it
+    pub id: JobId,
+    /// The command that is run
+    pub command: String,
+    /// When the command is next run
+    pub next: SystemTime,
+    /// The interval at which the command is run again, if it repeats
+    pub every: Option<Duration>,
+}
+
+/// The commands scheduled to be run by the console
+#[derive(Default)]
+pub(crate) struct Scheduler {
+    jobs: Vec<ScheduledJob>,
+    next_id: u64,
+}
+
+impl Scheduler {
+    /// Schedule a command, returning the job's id
+    pub fn schedule(
+        &mut self,
+        command: String,
+        next: SystemTime,
+        every: Option<Duration>,
+    ) -> JobId {
+        self.next_id += 1;
+        let id = JobId(self.next_id);
+        let every = every.map(|every| every.max(MIN_INTERVAL));
+        self.jobs.push(ScheduledJob {
+            id,
+            command,
+            next,
+            every,
+        });
+        id
+    }
+    /// Cancel a job
+    ///
+    /// Returns `false` if there was no job with the given id
+    pub fn cancel(&mut self, id: JobId) -> bool {
+        let len = self.jobs.len();
+        self.jobs.retain(|job| job.id != id);
+        self.jobs.len() != len
+    }
+    /// Get the scheduled jobs, soonest first
+    pub fn jobs(&self) -> Vec<ScheduledJob> {
+        let mut jobs = self.jobs.clone();
+        jobs.sort_by_key(|job| job.next);
+        jobs
+    }
+    /// Get the time at which the next job is due
+    pub fn next_due(&self) -> Option<SystemTime> {
+        self.jobs.iter().map(|job| job.next).min()
+    }
+    /// Take the jobs that are due, rescheduling the ones that repeat
+    pub fn take_due(&mut self, now: SystemTime) -> Vec<(JobId, String)> {
+        let mut due = Vec::new();
+        self.jobs.retain_mut(|job| {
+            if job.next > now {
+                return true;
+            }
+            due.push((job.id, job.command.clone()));
+            match job.every {
+                Some(every) => {
+                    // Skip runs that were missed
+                    let behind = now.duration_since(job.next).unwrap_or_default();
+                    let runs = behind.as_nanos() / every.as_nanos() + 1;
+                    let skip = every.as_nanos().saturating_mul(runs);
+                    job.next += Duration::from_nanos(u64::try_from(skip).unwrap_or(u64::MAX));
+                    true
+                }
+                None => false,
+            }
+        });
+        due
+    }
+}
+
+/// Parse a duration like `90`, `30s`, `5m`, `2h`, or `1h30m`
+///
+/// A number without a unit is a number of seconds. Durations shorter than a
+/// millisecond or longer than a year are rejected.
+pub(crate) fn parse_duration(text: &str) -> Option<Duration> {
+    let seconds = match text.parse::<f64>() {
+        Ok(seconds) => seconds,
+        Err(_) => parse_units(text)?,
+    };
+    Duration::try_from_secs_f64(seconds)
+        .ok()
+        .filter(|duration| (MIN_INTERVAL..=MAX_DURATION).contains(duration))
+}
+
+/// Parse the number of seconds in a duration with units, like `1h30m`
+fn parse_units(text: &str) -> Option<f64> {
+    let mut total = 0.0;
+    let mut number = String::new();
+    for c in text.chars() {
+        if c.is_ascii_digit() || c == '.' {
+            number.push(c);
+            continue;
+        }
+        let unit = match c {
+            's' => 1.0,
+            'm' => 60.0,
+            'h' => 3600.0,
+            'd' => 86400.0,
+            _ => return None,
+        };
+        total += number.parse::<f64>().ok()? * unit;
+        number.clear();
+    }
+    number.is_empty().then_some(total)
+}
+
+/// Parse a UTC time of day like `14:30` or `14:30:15`, getting the next time
+/// after `now` at which it occurs
+pub(crate) fn parse_time_of_day(text: &str, now: SystemTime) -> Option<SystemTime> {
+    let mut parts = text.split(':');
+    let hours: u64 = parts.next()?.parse().ok().filter(|&h| h < 24)?;
+    let minutes: u64 = parts.next()?.parse().ok().filter(|&m| m < 60)?;
+    let seconds: u64 = match parts.next() {
+        Some(seconds) => seconds.parse().ok().filter(|&s| s < 60)?,
+        None => 0,
+    };
+    if parts.next().is_some() {
+        return None;
+    }
+    let since_epoch = now.duration_since(UNIX_EPOCH).ok()?.as_secs();
+    let midnight = since_epoch - since_epoch % 86400;
+    let mut time = midnight + hours * 3600 + minutes * 60 + seconds;
+    if time <= since_epoch {
+        time += 86400;
+    }
+    Some(UNIX_EPOCH + Duration::from_secs(time))
+}
+
+/// Format a UTC time of day as `HH:MM:SS`
+pub(crate) fn format_time_of_day(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+        % 86400;
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration("90"), Some(Duration::from_secs(90)));
+        assert_eq!(parse_duration("1.5"), Some(Duration::from_millis(1500)));
+        assert_eq!(parse_duration("30s"), Some(Duration::from_secs(30)));
+        assert_eq!(parse_duration("1h30m"), Some(Duration::from_secs(5400)));
+        assert_eq!(parse_duration("2d"), Some(Duration::from_secs(172_800)));
+    }
+
+    #[test]
+    fn invalid_durations() {
+        for text in [
+            "", "0", "-5", "5x", "1h30", "NaN", "inf", "0s", "1e30", "1e30s",
+        ] {
+            assert_eq!(parse_duration(text), None, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn duration_limits() {
+        assert_eq!(parse_duration("0.0000000001"), None);
+        assert_eq!(parse_duration("0.001"), Some(MIN_INTERVAL));
+        assert_eq!(parse_duration("366d"), Some(MAX_DURATION));
+        assert_eq!(parse_duration("367d"), None);
+    }
+
+    #[test]
+    fn missed_runs_are_skipped() {
+        let mut scheduler = Scheduler::default();
+        let start = UNIX_EPOCH + Duration::from_secs(1000);
+        scheduler.schedule("x".into(), start, Some(Duration::ZERO));
+        let now = start + Duration::from_secs(3600);
+        assert_eq!(scheduler.take_due(now).len(), 1);
+        let next = scheduler.next_due().unwrap();
+        assert!(next > now && next <= now + MIN_INTERVAL);
+    }
+}
@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+
+use crate::{current_prompt, Mode, Origin};
+
+/// Where a console variable, alias, or mode applies
+///
+/// Each [`Origin`] is its own session, so two people connected to the same
+/// console can set the same variable without changing each other's value.
+/// Session values are looked up before global ones.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Scope {
+    /// Every session
+    Global,
+    /// Only the input from the given origin
+    Session(Origin),
+}
+
+/// Something kept for every session together and for each session on its own
+pub(crate) struct Scoped<T> {
+    pub global: T,
+    pub sessions: HashMap<Origin, T>,
+}
+
+impl<T> Scoped<T> {
+    /// Create a `Scoped` with the given global value and no session values
+    pub fn new(global: T) -> Self {
+        Scoped {
+            global,
+            sessions: HashMap::new(),
+        }
+    }
+    /// Get the value for a scope, if it has one
+    pub fn get(&self, scope: &Scope) -> Option<&T> {
+        match scope {
+            Scope::Global => Some(&self.global),
+            Scope::Session(origin) => self.sessions.get(origin),
+        }
+    }
+}
+
+impl<T: Default> Scoped<T> {
+    /// Get the value for a scope, creating it if it does not have one
+    pub fn get_mut(&mut self, scope: &Scope) -> &mut T {
+        match scope {
+            Scope::Global => &mut self.global,
+            Scope::Session(origin) => self.sessions.entry(origin.clone()).or_default(),
+        }
+    }
+}
+
+impl<T: Default> Default for Scoped<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl Scoped<HashMap<String, String>> {
+    /// Look up a name for an origin, falling back to the global value
+    pub fn lookup(&self, origin: &Origin, name: &str) -> Option<&String> {
+        self.sessions
+            .get(origin)
+            .and_then(|values| values.get(name))
+            .or_else(|| self.global.get(name))
+    }
+}
+
+impl<M> Scoped<Vec<Mode<M>>> {
+    /// Get the mode stack that handles the input from an origin
+    ///
+    /// This is the origin's own stack if it has entered a mode, and the
+    /// global one otherwise.
+    pub fn stack_mut(&mut self, origin: &Origin) -> &mut Vec<Mode<M>> {
+        match self.sessions.get_mut(origin) {
+            Some(modes) if !modes.is_empty() => modes,
+            _ => &mut self.global,
+        }
+    }
+    /// Get the prompt for the terminal's mode stack
+    pub fn prompt(&self, base: &str) -> String {
+        let modes = match self.sessions.get(&Origin::Terminal) {
+            Some(modes) if !modes.is_empty() => modes,
+            _ => &self.global,
+        };
+        current_prompt(base, modes)
+    }
+}
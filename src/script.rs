@@ -0,0 +1,83 @@
+use std::{
+    mem,
+    sync::{Arc, Mutex},
+};
+
+use rhai::{Dynamic, Engine, Scope, AST};
+
+/// Commands defined as functions in Rhai scripts
+pub(crate) struct Scripts {
+    engine: Engine,
+    ast: AST,
+    /// The commands that scripts have asked the console to run
+    queued: Arc<Mutex<Vec<String>>>,
+}
+
+impl Default for Scripts {
+    fn default() -> Self {
+        let mut engine = Engine::new();
+        engine.on_print(|text| println!("{}", text));
+        let queued = Arc::new(Mutex::new(Vec::new()));
+        let run_queue = Arc::clone(&queued);
+        engine.register_fn("run", move |command: &str| {
+            run_queue.lock().unwrap().push(command.into())
+        });
+        Scripts {
+            engine,
+            ast: AST::empty(),
+            queued,
+        }
+    }
+}
+
+impl Scripts {
+    /// Create `Scripts` with the functions in the given sources
+    ///
+    /// Errors in the sources are printed.
+    pub fn with_sources(sources: &[String]) -> Self {
+        let mut scripts = Scripts::default();
+        for source in sources {
+            if let Err(e) = scripts.define(source) {
+                println!("script error: {}", e);
+            }
+        }
+        scripts
+    }
+    /// Define the functions in some source, returning their names
+    ///
+    /// A function replaces any existing one with the same name and number of
+    /// parameters.
+    pub fn define(&mut self, source: &str) -> Result<Vec<String>, String> {
+        let ast = self
+            .engine
+            .compile(source)
+            .map_err(|e| e.to_string())?
+            .clone_functions_only();
+        let names = ast.iter_functions().map(|f| f.name.into()).collect();
+        self.ast.combine(ast);
+        Ok(names)
+    }
+    /// Check if a function with the given name is defined
+    pub fn contains(&self, name: &str) -> bool {
+        self.ast.iter_functions().any(|f| f.name == name)
+    }
+    /// Call a function with the given arguments
+    ///
+    /// Returns the commands that the function asked to run
+    pub fn call(&mut self, name: &str, args: Vec<String>) -> Result<Vec<String>, String> {
+        let args: Vec<Dynamic> = args.into_iter().map(Dynamic::from).collect();
+        let result = self
+            .engine
+            .call_fn::<Dynamic>(&mut Scope::new(), &self.ast, name, args);
+        let queued = mem::take(&mut *self.queued.lock().unwrap());
+        match result {
+            Ok(value) => {
+                if !value.is_unit() {
+                    println!("{}", value);
+                }
+                Ok(queued)
+            }
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
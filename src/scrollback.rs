@@ -0,0 +1,135 @@
+use std::collections::VecDeque;
+
+/// Which output is discarded when the scrollback is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Eviction {
+    /// Discard the oldest output to make room for new output
+    #[default]
+    Oldest,
+    /// Keep the oldest output and stop recording new output
+    Newest,
+}
+
+/// Limits on how much output the scrollback keeps
+///
+/// By default, the scrollback keeps the last 10,000 lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScrollbackLimit {
+    /// The maximum number of lines to keep
+    pub max_lines: Option<usize>,
+    /// The maximum number of bytes of text to keep
+    pub max_bytes: Option<usize>,
+    /// Which output is discarded when a limit is reached
+    pub eviction: Eviction,
+}
+
+impl Default for ScrollbackLimit {
+    fn default() -> Self {
+        ScrollbackLimit {
+            max_lines: Some(10_000),
+            max_bytes: None,
+            eviction: Eviction::default(),
+        }
+    }
+}
+
+impl ScrollbackLimit {
+    /// Create a `ScrollbackLimit` that keeps everything
+    pub fn unlimited() -> Self {
+        ScrollbackLimit {
+            max_lines: None,
+            max_bytes: None,
+            eviction: Eviction::default(),
+        }
+    }
+    /// Set the maximum number of lines to keep
+    pub fn lines(mut self, max_lines: usize) -> Self {
+        self.max_lines = Some(max_lines);
+        self
+    }
+    /// Set the maximum number of bytes of text to keep
+    pub fn bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+    /// Set which output is discarded when a limit is reached
+    pub fn eviction(mut self, eviction: Eviction) -> Self {
+        self.eviction = eviction;
+        self
+    }
+    /// Check if the given amount of output is over the limit
+    fn exceeded(&self, lines: usize, bytes: usize) -> bool {
+        self.max_lines.is_some_and(|max| lines > max)
+            || self.max_bytes.is_some_and(|max| bytes > max)
+    }
+}
+
+/// The output that has been printed, kept so that it can be viewed and
+/// searched again
+pub(crate) struct Scrollback {
+    lines: VecDeque<String>,
+    /// The total length of the lines
+    bytes: usize,
+    /// The number of lines that have ever been recorded
+    recorded: u64,
+    limit: ScrollbackLimit,
+}
+
+impl Scrollback {
+    pub fn new(limit: ScrollbackLimit) -> Self {
+        Scrollback {
+            lines: VecDeque::new(),
+            bytes: 0,
+            recorded: 0,
+            limit,
+        }
+    }
+    /// Record some output, discarding output if the limit is reached
+    pub fn push(&mut self, output: &str) {
+        for line in output.lines() {
+            match self.limit.eviction {
+                Eviction::Oldest => {
+                    self.bytes += line.len();
+                    self.lines.push_back(line.into());
+                    self.recorded += 1;
+                    while self.limit.exceeded(self.lines.len(), self.bytes) {
+                        match self.lines.pop_front() {
+                            Some(evicted) => self.bytes -= evicted.len(),
+                            None => break,
+                        }
+                    }
+                }
+                Eviction::Newest => {
+                    if self
+                        .limit
+                        .exceeded(self.lines.len() + 1, self.bytes + line.len())
+                    {
+                        return;
+                    }
+                    self.bytes += line.len();
+                    self.lines.push_back(line.into());
+                    self.recorded += 1;
+                }
+            }
+        }
+    }
+    /// Get a mark for the output recorded so far
+    pub fn mark(&self) -> u64 {
+        self.recorded
+    }
+    /// Get the lines recorded after a mark that are still kept, oldest first
+    pub fn since(&self, mark: u64) -> impl Iterator<Item = &str> {
+        let first = self.recorded - self.lines.len() as u64;
+        let skip = mark.saturating_sub(first) as usize;
+        self.lines.iter().skip(skip).map(String::as_str)
+    }
+    /// Get all recorded output, oldest first
+    pub fn text(&self) -> String {
+        let mut text = String::with_capacity(self.bytes + self.lines.len());
+        for line in &self.lines {
+            text.push_str(line);
+            text.push('\n');
+        }
+        text
+    }
+}
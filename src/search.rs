@@ -0,0 +1,107 @@
+use colored::Colorize;
+
+use crate::{fuzzy_match_case, CaseMatching};
+
+/// The state of the fuzzy history search overlay
+pub(crate) struct HistorySearch {
+    /// The input before the search started
+    pub original: String,
+    /// The prompt before the search started
+    pub prompt: String,
+    pub query: String,
+    /// Indices of matching history entries, best first
+    pub matches: Vec<(usize, Vec<usize>)>,
+    pub selected: usize,
+    case: CaseMatching,
+}
+
+impl HistorySearch {
+    pub fn new(
+        original: String,
+        prompt: String,
+        history: &[String],
+        case: CaseMatching,
+        skip: &dyn Fn(&str) -> bool,
+    ) -> Self {
+        let mut search = HistorySearch {
+            original,
+            prompt,
+            query: String::new(),
+            matches: Vec::new(),
+            selected: 0,
+            case,
+        };
+        search.update(history, skip);
+        search
+    }
+    /// Update the matches for the current query, leaving out the entries
+    /// that should be skipped
+    pub fn update(&mut self, history: &[String], skip: &dyn Fn(&str) -> bool) {
+        let mut scored: Vec<(i64, usize, Vec<usize>)> = Vec::new();
+        for (i, entry) in history.iter().enumerate().rev() {
+            if entry.is_empty()
+                || skip(entry)
+                || scored.iter().any(|&(_, j, _)| history[j] == *entry)
+            {
+                continue;
+            }
+            if let Some((score, indices)) = fuzzy_match_case(&self.query, entry, self.case) {
+                scored.push((score, i, indices));
+            }
+        }
+        // Sort by score, keeping more recent entries first among equals
+        scored.sort_by_key(|&(score, _, _)| -score);
+        self.matches = scored
+            .into_iter()
+            .map(|(_, i, indices)| (i, indices))
+            .collect();
+        self.selected = 0;
+    }
+    /// Select the next, worse match
+    pub fn next(&mut self) {
+        if self.selected + 1 < self.matches.len() {
+            self.selected += 1;
+        }
+    }
+    /// Select the previous, better match
+    pub fn prev(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+    /// Get the index of the selected history entry
+    pub fn selected_entry(&self) -> Option<usize> {
+        self.matches.get(self.selected).map(|(i, _)| *i)
+    }
+    /// Render the list of matches, best first, to fit in the given number of
+    /// rows, with a red `✗` after the entries that should be marked
+    pub fn render(&self, history: &[String], rows: usize, marked: &dyn Fn(&str) -> bool) -> String {
+        let first = self.selected.saturating_sub(rows.saturating_sub(1));
+        let mut lines: Vec<String> = self
+            .matches
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(rows)
+            .map(|(n, (i, indices))| {
+                let entry = &history[*i];
+                let mut rendered = String::new();
+                for (j, c) in entry.char_indices() {
+                    if indices.contains(&j) {
+                        rendered.push_str(&c.to_string().bold().to_string());
+                    } else {
+                        rendered.push(c);
+                    }
+                }
+                if marked(entry) {
+                    rendered.push_str(&format!(" {}", "✗".red()));
+                }
+                if n == self.selected {
+                    format!("> {}", rendered.reversed())
+                } else {
+                    format!("  {}", rendered)
+                }
+            })
+            .collect();
+        lines.push(format!("  {}/{}", self.matches.len(), history.len()));
+        lines.join("\r\n")
+    }
+}
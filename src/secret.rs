@@ -0,0 +1,95 @@
+use std::{
+    fmt, ptr,
+    sync::{
+        atomic::{self, Ordering},
+        mpsc::{self, Sender},
+    },
+};
+
+use crate::{Console, Event};
+
+/// A value read with [`Console::read_secret`]
+///
+/// The value is never printed by `Debug`, and its memory is zeroed when it
+/// is dropped. With the `secrecy` feature, it can be converted into a
+/// [`secrecy::SecretString`].
+pub struct Secret(String);
+
+impl Secret {
+    pub(crate) fn new(value: String) -> Self {
+        Secret(value)
+    }
+    /// Get the secret value
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Secret([REDACTED])")
+    }
+}
+
+impl Drop for Secret {
+    fn drop(&mut self) {
+        zero(&mut self.0);
+    }
+}
+
+#[cfg(feature = "secrecy")]
+impl From<Secret> for secrecy::SecretString {
+    fn from(mut secret: Secret) -> Self {
+        std::mem::take(&mut secret.0).into()
+    }
+}
+
+/// Overwrite the whole buffer of a string with zeros and clear it
+pub(crate) fn zero(s: &mut String) {
+    // Safety: only zeros are written, within the string's allocation, and
+    // the string is cleared afterwards
+    unsafe {
+        let bytes = s.as_mut_vec();
+        let start = bytes.as_mut_ptr();
+        for i in 0..bytes.capacity() {
+            ptr::write_volatile(start.add(i), 0);
+        }
+        bytes.clear();
+    }
+    atomic::compiler_fence(Ordering::SeqCst);
+}
+
+/// A secret being read by the editor
+pub(crate) struct SecretRead {
+    /// The prompt before the secret was asked for
+    pub prompt: String,
+    /// Whether the input was masked before the secret was asked for
+    pub mask: bool,
+    /// The input and cursor before the secret was asked for
+    pub input: (String, usize),
+    pub reply: Sender<Secret>,
+}
+
+impl<M> Console<M>
+where
+    M: Send + 'static,
+{
+    /// Read a secret, like a password or a token, from the terminal
+    ///
+    /// The prompt is shown and the input is masked until a line is
+    /// submitted, blocking until then. Unlike input that is only
+    /// [masked](crate::ConsoleBuilder::mask_input), the secret is never added
+    /// to the history, shown to the completer or hinter, put in the kill ring,
+    /// written to the scrollback or output sinks, or sent to the app as a
+    /// message. Only typing and moving the cursor work while it is read.
+    ///
+    /// Returns `None` if Ctrl-C or Esc is pressed, if another secret is
+    /// already being read, or if the console closes.
+    pub fn read_secret<S: Into<String>>(&self, prompt: S) -> Option<Secret> {
+        let (reply, secret) = mpsc::channel();
+        self.events
+            .send(Event::ReadSecret(prompt.into(), reply))
+            .ok()?;
+        secret.recv().ok()
+    }
+}
@@ -0,0 +1,118 @@
+use std::{
+    io::{self, Read, Write},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use serialport::SerialPort;
+
+use crate::{Console, Input, InputSource, Origin, OutputSink};
+
+impl<M> Console<M>
+where
+    M: Send + 'static,
+{
+    /// Take commands from a device connected to a serial port
+    ///
+    /// The device is treated as a VT100 terminal. Typed characters are echoed
+    /// back to it, Backspace erases the last character, and Ctrl-U erases
+    /// the whole line. Each line is its own command with the origin
+    /// `serial:PATH`, and output printed with [`Console::print_to`] for that
+    /// origin is written back to the device.
+    pub fn connect_serial(&self, path: &str, baud_rate: u32) -> serialport::Result<()> {
+        let port = serialport::new(path, baud_rate)
+            .timeout(Duration::from_secs(60))
+            .open()?;
+        let origin = Origin::Source(format!("serial:{}", path));
+        let writer = Arc::new(Mutex::new(port.try_clone()?));
+        self.add_origin_sink(
+            origin.clone(),
+            SerialSink {
+                writer: Arc::clone(&writer),
+            },
+        );
+        self.add_input_source(SerialSource {
+            port,
+            writer,
+            origin,
+        });
+        Ok(())
+    }
+}
+
+/// Reads lines typed on a serial device
+struct SerialSource {
+    port: Box<dyn SerialPort>,
+    /// The port used to echo input back to the device
+    writer: Arc<Mutex<Box<dyn SerialPort>>>,
+    origin: Origin,
+}
+
+impl SerialSource {
+    fn echo(&self, bytes: &[u8]) {
+        let mut writer = self.writer.lock().unwrap();
+        let _ = writer.write_all(bytes);
+        let _ = writer.flush();
+    }
+}
+
+impl InputSource for SerialSource {
+    fn next_input(&mut self) -> Option<Input> {
+        let mut line = Vec::new();
+        let mut byte = [0];
+        loop {
+            match self.port.read(&mut byte) {
+                Ok(0) => return None,
+                Ok(_) => {}
+                Err(e) if e.kind() == io::ErrorKind::TimedOut => continue,
+                Err(_) => return None,
+            }
+            match byte[0] {
+                b'\r' | b'\n' if line.is_empty() => continue,
+                b'\r' | b'\n' => {
+                    self.echo(b"\r\n");
+                    return Some(Input::Line(String::from_utf8_lossy(&line).into()));
+                }
+                // Backspace and DEL
+                0x08 | 0x7F if !line.is_empty() => {
+                    // Erase a whole character
+                    while let Some(b) = line.pop() {
+                        if b & 0xC0 != 0x80 {
+                            break;
+                        }
+                    }
+                    self.echo(b"\x08 \x08");
+                }
+                // Ctrl-U
+                0x15 => {
+                    self.echo(b"\r\x1b[K");
+                    line.clear();
+                }
+                b @ (0x20..=0x7E | 0x80..=0xFF) => {
+                    line.push(b);
+                    self.echo(&[b]);
+                }
+                _ => {}
+            }
+        }
+    }
+    fn origin(&self) -> Origin {
+        self.origin.clone()
+    }
+}
+
+/// Writes output to a serial device
+struct SerialSink {
+    writer: Arc<Mutex<Box<dyn SerialPort>>>,
+}
+
+impl OutputSink for SerialSink {
+    fn write(&mut self, output: &str) -> io::Result<()> {
+        // Terminals need a carriage return to start each line
+        let output = output.replace('\n', "\r\n");
+        let mut writer = self.writer.lock().unwrap();
+        writer.write_all(output.as_bytes())?;
+        writer.write_all(b"\r\n")?;
+        writer.flush()
+    }
+}
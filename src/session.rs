@@ -0,0 +1,150 @@
+use std::{
+    io::{self, BufRead, Read, Write},
+    sync::{mpsc::Sender, Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use crate::{Input, InputSource, Level, Origin, Responder, Role};
+
+/// A change to a remote session of a console
+///
+/// These are received from
+/// [`Console::session_events`](crate::Console::session_events), so the app
+/// can log connections or enforce its own policies for them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionEvent {
+    /// A connection was accepted
+    Opened(SessionInfo),
+    /// A connection was closed
+    Closed(SessionInfo),
+    /// A connection from an address that is not allowed was closed before it
+    /// was opened
+    Rejected(SessionInfo),
+    /// A connection whose login was rejected was closed before it was
+    /// opened
+    ///
+    /// The user is the one the connection tried to log in as.
+    AuthFailed(SessionInfo),
+}
+
+/// Information about a remote session
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionInfo {
+    /// The origin of the session's input
+    pub origin: Origin,
+    /// The address of the peer, or of the socket for Unix connections
+    pub address: String,
+    /// The user the session logged in as, if sessions
+    /// [log in](crate::ConsoleBuilder::authenticate)
+    pub user: Option<String>,
+}
+
+/// A function that checks the user name and password of a remote session
+pub(crate) type Authenticator = Arc<dyn Fn(&str, &str) -> bool + Send + Sync>;
+
+/// The longest user name or password line that is read, in bytes
+const MAX_LOGIN_LINE: u64 = 1024;
+
+/// The result of asking a remote session to log in
+pub(crate) enum Login {
+    Accepted(String),
+    Rejected(String),
+    /// The connection ended before the login was finished
+    Ended,
+}
+
+/// Ask a remote session for a user name and password and check them
+pub(crate) fn log_in<R, W>(reader: &mut R, writer: &mut W, authenticator: &Authenticator) -> Login
+where
+    R: BufRead,
+    W: Write,
+{
+    let mut ask = |prompt: &str| {
+        writer.write_all(prompt.as_bytes()).ok()?;
+        writer.flush().ok()?;
+        let mut line = String::new();
+        match reader.by_ref().take(MAX_LOGIN_LINE).read_line(&mut line) {
+            Ok(0) | Err(_) => None,
+            Ok(_) => Some(line.trim_end_matches(['\r', '\n']).to_string()),
+        }
+    };
+    let (user, password) = match (ask("user: "), ask("password: ")) {
+        (Some(user), Some(password)) => (user, password),
+        _ => return Login::Ended,
+    };
+    if authenticator(&user, &password) {
+        Login::Accepted(user)
+    } else {
+        let _ = writer.write_all(b"login failed\n");
+        Login::Rejected(user)
+    }
+}
+
+/// The senders of the session events of a console
+pub(crate) type SessionWatchers = Arc<Mutex<Vec<Sender<SessionEvent>>>>;
+
+/// Send a session event to every watcher, removing the ones that are gone
+pub(crate) fn send_session_event(watchers: &SessionWatchers, event: SessionEvent) {
+    watchers
+        .lock()
+        .unwrap()
+        .retain(|watcher| watcher.send(event.clone()).is_ok());
+}
+
+/// An [`InputSource`] for a remote session that reports when it is closed
+pub(crate) struct SessionSource<S> {
+    source: S,
+    info: SessionInfo,
+    watchers: SessionWatchers,
+}
+
+impl<S> SessionSource<S> {
+    /// Wrap the source of a session, reporting that it was opened
+    pub fn open(source: S, info: SessionInfo, watchers: SessionWatchers) -> Self {
+        send_session_event(&watchers, SessionEvent::Opened(info.clone()));
+        SessionSource {
+            source,
+            info,
+            watchers,
+        }
+    }
+}
+
+impl<S: InputSource> InputSource for SessionSource<S> {
+    fn next_input(&mut self) -> Option<Input> {
+        self.source.next_input()
+    }
+    fn origin(&self) -> Origin {
+        self.source.origin()
+    }
+    fn role(&self) -> Role {
+        self.source.role()
+    }
+    fn user(&self) -> Option<String> {
+        self.info.user.clone()
+    }
+}
+
+impl<S> Drop for SessionSource<S> {
+    fn drop(&mut self) {
+        let info = self.info.clone();
+        send_session_event(&self.watchers, SessionEvent::Closed(info));
+    }
+}
+
+/// How long to wait before accepting again after a connection fails
+const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// Get an accepted connection, or log the error and wait a moment before the
+/// next attempt so that a failing listener does not spin
+pub(crate) fn accepted<T>(responder: &Responder, connection: io::Result<T>) -> Option<T> {
+    match connection {
+        Ok(connection) => Some(connection),
+        Err(e) => {
+            responder.log(Level::Warn, format!("failed to accept a connection: {}", e));
+            thread::sleep(ACCEPT_RETRY_DELAY);
+            None
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use std::{
+    io::{self, Write},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::{strip_ansi, Origin};
+
+/// A destination that receives a copy of a console's output
+///
+/// Sinks are added with
+/// [`ConsoleBuilder::output_sink`](crate::ConsoleBuilder::output_sink) or
+/// [`Console::add_output_sink`](crate::Console::add_output_sink). Everything
+/// printed with a [`Responder`](crate::Responder) is shown on the terminal
+/// and written to every sink. A sink that fails to write is removed.
+pub trait OutputSink: Send {
+    /// Write some output
+    fn write(&mut self, output: &str) -> io::Result<()>;
+    /// Check if the sink shows ANSI escape codes
+    ///
+    /// If not, they are removed from the output before it is written. By
+    /// default, they are shown.
+    fn ansi(&self) -> bool {
+        true
+    }
+    /// Check if the sink can no longer be written to
+    ///
+    /// Closed sinks are removed the next time output is written or a chat
+    /// command is received. By default, a sink is only removed once it fails
+    /// to write.
+    fn is_closed(&self) -> bool {
+        false
+    }
+}
+
+/// An [`OutputSink`] that writes each output as a line to a writer
+///
+/// This can write to a transcript file or a socket.
+pub struct WriterSink<W> {
+    writer: W,
+    ansi: bool,
+    timeout: Option<Duration>,
+}
+
+impl<W: Write> WriterSink<W> {
+    /// Create a `WriterSink` that writes to the given writer
+    pub fn new(writer: W) -> Self {
+        WriterSink {
+            writer,
+            ansi: true,
+            timeout: None,
+        }
+    }
+    /// Remove ANSI escape codes from the output
+    ///
+    /// This is useful for writers that are not terminals, like files.
+    pub fn plain(mut self) -> Self {
+        self.ansi = false;
+        self
+    }
+    /// Fail an output that takes longer than the timeout to write
+    ///
+    /// The time is only checked between writes, so the writer should time
+    /// out on its own too, like a socket with
+    /// [`set_write_timeout`](std::net::TcpStream::set_write_timeout). By
+    /// default, there is no timeout.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+impl<W: Write + Send> OutputSink for WriterSink<W> {
+    fn write(&mut self, output: &str) -> io::Result<()> {
+        let line = format!("{}\n", output);
+        let deadline = self.timeout.map(|timeout| Instant::now() + timeout);
+        let mut rest = line.as_bytes();
+        while !rest.is_empty() {
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return Err(io::ErrorKind::TimedOut.into());
+            }
+            match self.writer.write(rest) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(n) => rest = &rest[n..],
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        self.writer.flush()
+    }
+    fn ansi(&self) -> bool {
+        self.ansi
+    }
+}
+
+/// A sink that can be written to without holding the lock on the list of
+/// sinks
+pub(crate) type SharedSink = Arc<Mutex<Box<dyn OutputSink>>>;
+
+/// The output sinks of a console
+///
+/// A sink with an origin only receives the output that is sent to that
+/// origin.
+pub(crate) type Sinks = Arc<Mutex<Vec<(Option<Origin>, SharedSink)>>>;
+
+/// Add a sink for the given origin, or for all output if there is none
+pub(crate) fn add_sink(sinks: &Sinks, origin: Option<Origin>, sink: Box<dyn OutputSink>) {
+    let sink = Arc::new(Mutex::new(sink));
+    sinks.lock().unwrap().push((origin, sink));
+}
+
+/// Write some output to every sink for the given origin, removing the ones
+/// that fail
+pub(crate) fn write_to_sinks(sinks: &Sinks, origin: Option<&Origin>, output: &str) {
+    write_where(sinks, |sink_origin| sink_origin == origin, output);
+}
+
+/// Write some output to every sink, removing the ones that fail
+pub(crate) fn write_to_all_sinks(sinks: &Sinks, output: &str) {
+    write_where(sinks, |_| true, output);
+}
+
+/// Write some output to every sink whose origin matches, removing the ones
+/// that fail
+///
+/// The sinks are written to after the list is unlocked, so a slow sink only
+/// holds up the output that goes to it.
+fn write_where<F>(sinks: &Sinks, matches: F, output: &str)
+where
+    F: Fn(Option<&Origin>) -> bool,
+{
+    let mut list = sinks.lock().unwrap();
+    // Sinks that are being written to are checked the next time
+    list.retain(|(_, sink)| !sink.try_lock().is_ok_and(|sink| sink.is_closed()));
+    let matching: Vec<SharedSink> = list
+        .iter()
+        .filter(|(sink_origin, _)| matches(sink_origin.as_ref()))
+        .map(|(_, sink)| Arc::clone(sink))
+        .collect();
+    drop(list);
+    let mut plain = None;
+    let mut failed = Vec::new();
+    for shared in matching {
+        let mut sink = shared.lock().unwrap();
+        let output = if sink.ansi() {
+            output
+        } else {
+            plain.get_or_insert_with(|| strip_ansi(output))
+        };
+        if sink.is_closed() || sink.write(output).is_err() {
+            drop(sink);
+            failed.push(shared);
+        }
+    }
+    if !failed.is_empty() {
+        sinks
+            .lock()
+            .unwrap()
+            .retain(|(_, sink)| !failed.iter().any(|f| Arc::ptr_eq(f, sink)));
+    }
+}
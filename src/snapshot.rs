@@ -0,0 +1,80 @@
+use std::{collections::HashMap, sync::mpsc};
+
+use crate::{Console, Event};
+
+/// The state of a [`Console`]'s session, saved so that it can be resumed
+///
+/// A snapshot is taken with [`Console::snapshot`] and resumed with
+/// [`Console::restore`], for example across a restart of the app during
+/// development. With the `json` feature, it can be serialized with serde.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConsoleSnapshot {
+    /// The lines in the history, oldest first
+    pub history: Vec<String>,
+    /// The aliases that were defined
+    pub aliases: HashMap<String, String>,
+    /// The global console variables
+    pub vars: HashMap<String, String>,
+    /// The prompts of the nested global modes that were active, outermost
+    /// first
+    ///
+    /// A mode's processor cannot be saved, so the modes are not entered
+    /// again by [`Console::restore`]. The app can push its modes again based
+    /// on these prompts.
+    pub modes: Vec<String>,
+    /// The text of the input line
+    pub input: String,
+    /// The byte index of the cursor in the input line
+    pub cursor: usize,
+}
+
+impl<M> Console<M>
+where
+    M: Send + 'static,
+{
+    /// Take a snapshot of the console's session
+    ///
+    /// This waits for the console thread to answer.
+    pub fn snapshot(&self) -> ConsoleSnapshot {
+        let (reply, history) = mpsc::channel();
+        let (history, aliases) = match self.events.send(Event::Snapshot(reply)) {
+            Ok(()) => history.recv().unwrap_or_default(),
+            Err(_) => Default::default(),
+        };
+        let line = self.responder.line.lock().unwrap();
+        ConsoleSnapshot {
+            history,
+            aliases,
+            vars: self.vars.lock().unwrap().global.clone(),
+            modes: self
+                .modes
+                .lock()
+                .unwrap()
+                .global
+                .iter()
+                .map(|mode| mode.prompt.clone())
+                .collect(),
+            // A secret being read is left out
+            input: if line.secret {
+                String::new()
+            } else {
+                line.input.clone()
+            },
+            cursor: if line.secret { 0 } else { line.cursor },
+        }
+    }
+    /// Resume a session from a snapshot
+    ///
+    /// The history, console variables, and input line are replaced. The
+    /// aliases are added to the ones defined in the
+    /// [definitions file](crate::ConsoleBuilder::definitions) until it is
+    /// reloaded.
+    pub fn restore(&self, snapshot: ConsoleSnapshot) {
+        self.vars.lock().unwrap().global = snapshot.vars;
+        self.set_input(snapshot.input, snapshot.cursor);
+        let _ = self
+            .events
+            .send(Event::Restore(snapshot.history, snapshot.aliases));
+    }
+}
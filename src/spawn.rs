@@ -0,0 +1,38 @@
+use std::{
+    sync::mpsc::{self, Receiver},
+    thread,
+};
+
+/// Starts the threads that a console uses
+///
+/// Threads are plain OS threads unless an async runtime has been set with
+/// [`ConsoleBuilder::runtime`](crate::ConsoleBuilder::runtime), in which case
+/// they run on the runtime's blocking pool.
+#[derive(Clone, Default)]
+pub(crate) struct Spawner {
+    #[cfg(feature = "tokio")]
+    pub runtime: Option<tokio::runtime::Handle>,
+}
+
+impl Spawner {
+    /// Run a blocking function on its own thread
+    ///
+    /// The returned receiver is disconnected once the function finishes.
+    pub fn spawn<F>(&self, f: F) -> Receiver<()>
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let (running, finished) = mpsc::channel();
+        let f = move || {
+            let _running = running;
+            f()
+        };
+        #[cfg(feature = "tokio")]
+        if let Some(runtime) = &self.runtime {
+            runtime.spawn_blocking(f);
+            return finished;
+        }
+        thread::spawn(f);
+        finished
+    }
+}
@@ -0,0 +1,151 @@
+use std::{
+    collections::HashMap,
+    path::Path,
+    time::{Duration, UNIX_EPOCH},
+};
+
+use rusqlite::{params, Connection, Row};
+
+use crate::{HistoryEntry, HistoryStore, SessionId};
+
+/// A [`HistoryStore`] backed by an SQLite database
+///
+/// Entries are stored with their timestamp, session, and exit status.
+/// Several processes can share the same database. SQLite's locking keeps
+/// concurrent writes from corrupting it.
+pub struct SqliteHistory {
+    conn: Connection,
+    /// The database's data version when it was last read
+    data_version: i64,
+}
+
+impl SqliteHistory {
+    /// Open or create a history database at the given path
+    pub fn open<P: AsRef<Path>>(path: P) -> rusqlite::Result<Self> {
+        Self::from_connection(Connection::open(path)?)
+    }
+    /// Use an existing connection, creating the history table if necessary
+    pub fn from_connection(conn: Connection) -> rusqlite::Result<Self> {
+        conn.busy_timeout(Duration::from_secs(5))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS history (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                line TEXT NOT NULL,
+                timestamp INTEGER NOT NULL,
+                session INTEGER NOT NULL,
+                exit_status INTEGER
+            );
+            CREATE INDEX IF NOT EXISTS history_line ON history (line);",
+        )?;
+        let data_version = data_version(&conn)?;
+        Ok(SqliteHistory { conn, data_version })
+    }
+    /// Get the underlying connection for custom queries
+    pub fn connection(&self) -> &Connection {
+        &self.conn
+    }
+    /// Get all entries whose line contains the given text, oldest first
+    pub fn search(&self, text: &str) -> rusqlite::Result<Vec<HistoryEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT line, timestamp, session, exit_status FROM history
+            WHERE instr(line, ?1) > 0 ORDER BY id",
+        )?;
+        let entries = stmt.query_map(params![text], entry_from_row)?;
+        entries.collect()
+    }
+    /// Get all entries entered in the given session, oldest first
+    pub fn session(&self, session: SessionId) -> rusqlite::Result<Vec<HistoryEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT line, timestamp, session, exit_status FROM history
+            WHERE session = ?1 ORDER BY id",
+        )?;
+        let entries = stmt.query_map(params![session.0 as i64], entry_from_row)?;
+        entries.collect()
+    }
+}
+
+/// Get a number that changes whenever another connection modifies the database
+fn data_version(conn: &Connection) -> rusqlite::Result<i64> {
+    conn.query_row("PRAGMA data_version", [], |row| row.get(0))
+}
+
+fn entry_from_row(row: &Row) -> rusqlite::Result<HistoryEntry> {
+    let millis: i64 = row.get(1)?;
+    let session: i64 = row.get(2)?;
+    Ok(HistoryEntry {
+        line: row.get(0)?,
+        timestamp: UNIX_EPOCH + Duration::from_millis(millis as u64),
+        session: SessionId(session as u64),
+        exit_status: row.get(3)?,
+    })
+}
+
+impl HistoryStore for SqliteHistory {
+    fn load(&mut self) -> Vec<String> {
+        let mut stmt = match self.conn.prepare("SELECT line FROM history ORDER BY id") {
+            Ok(stmt) => stmt,
+            Err(_) => return Vec::new(),
+        };
+        let lines = match stmt.query_map([], |row| row.get(0)) {
+            Ok(lines) => lines,
+            Err(_) => return Vec::new(),
+        };
+        lines.filter_map(Result::ok).collect()
+    }
+    fn append(&mut self, entry: &HistoryEntry) {
+        let millis = entry
+            .timestamp
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as i64;
+        let _ = self.conn.execute(
+            "INSERT INTO history (line, timestamp, session, exit_status)
+            VALUES (?1, ?2, ?3, ?4)",
+            params![
+                entry.line,
+                millis,
+                entry.session.0 as i64,
+                entry.exit_status
+            ],
+        );
+    }
+    fn set_exit_status(&mut self, line: &str, session: SessionId, exit_status: i32) {
+        let _ = self.conn.execute(
+            "UPDATE history SET exit_status = ?1 WHERE id = (
+                SELECT max(id) FROM history WHERE line = ?2 AND session = ?3
+            )",
+            params![exit_status, line, session.0 as i64],
+        );
+    }
+    fn load_failed(&mut self) -> Vec<String> {
+        let mut stmt = match self.conn.prepare(
+            "SELECT line, exit_status FROM history
+            WHERE exit_status IS NOT NULL ORDER BY id",
+        ) {
+            Ok(stmt) => stmt,
+            Err(_) => return Vec::new(),
+        };
+        let statuses = match stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?))) {
+            Ok(statuses) => statuses,
+            Err(_) => return Vec::new(),
+        };
+        // Later runs of a line replace the status of earlier ones
+        let mut failed = HashMap::new();
+        for (line, status) in statuses.filter_map(Result::<(String, i32), _>::ok) {
+            failed.insert(line, status != 0);
+        }
+        failed
+            .into_iter()
+            .filter(|&(_, failed)| failed)
+            .map(|(line, _)| line)
+            .collect()
+    }
+    fn reload(&mut self) -> Option<Vec<String>> {
+        let version = data_version(&self.conn).ok()?;
+        if version == self.data_version {
+            return None;
+        }
+        self.data_version = version;
+        Some(self.load())
+    }
+}
@@ -0,0 +1,51 @@
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// A handle to app state that a processor can consult while the app changes
+/// it
+///
+/// Handles are cheap to clone and can be sent to other threads. Give one to
+/// the processor builder with [`ConsoleBuilder::build_with_state`](crate::ConsoleBuilder::build_with_state)
+/// and keep another to update the state, so that parsing and completion
+/// always see live data, like the names of the entities in a game. Locks
+/// should only be held briefly, since the console thread waits for them
+/// while the user is typing.
+pub struct SharedState<S> {
+    state: Arc<RwLock<S>>,
+}
+
+impl<S> SharedState<S> {
+    /// Create a new `SharedState` holding the given state
+    pub fn new(state: S) -> Self {
+        SharedState {
+            state: Arc::new(RwLock::new(state)),
+        }
+    }
+    /// Lock the state for reading
+    pub fn read(&self) -> RwLockReadGuard<'_, S> {
+        self.state.read().unwrap()
+    }
+    /// Lock the state for writing
+    pub fn write(&self) -> RwLockWriteGuard<'_, S> {
+        self.state.write().unwrap()
+    }
+}
+
+impl<S> Clone for SharedState<S> {
+    fn clone(&self) -> Self {
+        SharedState {
+            state: Arc::clone(&self.state),
+        }
+    }
+}
+
+impl<S: Default> Default for SharedState<S> {
+    fn default() -> Self {
+        Self::new(S::default())
+    }
+}
+
+impl<S> From<Arc<RwLock<S>>> for SharedState<S> {
+    fn from(state: Arc<RwLock<S>>) -> Self {
+        SharedState { state }
+    }
+}
@@ -0,0 +1,115 @@
+use crate::CaseMatching;
+
+/// Find the known commands most similar to an unrecognized one
+///
+/// Commands are ordered from most to least similar. Commands that are too
+/// different to plausibly be what was meant are left out.
+pub fn suggestions<'a, I>(command: &str, known: I) -> Vec<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let max_distance = (command.chars().count() / 3).max(2);
+    let mut scored: Vec<(usize, &str)> = known
+        .into_iter()
+        .map(|name| (strsim::levenshtein(command, name), name))
+        .filter(|&(distance, _)| distance <= max_distance)
+        .collect();
+    scored.sort();
+    scored.dedup();
+    scored.into_iter().map(|(_, name)| name).collect()
+}
+
+/// Format a "did you mean" message for a list of suggestions
+pub(crate) fn did_you_mean(suggestions: &[&str]) -> Option<String> {
+    match suggestions {
+        [] => None,
+        [one] => Some(format!("did you mean `{}`?", one)),
+        many => Some(format!(
+            "did you mean one of {}?",
+            many.iter()
+                .take(3)
+                .map(|name| format!("`{}`", name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+/// The result of expanding an abbreviated command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Abbreviation {
+    /// The input's command did not need to be expanded
+    Unchanged,
+    /// The command was a unique prefix and was expanded to the contained input
+    Expanded(String),
+    /// The command was a prefix of several commands
+    Ambiguous(Vec<String>),
+}
+
+/// Expand the command at the start of the input if it is a unique prefix of
+/// one of the known commands
+pub fn expand_abbreviation(input: &str, commands: &[String]) -> Abbreviation {
+    expand_abbreviation_with_case(input, commands, CaseMatching::Sensitive)
+}
+
+/// Expand the command at the start of the input if it is a unique prefix of
+/// one of the known commands, matching letter case in the given way
+pub(crate) fn expand_abbreviation_with_case(
+    input: &str,
+    commands: &[String],
+    case: CaseMatching,
+) -> Abbreviation {
+    let trimmed = input.trim_start();
+    let command = match trimmed.split_whitespace().next() {
+        Some(command) => command,
+        None => return Abbreviation::Unchanged,
+    };
+    if commands.iter().any(|c| case.matches(command, c)) {
+        return Abbreviation::Unchanged;
+    }
+    let mut matches: Vec<String> = commands
+        .iter()
+        .filter(|c| case.prefix_match(command, c).is_some())
+        .cloned()
+        .collect();
+    match matches.len() {
+        0 => Abbreviation::Unchanged,
+        1 => {
+            let offset = input.len() - trimmed.len();
+            Abbreviation::Expanded(format!(
+                "{}{}{}",
+                &input[..offset],
+                matches.remove(0),
+                &trimmed[command.len()..]
+            ))
+        }
+        _ => {
+            matches.sort();
+            Abbreviation::Ambiguous(matches)
+        }
+    }
+}
+
+/// Replace the command at the start of the input with the known command that
+/// it matches in another letter case
+///
+/// Returns `None` if the command is known as it is or matches none of them.
+pub(crate) fn match_command_case(
+    input: &str,
+    commands: &[String],
+    case: CaseMatching,
+) -> Option<String> {
+    let trimmed = input.trim_start();
+    let command = trimmed.split_whitespace().next()?;
+    if commands.iter().any(|c| c == command) {
+        return None;
+    }
+    let known = commands.iter().find(|c| case.matches(command, c))?;
+    let offset = input.len() - trimmed.len();
+    Some(format!(
+        "{}{}{}",
+        &input[..offset],
+        known,
+        &trimmed[command.len()..]
+    ))
+}
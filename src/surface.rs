@@ -0,0 +1,191 @@
+use colored::Color;
+use unicode_width::UnicodeWidthChar;
+
+use crate::{display_width, escape_len, wrap_line, EmbeddedConsole};
+
+/// The style of a [`StyledSpan`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SpanStyle {
+    /// The color of the text, or `None` for the surface's default color
+    pub color: Option<Color>,
+    /// Whether the text is bold
+    pub bold: bool,
+    /// Whether the text is dimmed
+    pub dim: bool,
+}
+
+/// A piece of text with a single style
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StyledSpan {
+    /// The text
+    pub text: String,
+    /// The style of the text
+    pub style: SpanStyle,
+}
+
+/// A grid of text that an [`EmbeddedConsole`] can be drawn on
+///
+/// Game engines can implement this with their own font rendering to show a
+/// drop-down console inside the game window. The console is drawn with
+/// [`EmbeddedConsole::draw`].
+pub trait TextSurface {
+    /// Get the number of rows and columns of text that fit on the surface
+    fn size(&self) -> (usize, usize);
+    /// Draw a line of styled text at a row, replacing what was there
+    fn draw_line(&mut self, row: usize, spans: &[StyledSpan]);
+    /// Set where the text cursor is shown
+    fn set_cursor(&mut self, row: usize, column: usize);
+}
+
+/// Split some text into spans at its ANSI style codes
+///
+/// The style is carried over from and left for the next text, so a line can
+/// be split into several rows.
+fn styled_spans(text: &str, style: &mut SpanStyle) -> Vec<StyledSpan> {
+    let mut spans = Vec::new();
+    let mut current = String::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let len = match escape_len(rest) {
+            Some(len) => len,
+            None => {
+                current.push(c);
+                rest = &rest[c.len_utf8()..];
+                continue;
+            }
+        };
+        if !current.is_empty() {
+            spans.push(StyledSpan {
+                text: std::mem::take(&mut current),
+                style: *style,
+            });
+        }
+        if let Some(params) = rest[..len]
+            .strip_prefix("\x1b[")
+            .and_then(|seq| seq.strip_suffix('m'))
+        {
+            apply_sgr(params, style);
+        }
+        rest = &rest[len..];
+    }
+    if !current.is_empty() {
+        spans.push(StyledSpan {
+            text: current,
+            style: *style,
+        });
+    }
+    spans
+}
+
+/// Apply the parameters of an ANSI select graphic rendition code to a style
+fn apply_sgr(params: &str, style: &mut SpanStyle) {
+    let mut params = params.split(';').map(|p| p.parse::<u8>().unwrap_or(0));
+    while let Some(param) = params.next() {
+        match param {
+            0 => *style = SpanStyle::default(),
+            1 => style.bold = true,
+            2 => style.dim = true,
+            22 => {
+                style.bold = false;
+                style.dim = false;
+            }
+            30..=37 | 90..=97 => style.color = Some(basic_color(param)),
+            38 => match params.next() {
+                Some(2) => {
+                    let mut next = || params.next().unwrap_or(0);
+                    style.color = Some(Color::TrueColor {
+                        r: next(),
+                        g: next(),
+                        b: next(),
+                    });
+                }
+                Some(5) => {
+                    let n = params.next().unwrap_or(0);
+                    style.color = match n {
+                        0..=7 => Some(basic_color(30 + n)),
+                        8..=15 => Some(basic_color(82 + n)),
+                        _ => None,
+                    };
+                }
+                _ => {}
+            },
+            39 => style.color = None,
+            _ => {}
+        }
+    }
+}
+
+/// Get the color for an ANSI foreground code
+fn basic_color(code: u8) -> Color {
+    match code {
+        30 => Color::Black,
+        31 => Color::Red,
+        32 => Color::Green,
+        33 => Color::Yellow,
+        34 => Color::Blue,
+        35 => Color::Magenta,
+        36 => Color::Cyan,
+        37 => Color::White,
+        90 => Color::BrightBlack,
+        91 => Color::BrightRed,
+        92 => Color::BrightGreen,
+        93 => Color::BrightYellow,
+        94 => Color::BrightBlue,
+        95 => Color::BrightMagenta,
+        96 => Color::BrightCyan,
+        _ => Color::BrightWhite,
+    }
+}
+
+impl EmbeddedConsole {
+    /// Draw the console on a [`TextSurface`]
+    ///
+    /// The input line is drawn on the bottom row with the cursor in it. The
+    /// completion candidates and the most recent output fill the rows above,
+    /// with long lines wrapped.
+    pub fn draw<S: TextSurface + ?Sized>(&self, surface: &mut S) {
+        let (rows, columns) = surface.size();
+        if rows == 0 || columns == 0 {
+            return;
+        }
+        let mut lines = self.ansi_output();
+        if !self.candidates().is_empty() {
+            let candidates: Vec<&str> = self
+                .candidates()
+                .iter()
+                .map(|candidate| candidate.replacement.as_str())
+                .collect();
+            lines.push(format!("\x1b[2m{}\x1b[0m", candidates.join("  ")));
+        }
+        let mut output = Vec::new();
+        for line in &lines {
+            let mut style = SpanStyle::default();
+            for row in wrap_line(line, columns, 0) {
+                output.push(styled_spans(&row, &mut style));
+            }
+        }
+        let shown = output.len().min(rows - 1);
+        let blank = rows - 1 - shown;
+        for row in 0..blank {
+            surface.draw_line(row, &[]);
+        }
+        for (row, spans) in (blank..).zip(&output[output.len() - shown..]) {
+            surface.draw_line(row, spans);
+        }
+        // Scroll the input so that the cursor is visible
+        let before = format!("{}{}", self.prompt_text(), &self.input()[..self.cursor()]);
+        let scroll = (display_width(&before) + 1).saturating_sub(columns);
+        let mut skipped = 0;
+        let text: String = format!("{}{}", self.prompt_text(), self.input())
+            .chars()
+            .skip_while(|c| {
+                let skip = skipped < scroll;
+                skipped += c.width().unwrap_or(0);
+                skip
+            })
+            .collect();
+        let mut style = SpanStyle::default();
+        surface.draw_line(rows - 1, &styled_spans(&text, &mut style));
+        surface.set_cursor(rows - 1, display_width(&before) - scroll);
+    }
+}
@@ -0,0 +1,301 @@
+/*!
+End-to-end tests of a console running in a pseudo-terminal
+
+A [`PtySession`] runs a program, such as an example that opens a console,
+in a pseudo-terminal of a fixed size. Key presses are fed to it, and the
+output is run through a terminal emulator so that the rendered screen can be
+compared with a snapshot.
+
+```no_run
+use std::process::Command;
+
+use encore::{testing::{PtySession, Script}, KeyEvent};
+
+let mut session = PtySession::spawn(Command::new("target/debug/examples/minimal")).unwrap();
+session
+    .run(&Script::new().text("helo").key(KeyEvent::Left).text("l").key(KeyEvent::Char('\n')))
+    .unwrap();
+session.assert_screen("hello\nHELLO");
+```
+*/
+
+use std::{
+    fs::File,
+    io::{self, Read, Write},
+    mem,
+    os::unix::{io::FromRawFd, process::CommandExt},
+    process::{Child, Command, ExitStatus, Stdio},
+    ptr,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crossterm::KeyEvent;
+
+/// How long [`PtySession::assert_screen`] waits for the screen to match
+const SCREEN_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How often the screen is checked while waiting
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// A step in a [`Script`]
+#[derive(Debug, Clone)]
+enum Step {
+    Send(Vec<u8>),
+    Pause(Duration),
+}
+
+/// A series of key presses and pauses to feed to a [`PtySession`]
+#[derive(Debug, Clone, Default)]
+pub struct Script {
+    steps: Vec<Step>,
+}
+
+impl Script {
+    /// Create a new empty `Script`
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Type some text
+    ///
+    /// Each character is sent as its own key press.
+    pub fn text(mut self, text: &str) -> Self {
+        self.steps
+            .extend(text.chars().map(|c| Step::Send(c.to_string().into_bytes())));
+        self
+    }
+    /// Press a key
+    pub fn key(mut self, key: KeyEvent) -> Self {
+        self.steps.push(Step::Send(key_bytes(key)));
+        self
+    }
+    /// Wait before the next step
+    pub fn pause(mut self, duration: Duration) -> Self {
+        self.steps.push(Step::Pause(duration));
+        self
+    }
+}
+
+/// Get the bytes a terminal sends for a key
+fn key_bytes(key: KeyEvent) -> Vec<u8> {
+    let bytes: &[u8] = match key {
+        KeyEvent::Backspace => b"\x7f",
+        KeyEvent::Left => b"\x1b[D",
+        KeyEvent::Right => b"\x1b[C",
+        KeyEvent::Up => b"\x1b[A",
+        KeyEvent::Down => b"\x1b[B",
+        KeyEvent::Home => b"\x1b[H",
+        KeyEvent::End => b"\x1b[F",
+        KeyEvent::PageUp => b"\x1b[5~",
+        KeyEvent::PageDown => b"\x1b[6~",
+        KeyEvent::BackTab => b"\x1b[Z",
+        KeyEvent::Delete => b"\x1b[3~",
+        KeyEvent::Insert => b"\x1b[2~",
+        KeyEvent::F(n @ 1..=4) => return vec![0x1b, b'O', b'P' + n - 1],
+        KeyEvent::F(n @ 5..=12) => {
+            let code = match n {
+                5 => 15,
+                6..=10 => n + 11,
+                _ => n + 12,
+            };
+            return format!("\x1b[{}~", code).into_bytes();
+        }
+        KeyEvent::F(_) => b"",
+        KeyEvent::Char('\n') => b"\r",
+        KeyEvent::Char(c) => return c.to_string().into_bytes(),
+        KeyEvent::Alt(c) => return format!("\x1b{}", c).into_bytes(),
+        KeyEvent::Ctrl(c @ 'a'..='z') => return vec![c as u8 - b'a' + 1],
+        KeyEvent::Ctrl(c @ '4'..='7') => return vec![c as u8 - b'4' + 0x1c],
+        KeyEvent::Ctrl(_) => b"",
+        KeyEvent::Null => b"\0",
+        KeyEvent::Esc => b"\x1b",
+        KeyEvent::CtrlUp => b"\x1b[1;5A",
+        KeyEvent::CtrlDown => b"\x1b[1;5B",
+        KeyEvent::CtrlRight => b"\x1b[1;5C",
+        KeyEvent::CtrlLeft => b"\x1b[1;5D",
+        KeyEvent::ShiftUp => b"\x1b[1;2A",
+        KeyEvent::ShiftDown => b"\x1b[1;2B",
+        KeyEvent::ShiftRight => b"\x1b[1;2C",
+        KeyEvent::ShiftLeft => b"\x1b[1;2D",
+    };
+    bytes.to_vec()
+}
+
+/// A program running in a pseudo-terminal
+///
+/// The program is killed when the session is dropped.
+pub struct PtySession {
+    master: File,
+    child: Child,
+    screen: Arc<Mutex<vt100::Parser>>,
+}
+
+impl PtySession {
+    /// Run a command in a 24 by 80 pseudo-terminal
+    pub fn spawn(command: Command) -> io::Result<Self> {
+        Self::spawn_sized(command, 24, 80)
+    }
+    /// Run a command in a pseudo-terminal with the given number of rows and
+    /// columns
+    pub fn spawn_sized(mut command: Command, rows: u16, columns: u16) -> io::Result<Self> {
+        let (mut master, mut slave) = (0, 0);
+        let size = libc::winsize {
+            ws_row: rows,
+            ws_col: columns,
+            ws_xpixel: 0,
+            ws_ypixel: 0,
+        };
+        let opened = unsafe {
+            libc::openpty(
+                &mut master,
+                &mut slave,
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+            )
+        };
+        if opened != 0 || unsafe { libc::ioctl(master, libc::TIOCSWINSZ, &size) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // Start in raw mode, as a console expects
+        unsafe {
+            let mut termios = mem::zeroed();
+            if libc::tcgetattr(slave, &mut termios) == 0 {
+                libc::cfmakeraw(&mut termios);
+                libc::tcsetattr(slave, libc::TCSANOW, &termios);
+            }
+        }
+        let master = unsafe { File::from_raw_fd(master) };
+        let slave = unsafe { File::from_raw_fd(slave) };
+        command
+            .stdin(Stdio::from(slave.try_clone()?))
+            .stdout(Stdio::from(slave.try_clone()?))
+            .stderr(Stdio::from(slave));
+        // Make the pseudo-terminal the controlling terminal of the program,
+        // since the console reads keys from /dev/tty
+        unsafe {
+            command.pre_exec(|| {
+                if libc::setsid() == -1 || libc::ioctl(0, libc::TIOCSCTTY as _, 0) == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+        let child = command.spawn()?;
+        // Close our copies of the slave so that reads end when the program
+        // exits
+        drop(command);
+        let screen = Arc::new(Mutex::new(vt100::Parser::new(rows, columns, 0)));
+        let mut reader = master.try_clone()?;
+        let parser = Arc::clone(&screen);
+        thread::spawn(move || {
+            let mut buffer = [0; 4096];
+            while let Ok(n @ 1..) = reader.read(&mut buffer) {
+                parser.lock().unwrap().process(&buffer[..n]);
+            }
+        });
+        Ok(PtySession {
+            master,
+            child,
+            screen,
+        })
+    }
+    /// Type some text
+    pub fn send_text(&mut self, text: &str) -> io::Result<()> {
+        self.master.write_all(text.as_bytes())
+    }
+    /// Press a key
+    pub fn send_key(&mut self, key: KeyEvent) -> io::Result<()> {
+        self.master.write_all(&key_bytes(key))
+    }
+    /// Feed a script to the program
+    ///
+    /// There is a short pause after each key press so that the program sees
+    /// them one at a time.
+    pub fn run(&mut self, script: &Script) -> io::Result<()> {
+        for step in &script.steps {
+            match step {
+                Step::Send(bytes) => {
+                    self.master.write_all(bytes)?;
+                    thread::sleep(POLL_INTERVAL);
+                }
+                Step::Pause(duration) => thread::sleep(*duration),
+            }
+        }
+        Ok(())
+    }
+    /// Get the text on the screen
+    ///
+    /// Rows are separated by newlines, and trailing blank space is removed.
+    pub fn screen(&self) -> String {
+        self.screen.lock().unwrap().screen().contents()
+    }
+    /// Get the row and column of the cursor
+    pub fn cursor(&self) -> (u16, u16) {
+        self.screen.lock().unwrap().screen().cursor_position()
+    }
+    /// Wait until some text is on the screen
+    ///
+    /// Returns `false` if it does not appear before the timeout.
+    pub fn wait_for(&self, text: &str, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        while !self.screen().contains(text) {
+            if Instant::now() >= deadline {
+                return false;
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+        true
+    }
+    /// Check that the screen matches a snapshot
+    ///
+    /// The screen is given a moment to finish rendering, and trailing blank
+    /// space is ignored.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the screen does not match the snapshot
+    pub fn assert_screen(&self, expected: &str) {
+        let expected = expected.trim_end();
+        let deadline = Instant::now() + SCREEN_TIMEOUT;
+        loop {
+            let screen = self.screen();
+            if screen.trim_end() == expected {
+                return;
+            }
+            if Instant::now() >= deadline {
+                panic!(
+                    "the screen does not match\n\
+                     expected:\n{}\n\
+                     found:\n{}",
+                    expected,
+                    screen.trim_end()
+                );
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+    /// Wait for the program to exit
+    ///
+    /// Returns `None` if it is still running after the timeout.
+    pub fn wait(&mut self, timeout: Duration) -> io::Result<Option<ExitStatus>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(status) = self.child.try_wait()? {
+                return Ok(Some(status));
+            }
+            if Instant::now() >= deadline {
+                return Ok(None);
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+impl Drop for PtySession {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
@@ -0,0 +1,28 @@
+pub use colored::Color;
+
+/// The colors the console uses for its output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    /// The color of [`Level::Error`](crate::Level::Error) messages
+    pub error: Color,
+    /// The color of [`Level::Warn`](crate::Level::Warn) messages
+    pub warn: Color,
+    /// The color of [`Level::Info`](crate::Level::Info) messages
+    pub info: Color,
+    /// The color of [`Level::Debug`](crate::Level::Debug) messages
+    pub debug: Color,
+    /// The color of [`Level::Trace`](crate::Level::Trace) messages
+    pub trace: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            error: Color::Red,
+            warn: Color::Yellow,
+            info: Color::Green,
+            debug: Color::Blue,
+            trace: Color::BrightBlack,
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use std::{collections::HashMap, sync::Mutex, time::Instant};
+
+use crate::{CommandHandle, Origin};
+
+/// A command that is being watched in case it takes too long
+pub(crate) struct PendingCommand {
+    pub command: String,
+    pub deadline: Instant,
+    /// Where the command came from
+    pub origin: Origin,
+    /// The number of outputs that had been printed for its origin when the
+    /// command was sent
+    pub outputs: u64,
+    pub handle: CommandHandle,
+}
+
+impl PendingCommand {
+    /// Check if the app has finished with the command's message
+    pub fn dropped(&self) -> bool {
+        !self.handle.is_alive()
+    }
+}
+
+/// The number of outputs printed for each origin, used to tell if a command
+/// has responded
+///
+/// General output counts for the terminal, since that is where it is shown.
+#[derive(Default)]
+pub(crate) struct OutputCounts(Mutex<HashMap<Origin, u64>>);
+
+impl OutputCounts {
+    /// Count an output for an origin
+    pub fn add(&self, origin: &Origin) {
+        *self.0.lock().unwrap().entry(count_key(origin)).or_default() += 1;
+    }
+    /// Get the number of outputs printed for an origin
+    pub fn get(&self, origin: &Origin) -> u64 {
+        let counts = self.0.lock().unwrap();
+        counts.get(&count_key(origin)).copied().unwrap_or(0)
+    }
+}
+
+/// Get the origin whose outputs are counted for an origin
+fn count_key(origin: &Origin) -> Origin {
+    match origin {
+        Origin::Source(_) => origin.clone(),
+        Origin::Terminal | Origin::Console => Origin::Terminal,
+    }
+}
@@ -0,0 +1,73 @@
+use std::{
+    convert::TryFrom,
+    time::{Duration, Instant},
+};
+
+/// A unique identifier for a timer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TimerId(pub u64);
+
+/// A timer that periodically produces a message
+struct Timer<M> {
+    id: TimerId,
+    interval: Duration,
+    next: Instant,
+    make: Box<dyn FnMut() -> M + Send>,
+}
+
+/// The timers registered on a console
+pub(crate) struct Timers<M> {
+    timers: Vec<Timer<M>>,
+    next_id: u64,
+}
+
+impl<M> Default for Timers<M> {
+    fn default() -> Self {
+        Timers {
+            timers: Vec::new(),
+            next_id: 0,
+        }
+    }
+}
+
+impl<M> Timers<M> {
+    /// Add a timer, returning its id
+    pub fn add(&mut self, interval: Duration, make: Box<dyn FnMut() -> M + Send>) -> TimerId {
+        self.next_id += 1;
+        let id = TimerId(self.next_id);
+        self.timers.push(Timer {
+            id,
+            interval,
+            next: Instant::now() + interval,
+            make,
+        });
+        id
+    }
+    /// Remove a timer
+    ///
+    /// Returns `false` if there was no timer with the given id
+    pub fn remove(&mut self, id: TimerId) -> bool {
+        let len = self.timers.len();
+        self.timers.retain(|timer| timer.id != id);
+        self.timers.len() != len
+    }
+    /// Get the time at which the next timer fires
+    pub fn next_due(&self) -> Option<Instant> {
+        self.timers.iter().map(|timer| timer.next).min()
+    }
+    /// Make the messages of the timers that have fired
+    pub fn fire(&mut self, now: Instant) -> Vec<M> {
+        let mut messages = Vec::new();
+        for timer in &mut self.timers {
+            if timer.next <= now {
+                messages.push((timer.make)());
+                // Skip ticks that were missed
+                let behind = now - timer.next;
+                let ticks = behind.as_nanos() / timer.interval.as_nanos() + 1;
+                let skip = timer.interval.as_nanos().saturating_mul(ticks);
+                timer.next += Duration::from_nanos(u64::try_from(skip).unwrap_or(u64::MAX));
+            }
+        }
+        messages
+    }
+}
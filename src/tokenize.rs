@@ -0,0 +1,126 @@
+use std::{error::Error, fmt};
+
+/// The way input is split into arguments before being given to a clap `App`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Tokenizer {
+    /// Split on whitespace
+    #[default]
+    Whitespace,
+    /// Split on whitespace, but respect single and double quotes and
+    /// backslash escapes like a shell does
+    ShellWords,
+}
+
+impl Tokenizer {
+    /// Split an input into arguments
+    pub fn split(self, input: &str) -> Result<Vec<String>, UnclosedQuote> {
+        match self {
+            Tokenizer::Whitespace => Ok(input.split_whitespace().map(Into::into).collect()),
+            Tokenizer::ShellWords => shell_words(input),
+        }
+    }
+}
+
+/// An error indicating that a quote was opened but never closed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnclosedQuote(pub char);
+
+impl fmt::Display for UnclosedQuote {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unclosed quote: {}", self.0)
+    }
+}
+
+impl Error for UnclosedQuote {}
+
+/// Split an input into words like a shell does
+///
+/// Text in single quotes is taken literally. In double quotes, a backslash
+/// escapes `"` and `\`. Outside of quotes, a backslash escapes any character.
+pub fn shell_words(input: &str) -> Result<Vec<String>, UnclosedQuote> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut chars = input.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err(UnclosedQuote('\'')),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ '"') | Some(c @ '\\') => word.push(c),
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => return Err(UnclosedQuote('"')),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err(UnclosedQuote('"')),
+                    }
+                }
+            }
+            '\\' => {
+                in_word = true;
+                if let Some(c) = chars.next() {
+                    word.push(c);
+                }
+            }
+            c if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            c => {
+                in_word = true;
+                word.push(c);
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn whitespace() {
+        let words = Tokenizer::Whitespace.split(" say  \"hi there\" ").unwrap();
+        assert_eq!(words, ["say", "\"hi", "there\""]);
+    }
+
+    #[test]
+    fn quotes() {
+        let words = shell_words(r#"say "hi there" 'a "b"' c\ d "e\"f\\g\h" ''"#).unwrap();
+        assert_eq!(
+            words,
+            ["say", "hi there", "a \"b\"", "c d", "e\"f\\g\\h", ""]
+        );
+        assert_eq!(shell_words("a'b'\"c\"").unwrap(), ["abc"]);
+        assert_eq!(shell_words("   ").unwrap(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn unclosed() {
+        assert_eq!(shell_words("say 'hi"), Err(UnclosedQuote('\'')));
+        assert_eq!(shell_words("say \"hi"), Err(UnclosedQuote('"')));
+        assert_eq!(shell_words("say \"hi\\"), Err(UnclosedQuote('"')));
+    }
+}
@@ -0,0 +1,138 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Position, Rect},
+    style::Style,
+    widgets::StatefulWidget,
+};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use crate::EmbeddedConsole;
+
+/// Split a line into rows that fit in a width
+fn wrap(line: &str, width: usize) -> Vec<String> {
+    let mut rows = vec![String::new()];
+    let mut row_width = 0;
+    for g in line.graphemes(true) {
+        let w = g.width();
+        if row_width + w > width && row_width > 0 {
+            rows.push(String::new());
+            row_width = 0;
+        }
+        rows.last_mut().unwrap().push_str(g);
+        row_width += w;
+    }
+    rows
+}
+
+/// A ratatui widget that shows the output of an [`EmbeddedConsole`]
+///
+/// Long lines are wrapped, and the most recent lines that fit are shown.
+#[derive(Debug, Clone, Default)]
+pub struct ConsoleOutput {
+    style: Style,
+}
+
+impl ConsoleOutput {
+    /// Create a new `ConsoleOutput`
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Set the style of the output
+    pub fn style<S: Into<Style>>(mut self, style: S) -> Self {
+        self.style = style.into();
+        self
+    }
+}
+
+impl StatefulWidget for ConsoleOutput {
+    type State = EmbeddedConsole;
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut EmbeddedConsole) {
+        if area.width == 0 || area.height == 0 {
+            return;
+        }
+        buf.set_style(area, self.style);
+        let rows: Vec<String> = state
+            .output()
+            .iter()
+            .flat_map(|line| wrap(line, area.width as usize))
+            .collect();
+        let shown = rows.len().min(area.height as usize);
+        for (y, row) in (area.y..).zip(&rows[rows.len() - shown..]) {
+            buf.set_stringn(area.x, y, row, area.width as usize, self.style);
+        }
+    }
+}
+
+/// A ratatui widget that shows the input line of an [`EmbeddedConsole`]
+///
+/// The prompt and input are shown on the first row, scrolled so that the
+/// cursor is visible. Completion candidates are listed on the rows below if
+/// there is room. The widget does not move the terminal cursor, so use
+/// [`ConsoleInput::cursor_position`] with `Frame::set_cursor_position`.
+#[derive(Debug, Clone, Default)]
+pub struct ConsoleInput {
+    style: Style,
+    candidate_style: Style,
+}
+
+impl ConsoleInput {
+    /// Create a new `ConsoleInput`
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Set the style of the prompt and input
+    pub fn style<S: Into<Style>>(mut self, style: S) -> Self {
+        self.style = style.into();
+        self
+    }
+    /// Set the style of the completion candidates
+    pub fn candidate_style<S: Into<Style>>(mut self, style: S) -> Self {
+        self.candidate_style = style.into();
+        self
+    }
+    /// Get the column of the cursor and how many columns are scrolled off
+    /// the left side
+    fn columns(area: Rect, state: &EmbeddedConsole) -> (usize, usize) {
+        let cursor = state.prompt_text().width() + state.input()[..state.cursor()].width();
+        let scroll = (cursor + 1).saturating_sub(area.width as usize);
+        (cursor - scroll, scroll)
+    }
+    /// Get where the terminal cursor should be when the widget is rendered
+    /// in an area
+    pub fn cursor_position(area: Rect, state: &EmbeddedConsole) -> Position {
+        let (column, _) = Self::columns(area, state);
+        Position::new(area.x + column as u16, area.y)
+    }
+}
+
+impl StatefulWidget for ConsoleInput {
+    type State = EmbeddedConsole;
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut EmbeddedConsole) {
+        if area.width == 0 || area.height == 0 {
+            return;
+        }
+        buf.set_style(area, self.style);
+        let (_, scroll) = Self::columns(area, state);
+        let text = format!("{}{}", state.prompt_text(), state.input());
+        let mut skipped = 0;
+        let visible: String = text
+            .graphemes(true)
+            .skip_while(|g| {
+                let skip = skipped < scroll;
+                skipped += g.width();
+                skip
+            })
+            .collect();
+        buf.set_stringn(area.x, area.y, visible, area.width as usize, self.style);
+        let candidates = state
+            .candidates()
+            .iter()
+            .map(|candidate| candidate.replacement.as_str())
+            .collect::<Vec<_>>()
+            .join("  ");
+        for (y, row) in (area.y + 1..area.bottom()).zip(wrap(&candidates, area.width as usize)) {
+            buf.set_stringn(area.x, y, row, area.width as usize, self.candidate_style);
+        }
+    }
+}
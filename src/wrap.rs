@@ -0,0 +1,152 @@
+use std::collections::VecDeque;
+
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Wrap a line of text at word boundaries so that no row is wider than the
+/// given width
+///
+/// Rows after the first are indented by the given number of spaces. Words
+/// that are too wide to fit on a row are split. ANSI escape sequences take up
+/// no width.
+pub(crate) fn wrap_line(line: &str, width: usize, indent: usize) -> Vec<String> {
+    let width = width.max(indent + 1);
+    let mut rows = Vec::new();
+    let mut row = String::new();
+    let mut row_width = 0;
+    let mut row_start = true;
+    for word in line.split(' ') {
+        if !row_start {
+            if row_width + 1 + display_width(word) > width {
+                rows.push(std::mem::replace(&mut row, " ".repeat(indent)));
+                row_width = indent;
+                row_start = true;
+            } else {
+                row.push(' ');
+                row_width += 1;
+            }
+        }
+        let mut rest = word;
+        while let Some(c) = rest.chars().next() {
+            if let Some(len) = escape_len(rest) {
+                row.push_str(&rest[..len]);
+                rest = &rest[len..];
+                continue;
+            }
+            rest = &rest[c.len_utf8()..];
+            let c_width = c.width().unwrap_or(0);
+            if row_width + c_width > width && !row_start {
+                rows.push(std::mem::replace(&mut row, " ".repeat(indent)));
+                row_width = indent;
+            }
+            row.push(c);
+            row_width += c_width;
+            row_start = false;
+        }
+        row_start = false;
+    }
+    rows.push(row);
+    rows
+}
+
+/// Get the width of some text on the terminal, ignoring ANSI escape
+/// sequences
+pub(crate) fn display_width(text: &str) -> usize {
+    if text.contains('\x1b') {
+        strip_ansi(text).width()
+    } else {
+        text.width()
+    }
+}
+
+/// Get the length in bytes of the ANSI escape sequence at the start of some
+/// text, if there is one
+///
+/// Both control sequences like `ESC [ 1 m` and operating system commands
+/// like the `ESC ] 8` hyperlinks are recognized.
+pub(crate) fn escape_len(text: &str) -> Option<usize> {
+    let rest = text.strip_prefix('\x1b')?;
+    let len = match rest.chars().next() {
+        // Operating system commands end with BEL or ESC \
+        Some(']') => rest
+            .find(['\x07', '\x1b'])
+            .map(|i| i + if rest[i..].starts_with('\x1b') { 2 } else { 1 })
+            .unwrap_or(rest.len()),
+        // Control sequences end with a letter
+        Some('[') => rest[1..]
+            .find(|c: char| c.is_ascii_alphabetic())
+            .map(|i| i + 2)
+            .unwrap_or(rest.len()),
+        Some(c) => c.len_utf8(),
+        None => 0,
+    };
+    Some(1 + len.min(rest.len()))
+}
+
+/// Remove ANSI escape sequences from some text
+pub(crate) fn strip_ansi(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        match escape_len(rest) {
+            Some(len) => rest = &rest[len..],
+            None => {
+                stripped.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    stripped
+}
+
+/// Wrap every line of some text with [`wrap_line`]
+pub(crate) fn wrap_text(text: &str, width: usize, indent: usize) -> String {
+    text.lines()
+        .flat_map(|line| wrap_line(line, width, indent))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Output that was wrapped, kept so that it can be wrapped again when the
+/// terminal is resized
+pub(crate) struct Rewrap {
+    indent: usize,
+    /// The width the output was last wrapped to
+    width: usize,
+    /// The most recent lines of output, before they were wrapped
+    lines: VecDeque<String>,
+}
+
+impl Rewrap {
+    pub fn new(indent: usize) -> Self {
+        Rewrap {
+            indent,
+            width: 0,
+            lines: VecDeque::new(),
+        }
+    }
+    /// Wrap some output to the given width, keeping as many of the most
+    /// recent lines as fit in the given height
+    pub fn wrap(&mut self, text: &str, width: usize, height: usize) -> String {
+        self.width = width;
+        self.lines.extend(text.lines().map(String::from));
+        while self.lines.len() > height {
+            self.lines.pop_front();
+        }
+        wrap_text(text, width, self.indent)
+    }
+    /// Wrap the kept output again if the width has changed since it was
+    /// wrapped, returning the last rows that fit in the given height
+    pub fn resize(&mut self, width: usize, height: usize) -> Option<String> {
+        if width == self.width || self.lines.is_empty() {
+            return None;
+        }
+        self.width = width;
+        let mut rows: Vec<String> = self
+            .lines
+            .iter()
+            .flat_map(|line| wrap_line(line, width, self.indent))
+            .collect();
+        rows.drain(..rows.len().saturating_sub(height));
+        Some(rows.join("\n"))
+    }
+}
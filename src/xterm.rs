@@ -0,0 +1,231 @@
+use std::{
+    io,
+    iter::Peekable,
+    mem,
+    str::Chars,
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+};
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::{Console, EmbeddedConsole, Input, InputSource, Origin, OutputSink};
+
+/// The origin of input from an [`XtermBridge`]
+fn xterm_origin() -> Origin {
+    Origin::Source("xterm".into())
+}
+
+/// A bridge that runs a console in an xterm.js terminal
+///
+/// This lets a web build offer the same console without a real terminal.
+/// Key presses from the terminal's `onData` callback are passed to
+/// [`XtermBridge::feed`], and the text from [`XtermBridge::take_output`] is
+/// passed to the terminal's `write` method. The line is edited by the bridge,
+/// and submitted lines are read by the console from the bridge's
+/// [`XtermSource`], so the console never reads a terminal of its own. The
+/// console still runs its commands on threads, so the target must support
+/// them.
+///
+/// ```no_run
+/// use encore::{Console, XtermBridge};
+///
+/// let mut xterm = XtermBridge::new();
+/// let console = Console::builder()
+///     .input_source(xterm.source())
+///     .build(|| |input: &str| input.to_string(), Some);
+/// xterm.attach(&console);
+/// // In the terminal's `onData` callback
+/// xterm.feed(&console, "help\r");
+/// let output: String = xterm.take_output();
+/// # let _ = console.poll();
+/// ```
+pub struct XtermBridge {
+    state: EmbeddedConsole,
+    lines: Sender<String>,
+    source: Option<XtermSource>,
+    pending: Arc<Mutex<String>>,
+    dirty: bool,
+}
+
+impl XtermBridge {
+    /// Create a new `XtermBridge`
+    pub fn new() -> Self {
+        let (lines, recv) = mpsc::channel();
+        XtermBridge {
+            state: EmbeddedConsole::detached(xterm_origin()),
+            lines,
+            source: Some(XtermSource { lines: recv }),
+            pending: Arc::default(),
+            dirty: true,
+        }
+    }
+    /// Set the prompt shown before the input
+    ///
+    /// This is `"> "` by default.
+    pub fn prompt<S: Into<String>>(mut self, prompt: S) -> Self {
+        self.state = self.state.prompt(prompt);
+        self
+    }
+    /// Take the input source that reads the lines submitted in the terminal
+    ///
+    /// Add it to the console with
+    /// [`ConsoleBuilder::input_source`](crate::ConsoleBuilder::input_source).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the source was already taken
+    pub fn source(&mut self) -> XtermSource {
+        self.source
+            .take()
+            .expect("the xterm source was already taken")
+    }
+    /// Show a console's output in the terminal
+    ///
+    /// Both the console's general output and the output sent back to the
+    /// terminal's input are shown.
+    pub fn attach<M: Send + 'static>(&self, console: &Console<M>) {
+        console.add_output_sink(XtermSink(Arc::clone(&self.pending)));
+        console.add_origin_sink(xterm_origin(), XtermSink(Arc::clone(&self.pending)));
+    }
+    /// Handle data from the terminal's `onData` callback
+    ///
+    /// Enter submits the line, Tab completes it, and the arrow keys move the
+    /// cursor and browse the history.
+    pub fn feed<M: Send + 'static>(&mut self, console: &Console<M>, data: &str) {
+        let mut chars = data.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\r' => {
+                    let input = self.state.take_input();
+                    self.push(&format!("{}{}", self.state.prompt_text(), input));
+                    let _ = self.lines.send(input);
+                }
+                '\t' => {
+                    self.state.complete(console);
+                    let candidates: Vec<&str> = self
+                        .state
+                        .candidates()
+                        .iter()
+                        .map(|candidate| candidate.replacement.as_str())
+                        .collect();
+                    if !candidates.is_empty() {
+                        self.push(&candidates.join("  "));
+                    }
+                }
+                '\x7f' | '\x08' => self.state.backspace(),
+                '\x01' => self.state.move_home(),
+                '\x05' => self.state.move_end(),
+                '\x02' => self.state.move_left(),
+                '\x06' => self.state.move_right(),
+                '\x15' => self.state.set_input(""),
+                '\x1b' => match escape_sequence(&mut chars) {
+                    Some('A') => self.state.history_prev(console),
+                    Some('B') => self.state.history_next(),
+                    Some('C') => self.state.move_right(),
+                    Some('D') => self.state.move_left(),
+                    Some('H') => self.state.move_home(),
+                    Some('F') => self.state.move_end(),
+                    Some('d') => self.state.delete(),
+                    _ => {}
+                },
+                c if c.is_control() => {}
+                c => self.state.insert(c),
+            }
+        }
+        self.dirty = true;
+    }
+    /// Add a line above the input
+    fn push(&self, line: &str) {
+        let mut pending = self.pending.lock().unwrap();
+        pending.push_str(line);
+        pending.push('\n');
+    }
+    /// Take the text to write to the terminal
+    ///
+    /// This prints any new output above the input and redraws the line. It
+    /// is empty if nothing has changed since the last call.
+    pub fn take_output(&mut self) -> String {
+        let pending = mem::take(&mut *self.pending.lock().unwrap());
+        if pending.is_empty() && !self.dirty {
+            return String::new();
+        }
+        self.dirty = false;
+        let mut output = String::from("\r\x1b[2K");
+        for line in pending.lines() {
+            output.push_str(line);
+            output.push_str("\r\n");
+        }
+        output.push_str(self.state.prompt_text());
+        output.push_str(self.state.input());
+        let after = self.state.input()[self.state.cursor()..].width();
+        if after > 0 {
+            output.push_str(&format!("\x1b[{}D", after));
+        }
+        output
+    }
+}
+
+impl Default for XtermBridge {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Read the rest of an escape sequence after the escape
+///
+/// Returns the final character of the sequence, with the editing keys that
+/// end in `~` mapped to the letters used by other terminals. Delete is
+/// mapped to `d`.
+fn escape_sequence(chars: &mut Peekable<Chars>) -> Option<char> {
+    if !matches!(chars.peek(), Some('[' | 'O')) {
+        return None;
+    }
+    chars.next();
+    let mut param = String::new();
+    for c in chars.by_ref() {
+        match c {
+            '0'..='9' | ';' => param.push(c),
+            '~' => {
+                return match param.as_str() {
+                    "1" | "7" => Some('H'),
+                    "4" | "8" => Some('F'),
+                    "3" => Some('d'),
+                    _ => None,
+                }
+            }
+            c => return Some(c),
+        }
+    }
+    None
+}
+
+/// An [`InputSource`] that reads the lines submitted in an [`XtermBridge`]
+///
+/// The source runs out of input once the bridge is dropped.
+pub struct XtermSource {
+    lines: Receiver<String>,
+}
+
+impl InputSource for XtermSource {
+    fn next_input(&mut self) -> Option<Input> {
+        self.lines.recv().ok().map(Input::Line)
+    }
+    fn origin(&self) -> Origin {
+        xterm_origin()
+    }
+}
+
+/// A sink that collects output to write to an xterm.js terminal
+struct XtermSink(Arc<Mutex<String>>);
+
+impl OutputSink for XtermSink {
+    fn write(&mut self, output: &str) -> io::Result<()> {
+        let mut pending = self.0.lock().unwrap();
+        pending.push_str(output);
+        pending.push('\n');
+        Ok(())
+    }
+}